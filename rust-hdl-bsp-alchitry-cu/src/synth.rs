@@ -52,3 +52,13 @@ pub fn generate_bitstream<U: Block>(mut uut: U, prefix: &str) {
         .unwrap();
     save_stdout(output, &dir, "icepack").unwrap();
 }
+
+rust_hdl::register_board! {
+    name: "alchitry_cu",
+    constraints_file: "top.pcf",
+    constraints: |uut| generate_pcf(&uut),
+    build: |uut, prefix| {
+        generate_bitstream(uut, prefix);
+        Ok(())
+    },
+}
@@ -277,3 +277,13 @@ pub fn synth_obj<U: Block>(uut: U, dir: &str) {
     yosys_validate(dir, &vlog).unwrap();
     generate_bitstream_xem_6010(uut, dir, Default::default());
 }
+
+rust_hdl::register_board! {
+    name: "ok_xem6010",
+    constraints_file: "top.ucf",
+    constraints: |uut| generate_ucf(&uut),
+    build: |uut, prefix| {
+        generate_bitstream_xem_6010(uut, prefix, Default::default());
+        Ok(())
+    },
+}
@@ -284,3 +284,13 @@ pub fn synth_obj<U: Block>(uut: U, dir: &str) {
     yosys_validate(dir, &vlog).unwrap();
     generate_bitstream_xem_7010(uut, dir, Default::default());
 }
+
+rust_hdl::register_board! {
+    name: "ok_xem7010",
+    constraints_file: "top.xdc",
+    constraints: |uut| generate_xdc(&uut),
+    build: |uut, prefix| {
+        generate_bitstream_xem_7010(uut, prefix, Default::default());
+        Ok(())
+    },
+}
@@ -0,0 +1,298 @@
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::{Expr, Member, Result, Stmt};
+
+use crate::common::{DFFSetupArgs, TS};
+
+// Instrument the simulated version of an `#[hdl_gen]` function so that it
+// tracks unknown (X) values for the four-state simulation mode.  Each assignment
+// to a signal marks the bits it writes as unknown if any bit read on the right hand
+// side (or in an enclosing condition) is unknown.  Copies of a signal copy its unknown
+// bits.  Conditions that read unknown values are reported.
+pub(crate) fn four_state_instrument(item: &syn::ItemFn) -> Result<TS> {
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let sig = &item.sig;
+    let stmts = instrument_stmts(&item.block.stmts)?;
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            #[allow(unused_variables)]
+            let __rhdl_x_cond = false;
+            #stmts
+        }
+    })
+}
+
+struct UnknownReads(Vec<TS>);
+
+// Given `<signal>.val()`, return `<signal>`
+fn val_source(expr: &Expr) -> Option<&Expr> {
+    match expr {
+        Expr::MethodCall(call) if call.method == "val" && call.args.is_empty() => {
+            Some(&call.receiver)
+        }
+        _ => None,
+    }
+}
+
+// The width `M` of `get_bits::<M>`
+fn bits_width(call: &syn::ExprMethodCall) -> Option<TS> {
+    match call.turbofish.as_ref()?.args.first()? {
+        syn::GenericMethodArgument::Type(t) => Some(quote!(#t)),
+        syn::GenericMethodArgument::Const(e) => Some(quote!(#e)),
+    }
+}
+
+// The offset and width of the bits of a struct valued `value` that hold `field`
+fn field_bits(value: TS, field: &Member) -> Option<(TS, TS)> {
+    match field {
+        Member::Named(name) => {
+            let offset = format_ident!("get_my_offset_{}", name);
+            let width = format_ident!("get_my_width_{}", name);
+            Some((quote!(#value.#offset()), quote!(#value.#width())))
+        }
+        Member::Unnamed(_) => None,
+    }
+}
+
+impl<'ast> Visit<'ast> for UnknownReads {
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        if let Some(source) = val_source(&node.receiver) {
+            // Reading some bits of a signal only depends on those bits
+            let width = match node.method.to_string().as_str() {
+                "get_bit" => Some(quote!(1)),
+                "get_bits" => bits_width(node),
+                _ => None,
+            };
+            if let (Some(width), Some(index)) = (width, node.args.first()) {
+                self.0
+                    .push(quote!((#source).unknown_mask().get_bits(#index, #width)));
+                node.args.iter().for_each(|x| self.visit_expr(x));
+                return;
+            }
+        }
+        if node.method == "val" && node.args.is_empty() {
+            let receiver = &node.receiver;
+            self.0.push(quote!((#receiver).is_unknown()));
+        }
+        syn::visit::visit_expr_method_call(self, node);
+    }
+    fn visit_expr_field(&mut self, node: &'ast syn::ExprField) {
+        // As does reading a field of a struct
+        if let Some(source) = val_source(&node.base) {
+            let base = &node.base;
+            if let Some((offset, width)) = field_bits(quote!((#base)), &node.member) {
+                self.0
+                    .push(quote!((#source).unknown_mask().get_bits(#offset, #width)));
+                return;
+            }
+        }
+        syn::visit::visit_expr_field(self, node);
+    }
+}
+
+fn unknown_reads<'a>(exprs: impl IntoIterator<Item = &'a Expr>) -> TS {
+    let mut reads = UnknownReads(vec![]);
+    for expr in exprs {
+        reads.visit_expr(expr);
+    }
+    let reads = reads.0;
+    quote!(__rhdl_x_cond #(|| #reads)*)
+}
+
+// Given `<signal>.next` or `<signal>.next.field`, return `<signal>` and the field
+// (if the write covers only part of the signal).
+fn next_target(expr: &Expr) -> Option<(&Expr, Option<&Member>)> {
+    if let Expr::Field(field) = expr {
+        if let Member::Named(name) = &field.member {
+            if name == "next" {
+                return Some((&field.base, None));
+            }
+        }
+        if let Expr::Field(inner) = field.base.as_ref() {
+            if let Member::Named(name) = &inner.member {
+                if name == "next" {
+                    return Some((&inner.base, Some(&field.member)));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn mark_target(target: &Expr, reads: TS) -> TS {
+    quote!((#target).set_next_unknown(#reads);)
+}
+
+// `<signal>.next.field = <value>` only changes the unknown bits of the field
+fn mark_target_field(target: &Expr, field: &Member, reads: TS) -> TS {
+    match field_bits(quote!((#target).next), field) {
+        Some((offset, width)) => {
+            quote!((#target).set_next_unknown_bits(#offset, #width, #reads);)
+        }
+        None => quote!((#target).set_next_unknown((#target).is_next_unknown() || #reads);),
+    }
+}
+
+// `<signal>.next = <other>.val()` copies the unknown bits of the other signal, and
+// `<signal>.next = <other>.val().replace_bit(<index>, <value>)` changes one of them
+fn copy_target(target: &Expr, value: &Expr) -> Option<TS> {
+    if let Some(source) = val_source(value) {
+        return Some(quote! {
+            if __rhdl_x_cond {
+                (#target).set_next_unknown(true);
+            } else {
+                (#target).set_next_unknown_mask((#source).unknown_mask());
+            }
+        });
+    }
+    match value {
+        Expr::MethodCall(call) if call.method == "replace_bit" && call.args.len() == 2 => {
+            let source = val_source(&call.receiver)?;
+            let index = &call.args[0];
+            let index_reads = unknown_reads(std::iter::once(index));
+            let bit_reads = unknown_reads(std::iter::once(&call.args[1]));
+            Some(quote! {
+                if #index_reads {
+                    (#target).set_next_unknown(true);
+                } else {
+                    let mut __rhdl_x_mask = (#source).unknown_mask();
+                    __rhdl_x_mask.set_bits(#index, 1, #bit_reads);
+                    (#target).set_next_unknown_mask(__rhdl_x_mask);
+                }
+            })
+        }
+        _ => None,
+    }
+}
+
+fn instrument_stmts(stmts: &[Stmt]) -> Result<TS> {
+    let mut ret = vec![];
+    for stmt in stmts {
+        ret.push(match stmt {
+            Stmt::Expr(e) | Stmt::Semi(e, _) => instrument_expr(e)?,
+            _ => quote!(#stmt),
+        });
+    }
+    Ok(quote!(#(#ret)*))
+}
+
+fn instrument_block(block: &syn::Block) -> Result<TS> {
+    let stmts = instrument_stmts(&block.stmts)?;
+    Ok(quote!({ #stmts }))
+}
+
+fn instrument_condition(cond: &Expr) -> TS {
+    let reads = unknown_reads(std::iter::once(cond));
+    let text = quote!(#cond).to_string();
+    let location = quote_spanned!(cond.span()=> concat!(file!(), ":", line!()));
+    quote! {
+        let __rhdl_x_test = #reads;
+        if __rhdl_x_test && !__rhdl_x_cond {
+            four_state::report_unknown_condition(#location, #text);
+        }
+        let __rhdl_x_cond = __rhdl_x_test;
+    }
+}
+
+fn instrument_if(expr: &syn::ExprIf) -> Result<TS> {
+    let cond = &expr.cond;
+    let check = instrument_condition(cond);
+    let then_branch = instrument_block(&expr.then_branch)?;
+    let else_branch = match &expr.else_branch {
+        None => quote!(),
+        Some((_, e)) => match e.as_ref() {
+            Expr::Block(block) => {
+                let block = instrument_block(&block.block)?;
+                quote!(else #block)
+            }
+            Expr::If(inner) => {
+                let inner = instrument_if(inner)?;
+                quote!(else { #inner })
+            }
+            _ => quote!(else #e),
+        },
+    };
+    Ok(quote!({
+        #check
+        if #cond #then_branch #else_branch
+    }))
+}
+
+fn instrument_match(expr: &syn::ExprMatch) -> Result<TS> {
+    let test = &expr.expr;
+    let check = instrument_condition(test);
+    let mut arms = vec![];
+    for arm in &expr.arms {
+        let attrs = &arm.attrs;
+        let pat = &arm.pat;
+        let guard = arm.guard.as_ref().map(|(_, g)| quote!(if #g));
+        let body = match arm.body.as_ref() {
+            Expr::Block(block) => instrument_block(&block.block)?,
+            body => {
+                let body = instrument_expr(body)?;
+                quote!({ #body })
+            }
+        };
+        arms.push(quote!(#(#attrs)* #pat #guard => #body));
+    }
+    Ok(quote!({
+        #check
+        match #test {
+            #(#arms),*
+        }
+    }))
+}
+
+fn instrument_expr(expr: &Expr) -> Result<TS> {
+    match expr {
+        Expr::Assign(assign) => {
+            let right = assign.right.as_ref();
+            let mark = match next_target(&assign.left) {
+                Some((target, None)) => copy_target(target, right)
+                    .unwrap_or_else(|| mark_target(target, unknown_reads(std::iter::once(right)))),
+                Some((target, Some(field))) => {
+                    mark_target_field(target, field, unknown_reads(std::iter::once(right)))
+                }
+                None => quote!(),
+            };
+            Ok(quote!(#expr; #mark))
+        }
+        Expr::MethodCall(call) => {
+            // Calls like `self.x.next.set_value_field(..)` update part of the signal
+            if let Some((target, _)) = next_target(&call.receiver) {
+                let reads = unknown_reads(&call.args);
+                let mark =
+                    quote!((#target).set_next_unknown((#target).is_next_unknown() || #reads););
+                Ok(quote!(#expr; #mark))
+            } else {
+                Ok(quote!(#expr;))
+            }
+        }
+        Expr::If(x) => instrument_if(x),
+        Expr::Match(x) => instrument_match(x),
+        Expr::ForLoop(x) => {
+            let pat = &x.pat;
+            let range = &x.expr;
+            let body = instrument_block(&x.body)?;
+            Ok(quote!(for #pat in #range #body))
+        }
+        Expr::Macro(x) => {
+            let ident = &x.mac.path;
+            if quote!(#ident).to_string() == "dff_setup" {
+                let args: DFFSetupArgs = x.mac.parse_body()?;
+                let me = &args.me;
+                let dffs = &args.dffs;
+                Ok(quote! {
+                    #expr;
+                    #(#me.#dffs.d.set_next_unknown_mask(#me.#dffs.q.unknown_mask());)*
+                })
+            } else {
+                Ok(quote!(#expr;))
+            }
+        }
+        _ => Ok(quote!(#expr;)),
+    }
+}
@@ -0,0 +1,94 @@
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{FnArg, GenericParam, Pat, Result, ReturnType};
+
+use crate::common::TS;
+use crate::hdl_gen::hdl_fn_process;
+
+// An `#[hdl_fn]` is left as is for simulation, and gets a companion `<name>_hdl` function
+// that builds the Verilog function for it.  Const generic arguments are part of the name
+// of the Verilog function, since each instantiation has different widths.
+pub(crate) fn hdl_fn_expand(item: syn::ItemFn) -> Result<TS> {
+    let signature = &item.sig;
+    let mut consts = vec![];
+    for param in &signature.generics.params {
+        match param {
+            GenericParam::Const(x) => consts.push(x.ident.clone()),
+            _ => {
+                return Err(syn::Error::new(
+                    param.span(),
+                    "hdl_fn functions can only have const generic parameters",
+                ))
+            }
+        }
+    }
+    let mut arg_names = vec![];
+    let mut arg_types = vec![];
+    for arg in &signature.inputs {
+        match arg {
+            FnArg::Typed(x) => match x.pat.as_ref() {
+                Pat::Ident(ident) if ident.mutability.is_none() => {
+                    arg_names.push(ident.ident.clone());
+                    let ty = &x.ty;
+                    arg_types.push(quote!(#ty));
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        x.pat.span(),
+                        "hdl_fn arguments must be simple (immutable) bindings",
+                    ))
+                }
+            },
+            FnArg::Receiver(_) => {
+                return Err(syn::Error::new(
+                    arg.span(),
+                    "hdl_fn must be a free function (without self)",
+                ))
+            }
+        }
+    }
+    let result_type = match &signature.output {
+        ReturnType::Type(_, ty) => ty,
+        ReturnType::Default => {
+            return Err(syn::Error::new(
+                signature.span(),
+                "hdl_fn functions must return a value",
+            ))
+        }
+    };
+    let (block, locals) = hdl_fn_process(
+        &item.block,
+        &quote!(ast::VerilogExpression::Signal(name.clone())),
+    )?;
+    if let Some((local, _)) = locals.iter().find(|(x, _)| arg_names.contains(x)) {
+        return Err(syn::Error::new(
+            local.span(),
+            "Locals in an hdl_fn cannot shadow its arguments",
+        ));
+    }
+    let local_names = locals.iter().map(|x| x.0.to_string());
+    let local_types = locals.iter().map(|x| &x.1);
+    let arg_names = arg_names.iter().map(|x| x.to_string());
+    let vis = &item.vis;
+    let name = signature.ident.to_string();
+    let hdl_name = format_ident!("{}_hdl", signature.ident);
+    let generics = &signature.generics;
+    let where_clause = &generics.where_clause;
+    Ok(quote! {
+        #item
+
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        #[automatically_derived]
+        #vis fn #hdl_name #generics () -> ast::VerilogFunction #where_clause {
+            let name = [#name.to_string(), #(format!("${}", #consts)),*].concat();
+            ast::VerilogFunction {
+                result: ast::VerilogFunctionVariable::new::<#result_type>(&name),
+                args: vec![#(ast::VerilogFunctionVariable::new::<#arg_types>(#arg_names)),*],
+                locals: vec![#(ast::VerilogFunctionVariable::new::<#local_types>(#local_names)),*],
+                block: #block,
+                name,
+            }
+        }
+    })
+}
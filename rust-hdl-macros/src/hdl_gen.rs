@@ -1,13 +1,24 @@
+use std::cell::RefCell;
 use std::ops::Index;
 
 use quote::format_ident;
 use quote::quote;
+use quote::quote_spanned;
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{BinOp, Expr, Pat, PathSegment, Result, Stmt, UnOp};
+use syn::{BinOp, Expr, Lit, Pat, PathSegment, Result, Stmt, Token, UnOp};
 
 use crate::common;
 use crate::common::{squash, DFFSetupArgs, TS};
 
+thread_local! {
+    // The variables bound by the patterns of the enclosing match arms (for enums with
+    // fields), along with the slices of the matched value they refer to
+    static MATCH_BINDINGS: RefCell<Vec<(String, TS)>> = const { RefCell::new(vec![]) };
+    // The local variables (and their types) declared so far in the body of an `#[hdl_fn]`
+    static FUNCTION_LOCALS: RefCell<Vec<(syn::Ident, TS)>> = const { RefCell::new(vec![]) };
+}
+
 pub(crate) fn hdl_gen_process(item: syn::ItemFn) -> Result<TS> {
     let signature = &item.sig;
     if signature.inputs.len() != 1 {
@@ -27,6 +38,7 @@ pub(crate) fn hdl_gen_process(item: syn::ItemFn) -> Result<TS> {
 fn hdl_block(block: &syn::Block) -> Result<TS> {
     let mut stmt = vec![];
     for statement in &block.stmts {
+        stmt.push(hdl_location(statement.span()));
         stmt.push(hdl_statement(statement)?);
     }
     Ok(quote! {
@@ -38,6 +50,13 @@ fn hdl_block(block: &syn::Block) -> Result<TS> {
     })
 }
 
+// file!() and line!() take their location from the span, i.e., the statement itself
+fn hdl_location(span: proc_macro2::Span) -> TS {
+    quote_spanned!(span=> ast::VerilogStatement::Location(
+        ast::SourceLocation::new(file!(), line!(), env!("CARGO_MANIFEST_DIR"))
+    ))
+}
+
 fn hdl_statement(statement: &syn::Stmt) -> Result<TS> {
     match statement {
         Stmt::Expr(e) => hdl_inner_statement(e),
@@ -141,18 +160,115 @@ fn hdl_non_indexed_assignment(expr: &syn::ExprAssign) -> Result<TS> {
             };
         }
         target = hdl_map_field_assign(p)?;
+    } else if let Some(local) = hdl_function_local(&expr.left) {
+        target = quote!(ast::VerilogExpression::Signal(#local.to_string()));
     } else {
         return Err(syn::Error::new(
             expr.span(),
             "unsupported assignment type for HDL",
         ));
     }
+    if let Some(assignment) = hdl_variant_assignment(&target, expr.right.as_ref())? {
+        return Ok(assignment);
+    }
     let value = hdl_compute(expr.right.as_ref())?;
     Ok(quote!({
        ast::VerilogStatement::Assignment(#target, #value)
     }))
 }
 
+// Assigning a variant of an enum with fields (e.g., `Cmd::Read { addr: x }` or `Cmd::Fill(x)`)
+// sets the tag (clearing the rest of the signal), and then each of the fields in turn.
+fn hdl_variant_assignment(target: &TS, value: &syn::Expr) -> Result<Option<TS>> {
+    let (path, fields) = match value {
+        Expr::Struct(x) if x.path.segments.len() > 1 => {
+            if x.rest.is_some() {
+                return Err(syn::Error::new(
+                    x.span(),
+                    "Functional update syntax is not supported in HDL",
+                ));
+            }
+            let fields = x
+                .fields
+                .iter()
+                .map(|f| (member_name(&f.member), &f.expr))
+                .collect::<Vec<_>>();
+            (&x.path, fields)
+        }
+        Expr::Call(x) => match x.func.as_ref() {
+            Expr::Path(p) if is_variant_path(&p.path) => {
+                let fields = x
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(ndx, arg)| (ndx.to_string(), arg))
+                    .collect::<Vec<_>>();
+                (&p.path, fields)
+            }
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let ty = variant_type(path);
+    let variant = &path.segments.last().unwrap().ident;
+    let tag = common::fixup_ident(quote!(#path).to_string());
+    let mut slices = vec![];
+    for (field, expr) in fields {
+        let get_width_name = format_ident!("get_my_width_{}_{}", variant, field);
+        let get_offset_name = format_ident!("get_my_offset_{}_{}", variant, field);
+        let value = hdl_compute(expr)?;
+        slices.push(quote!(ast::VerilogStatement::SliceAssignment {
+            base: #target,
+            width: #ty::#get_width_name(),
+            offset: ast::VerilogExpression::Literal(#ty::#get_offset_name().into()),
+            replacement: #value,
+        }));
+    }
+    Ok(Some(quote!({
+        ast::VerilogStatement::Macro(vec![
+            ast::VerilogStatement::Assignment(#target, ast::VerilogExpression::Signal(#tag.to_string())),
+            #(#slices),*
+        ])
+    })))
+}
+
+// Assignments to bare identifiers are only allowed for the locals of an `#[hdl_fn]`
+fn hdl_function_local(expr: &syn::Expr) -> Option<String> {
+    if let Expr::Path(p) = expr {
+        let ident = p.path.get_ident()?;
+        if FUNCTION_LOCALS.with(|x| x.borrow().iter().any(|(local, _)| local == ident)) {
+            return Some(ident.to_string());
+        }
+    }
+    None
+}
+
+fn member_name(member: &syn::Member) -> String {
+    match member {
+        syn::Member::Named(x) => x.to_string(),
+        syn::Member::Unnamed(x) => x.index.to_string(),
+    }
+}
+
+// Tuple variants are constructed like function calls, so we rely on the convention that
+// variants (unlike functions) are capitalized
+fn is_variant_path(path: &syn::Path) -> bool {
+    path.segments.len() > 1
+        && path
+            .segments
+            .last()
+            .unwrap()
+            .ident
+            .to_string()
+            .starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+// The type of an enum variant, e.g., `Cmd` for `Cmd::Read`
+fn variant_type(path: &syn::Path) -> TS {
+    let segments = path.segments.iter().take(path.segments.len() - 1);
+    quote!(#(#segments)::*)
+}
+
 fn hdl_map_field_assign(expr: &syn::ExprField) -> Result<TS> {
     let expr_expanded = common::fixup_ident(quote!(#expr).to_string());
     if expr_expanded.ends_with("$val") {
@@ -195,6 +311,19 @@ fn hdl_map_field(expr: &syn::ExprField) -> Result<TS> {
 }
 
 fn hdl_map_path(expr: &syn::ExprPath) -> Result<TS> {
+    if let Some(ident) = expr.path.get_ident() {
+        let name = ident.to_string();
+        let binding = MATCH_BINDINGS.with(|x| {
+            x.borrow()
+                .iter()
+                .rev()
+                .find(|(binding, _)| binding == &name)
+                .map(|(_, slice)| slice.clone())
+        });
+        if let Some(slice) = binding {
+            return Ok(slice);
+        }
+    }
     let expr_expanded = common::fixup_ident(quote!(#expr).to_string());
     if expr_expanded.ends_with("$next") {
         return Err(syn::Error::new(
@@ -209,17 +338,24 @@ fn hdl_map_path(expr: &syn::ExprPath) -> Result<TS> {
 }
 
 fn hdl_conditional(conditions: &syn::ExprIf) -> Result<TS> {
+    hdl_conditional_with(conditions, &hdl_block)
+}
+
+fn hdl_conditional_with(
+    conditions: &syn::ExprIf,
+    branch: &dyn Fn(&syn::Block) -> Result<TS>,
+) -> Result<TS> {
     let test_condition = hdl_compute(&conditions.cond)?;
-    let then_branch = hdl_block(&conditions.then_branch)?;
+    let then_branch = branch(&conditions.then_branch)?;
     let mut else_branch = quote!({ ast::VerilogBlockOrConditional::None });
     if let Some((_, e_branch)) = &conditions.else_branch {
         match e_branch.as_ref() {
             Expr::Block(block) => {
-                let else_branch_block = hdl_block(&block.block)?;
+                let else_branch_block = branch(&block.block)?;
                 else_branch = quote!({ast::VerilogBlockOrConditional::Block(#else_branch_block)});
             }
             Expr::If(cond) => {
-                let else_branch_block = hdl_conditional(cond)?;
+                let else_branch_block = hdl_conditional_with(cond, branch)?;
                 else_branch = quote!({ast::VerilogBlockOrConditional::Conditional(Box::new(#else_branch_block))});
             }
             _ => {
@@ -236,12 +372,39 @@ fn hdl_conditional(conditions: &syn::ExprIf) -> Result<TS> {
 }
 
 fn hdl_match(m: &syn::ExprMatch) -> Result<TS> {
-    let test = hdl_compute(m.expr.as_ref())?;
+    hdl_match_with(m, &hdl_body)
+}
+
+fn hdl_match_with(m: &syn::ExprMatch, arm_body: &dyn Fn(&syn::Expr) -> Result<TS>) -> Result<TS> {
+    let mut test = hdl_compute(m.expr.as_ref())?;
+    // A match with struct or tuple variant patterns is on an enum with fields, so it
+    // switches on the tag, and the fields are bound to slices of the matched value
+    let variant_type = m.arms.iter().find_map(|arm| match &arm.pat {
+        Pat::Struct(x) => Some(variant_type(&x.path)),
+        Pat::TupleStruct(x) => Some(variant_type(&x.path)),
+        _ => None,
+    });
     let mut condition = vec![];
     let mut blocks = vec![];
     for arm in &m.arms {
         condition.push(hdl_pattern(&arm.pat)?);
-        blocks.push(hdl_body(&arm.body)?);
+        let bindings = hdl_pattern_bindings(&arm.pat, &test)?;
+        let count = bindings.len();
+        MATCH_BINDINGS.with(|x| x.borrow_mut().extend(bindings));
+        let block = arm_body(&arm.body);
+        MATCH_BINDINGS.with(|x| {
+            let mut x = x.borrow_mut();
+            let len = x.len();
+            x.truncate(len - count);
+        });
+        blocks.push(block?);
+    }
+    if let Some(ty) = variant_type {
+        test = quote!(ast::VerilogExpression::Slice(
+            Box::new(#test),
+            #ty::get_my_tag_width(),
+            Box::new(ast::VerilogExpression::Literal(0_usize.into()))
+        ));
     }
     /*    if condition.len() == 0 || !condition.last().unwrap().eq("default") {
         return Err(syn::Error::new(
@@ -271,6 +434,7 @@ fn hdl_compute(m: &syn::Expr) -> Result<TS> {
         Expr::Unary(unop) => hdl_unop(unop),
         Expr::Call(call) => hdl_call(call),
         Expr::MethodCall(method) => hdl_method(method),
+        Expr::Macro(mac) => hdl_concat(mac),
         Expr::Lit(lit) => hdl_literal(lit),
         Expr::Index(_ndx) => {
             let ndx_expanded = common::fixup_ident(quote!(#m).to_string());
@@ -380,10 +544,19 @@ fn hdl_call(call: &syn::ExprCall) -> Result<TS> {
         || funcname.starts_with("Bits")
     {
         hdl_compute(&call.args[0])
+    } else if funcname.starts_with("hex_bits") {
+        // The value is computed when the Verilog is generated
+        Ok(quote!({
+            ast::VerilogExpression::Literal(#call.into())
+        }))
+    } else if funcname.starts_with("replicate") {
+        hdl_replicate(call)
     } else if squash(&funcname).contains("::join") {
         hdl_join_or_link(call, "join")
     } else if squash(&funcname).contains("::link") {
         hdl_join_or_link(call, "link")
+    } else if let Expr::Path(p) = call.func.as_ref() {
+        hdl_fn_call(call, &p.path)
     } else {
         Err(syn::Error::new(
             call.span(),
@@ -395,6 +568,182 @@ fn hdl_call(call: &syn::ExprCall) -> Result<TS> {
     }
 }
 
+// `replicate::<M, N>(x)` becomes the Verilog replication `{M/N{x}}`
+fn hdl_replicate(call: &syn::ExprCall) -> Result<TS> {
+    let widths = match call.func.as_ref() {
+        Expr::Path(p) => match &p.path.segments.last().unwrap().arguments {
+            syn::PathArguments::AngleBracketed(a) => a.args.iter().collect::<Vec<_>>(),
+            _ => vec![],
+        },
+        _ => vec![],
+    };
+    if widths.len() != 2 || call.args.len() != 1 {
+        return Err(syn::Error::new(
+            call.span(),
+            "replicate needs both widths spelled out (e.g., replicate::<16, 4>(x))",
+        ));
+    }
+    let (m, n) = (widths[0], widths[1]);
+    let arg = hdl_sized_operand(&call.args[0])?;
+    Ok(quote!({
+        ast::VerilogExpression::Replicate(#m / #n, Box::new(#arg))
+    }))
+}
+
+// A call to an `#[hdl_fn]`, e.g., `crc_step(x, y)` or `gray::<8>(x)`, refers to the
+// companion function (`crc_step_hdl`) generated by the attribute for the definition.
+// The fixed point conversions work the same way, except that their rounding and overflow
+// arguments are passed to the companion function, rather than to the Verilog function.
+fn hdl_fn_call(call: &syn::ExprCall, path: &syn::Path) -> Result<TS> {
+    if is_variant_path(path) {
+        return Err(syn::Error::new(
+            call.span(),
+            "Enum variants with fields can only be assigned directly to a signal in HDL",
+        ));
+    }
+    let mut path = path.clone();
+    let last = path.segments.last_mut().unwrap();
+    let operands = match last.ident.to_string().as_str() {
+        "fixed_cast" | "ufixed_cast" => 1,
+        "fixed_mul" | "ufixed_mul" => 2,
+        _ => call.args.len(),
+    };
+    if call.args.len() < operands {
+        return Err(syn::Error::new(
+            call.span(),
+            "Missing arguments for the fixed point conversion",
+        ));
+    }
+    last.ident = format_ident!("{}_hdl", last.ident);
+    let args = call
+        .args
+        .iter()
+        .take(operands)
+        .map(hdl_compute)
+        .collect::<Result<Vec<_>>>()?;
+    let options = call.args.iter().skip(operands);
+    Ok(quote!({
+        ast::VerilogExpression::Call(Box::new(ast::VerilogFunctionCall {
+            function: #path(#(#options),*),
+            args: vec![#(#args),*],
+        }))
+    }))
+}
+
+// Translates the body of an `#[hdl_fn]` into the body of a Verilog function, which assigns
+// its value to `target`.  Returns the body along with the locals declared in it.
+pub(crate) fn hdl_fn_process(
+    block: &syn::Block,
+    target: &TS,
+) -> Result<(TS, Vec<(syn::Ident, TS)>)> {
+    FUNCTION_LOCALS.with(|x| x.borrow_mut().clear());
+    let body = hdl_fn_block(block, target);
+    let locals = FUNCTION_LOCALS.with(|x| x.take());
+    Ok((body?, locals))
+}
+
+fn hdl_fn_block(block: &syn::Block, target: &TS) -> Result<TS> {
+    let mut stmt = vec![];
+    let mut has_value = false;
+    for (ndx, statement) in block.stmts.iter().enumerate() {
+        match statement {
+            Stmt::Local(local) => stmt.extend(hdl_fn_local(local)?),
+            Stmt::Expr(e) if ndx + 1 == block.stmts.len() => {
+                stmt.push(hdl_fn_result(e, target)?);
+                has_value = true;
+            }
+            _ => stmt.push(hdl_statement(statement)?),
+        }
+    }
+    if !has_value {
+        return Err(syn::Error::new(
+            block.span(),
+            "The blocks of an hdl_fn must end with the value they produce",
+        ));
+    }
+    Ok(quote! {
+    {
+        let mut ret = vec![];
+        #(ret.push(#stmt));*;
+        ret
+    }
+    })
+}
+
+// A local is declared as a `reg` of the Verilog function, so it needs an explicit type
+fn hdl_fn_local(local: &syn::Local) -> Result<Option<TS>> {
+    let (ident, ty) = match &local.pat {
+        Pat::Type(x) => match x.pat.as_ref() {
+            Pat::Ident(ident) => (ident.ident.clone(), &x.ty),
+            _ => {
+                return Err(syn::Error::new(
+                    x.pat.span(),
+                    "Only simple bindings can be declared in an hdl_fn",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                local.span(),
+                "Locals in an hdl_fn must have a type (e.g., let x: Bits<8> = ...)",
+            ))
+        }
+    };
+    let exists = FUNCTION_LOCALS.with(|x| x.borrow().iter().any(|(local, _)| local == &ident));
+    if exists {
+        return Err(syn::Error::new(
+            ident.span(),
+            "Locals in an hdl_fn cannot be shadowed",
+        ));
+    }
+    FUNCTION_LOCALS.with(|x| x.borrow_mut().push((ident.clone(), quote!(#ty))));
+    match &local.init {
+        Some((_, init)) => {
+            let name = ident.to_string();
+            let target = quote!(ast::VerilogExpression::Signal(#name.to_string()));
+            Ok(Some(hdl_fn_result(init, &target)?))
+        }
+        None => Ok(None),
+    }
+}
+
+// Assigns the value of an expression to `target`.  Conditionals and matches
+// assign in each of their branches.
+fn hdl_fn_result(expr: &syn::Expr, target: &TS) -> Result<TS> {
+    let branch = |block: &syn::Block| hdl_fn_block(block, target);
+    match expr {
+        Expr::If(x) => {
+            if x.else_branch.is_none() {
+                return Err(syn::Error::new(
+                    x.span(),
+                    "An if used as a value in an hdl_fn needs an else branch",
+                ));
+            }
+            hdl_conditional_with(x, &branch)
+        }
+        Expr::Match(x) => hdl_match_with(x, &|body| match body {
+            Expr::Block(b) => hdl_fn_block(&b.block, target),
+            _ => {
+                let statement = hdl_fn_result(body, target)?;
+                Ok(quote!({ vec![#statement] }))
+            }
+        }),
+        Expr::Block(x) => {
+            let block = hdl_fn_block(&x.block, target)?;
+            Ok(quote!(ast::VerilogStatement::Macro(#block)))
+        }
+        _ => {
+            if let Some(assignment) = hdl_variant_assignment(target, expr)? {
+                return Ok(assignment);
+            }
+            let value = hdl_compute(expr)?;
+            Ok(quote!({
+               ast::VerilogStatement::Assignment(#target, #value)
+            }))
+        }
+    }
+}
+
 fn hdl_method_set(method: &syn::ExprMethodCall) -> Result<TS> {
     let method_name = method.method.to_string();
     let field_set_match = regex::Regex::new(r"set_value_([a-zA-Z][a-zA-Z0-9_]*)").unwrap();
@@ -534,6 +883,19 @@ fn hdl_method(method: &syn::ExprMethodCall) -> Result<TS> {
                 Box::new(#target))
             }))
         }
+        "ashr" => {
+            let target = hdl_compute(method.receiver.as_ref())?;
+            if method.args.len() != 1 {
+                return Err(syn::Error::new(
+                    method.span(),
+                    "ashr needs one argument (the shift amount)",
+                ));
+            }
+            let amount = hdl_compute(&method.args[0])?;
+            Ok(quote!({
+                ast::VerilogExpression::Binary(Box::new(#target), ast::VerilogOp::AShr, Box::new(#amount))
+            }))
+        }
         "val" | "into" | "index" | "to_bits" => {
             let receiver = method.receiver.as_ref();
             hdl_compute(receiver)
@@ -559,6 +921,14 @@ fn hdl_pattern(pat: &Pat) -> Result<String> {
         Pat::Ident(ident) => Ok(ident.ident.to_string()),
         Pat::Lit(lit) => Ok(quote!(#lit).to_string()),
         Pat::Path(pat) => Ok(common::fixup_ident(quote!(#pat).to_string())),
+        Pat::Struct(pat) => {
+            let path = &pat.path;
+            Ok(common::fixup_ident(quote!(#path).to_string()))
+        }
+        Pat::TupleStruct(pat) => {
+            let path = &pat.path;
+            Ok(common::fixup_ident(quote!(#path).to_string()))
+        }
         Pat::Wild(_pat) => Ok("default".to_string()),
         _ => Err(syn::Error::new(
             pat.span(),
@@ -570,29 +940,149 @@ fn hdl_pattern(pat: &Pat) -> Result<String> {
     }
 }
 
+// The variables bound by a struct or tuple variant pattern, e.g., `Cmd::Write { addr, data: d }`
+// binds `addr` and `d` to the corresponding slices of the matched value.
+fn hdl_pattern_bindings(pat: &Pat, test: &TS) -> Result<Vec<(String, TS)>> {
+    let (path, fields) = match pat {
+        Pat::Struct(x) => (
+            &x.path,
+            x.fields
+                .iter()
+                .map(|f| (member_name(&f.member), f.pat.as_ref()))
+                .collect::<Vec<_>>(),
+        ),
+        Pat::TupleStruct(x) => {
+            let elems = x.pat.elems.iter().collect::<Vec<_>>();
+            if let Some(ndx) = elems.iter().position(|x| matches!(x, Pat::Rest(_))) {
+                if ndx + 1 != elems.len() {
+                    return Err(syn::Error::new(
+                        x.span(),
+                        "In HDL, `..` must be the last element of a tuple variant pattern",
+                    ));
+                }
+            }
+            (
+                &x.path,
+                elems
+                    .into_iter()
+                    .enumerate()
+                    .map(|(ndx, pat)| (ndx.to_string(), pat))
+                    .collect(),
+            )
+        }
+        _ => return Ok(vec![]),
+    };
+    let ty = variant_type(path);
+    let variant = &path.segments.last().unwrap().ident;
+    let mut ret = vec![];
+    for (field, pat) in fields {
+        match pat {
+            Pat::Ident(ident) => {
+                let get_width_name = format_ident!("get_my_width_{}_{}", variant, field);
+                let get_offset_name = format_ident!("get_my_offset_{}_{}", variant, field);
+                ret.push((
+                    ident.ident.to_string(),
+                    quote!(ast::VerilogExpression::Slice(
+                        Box::new(#test),
+                        #ty::#get_width_name(),
+                        Box::new(ast::VerilogExpression::Literal(#ty::#get_offset_name().into()))
+                    )),
+                ));
+            }
+            Pat::Wild(_) | Pat::Rest(_) => {}
+            _ => {
+                return Err(syn::Error::new(
+                    pat.span(),
+                    "Only bindings and `_` are allowed for the fields of a variant in HDL",
+                ))
+            }
+        }
+    }
+    Ok(ret)
+}
+
+fn hdl_concat(x: &syn::ExprMacro) -> Result<TS> {
+    let ident = &x.mac.path;
+    if quote!(#ident).to_string() != "concat_bits" {
+        return Err(syn::Error::new(
+            x.span(),
+            "Only concat_bits! can be used in an expression in HDL code",
+        ));
+    }
+    let args = x
+        .mac
+        .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)?
+        .iter()
+        .map(hdl_sized_operand)
+        .collect::<Result<Vec<_>>>()?;
+    if args.is_empty() {
+        return Err(syn::Error::new(
+            x.span(),
+            "concat_bits! needs at least one argument",
+        ));
+    }
+    Ok(quote!({ ast::VerilogExpression::Concat(vec![#(#args),*]) }))
+}
+
+fn is_integer_literal(x: &Expr) -> bool {
+    match x {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(_), ..
+        }) => true,
+        Expr::Unary(u) => matches!(u.op, UnOp::Neg(_)) && is_integer_literal(&u.expr),
+        Expr::Paren(p) => is_integer_literal(&p.expr),
+        _ => false,
+    }
+}
+
+// The operands of a concatenation or replication need a definite width.  Integer
+// literals are unsized in Verilog (and so 32 bits wide), so `bits::<N>(lit)` and
+// `signed::<N>(lit)` are written as N bit literals, and literals without an explicit
+// width are rejected.
+fn hdl_sized_operand(x: &Expr) -> Result<TS> {
+    let unsized_literal = || {
+        Err(syn::Error::new(
+            x.span(),
+            "Literals in concat_bits! and replicate need an explicit width (e.g., bits::<8>(0x12))",
+        ))
+    };
+    match x {
+        _ if is_integer_literal(x) => unsized_literal(),
+        Expr::MethodCall(m) if is_integer_literal(&m.receiver) => unsized_literal(),
+        Expr::Call(call) if call.args.len() == 1 && is_integer_literal(&call.args[0]) => {
+            let segment = match call.func.as_ref() {
+                Expr::Path(p) => p.path.segments.last().unwrap(),
+                _ => return hdl_compute(x),
+            };
+            let sized = matches!(segment.arguments, syn::PathArguments::AngleBracketed(_));
+            match (segment.ident.to_string().as_str(), sized) {
+                ("bits", true) => Ok(quote!({
+                    ast::VerilogExpression::Literal(#call.into())
+                })),
+                ("signed", true) => Ok(quote!({
+                    ast::VerilogExpression::Literal(#call.inner().into())
+                })),
+                ("bits", false) | ("signed", false) => unsized_literal(),
+                _ => hdl_compute(x),
+            }
+        }
+        _ => hdl_compute(x),
+    }
+}
+
 fn hdl_macro(x: &syn::ExprMacro) -> Result<TS> {
     let ident = &x.mac.path;
     let macro_name = quote!(#ident).to_string();
     let invocation_as_string = quote!(#x).to_string();
     match macro_name.as_ref() {
-        "println" => {
-            let invocation_as_string = invocation_as_string
-                .replace("println ! (\"", "")
-                .replace("\")", "");
-            Ok(quote!(ast::VerilogStatement::Comment(#invocation_as_string.to_string())))
-        }
+        "println" => hdl_display(x),
         "comment" => {
             let invocation_as_string = invocation_as_string
                 .replace("comment ! (\"", "")
                 .replace("\")", "");
             Ok(quote!(ast::VerilogStatement::Comment(#invocation_as_string.to_string())))
         }
-        "assert" => {
-            let invocation_as_string = invocation_as_string
-                .replace("assert ! (\"", "")
-                .replace("\")", "");
-            Ok(quote!(ast::VerilogStatement::Comment(#invocation_as_string.to_string())))
-        }
+        "assert" => hdl_assert(x),
         "dff_setup" => {
             let args: DFFSetupArgs = x.mac.parse_body()?;
             let args_clock = &args.clock;
@@ -644,3 +1134,137 @@ fn hdl_macro(x: &syn::ExprMacro) -> Result<TS> {
         )),
     }
 }
+
+// Convert a Rust format string into a Verilog one for `$display` and friends.  Only
+// the implicitly positional placeholders are supported, with the `Display`, `x` and `b`
+// format traits (and `#` for a radix prefix), since those print [Bits] the same way as
+// `%0d`, `%h` and `%b` do in Verilog (the hex and binary forms with leading zeros).
+fn verilog_format(fmt: &syn::LitStr, num_args: usize) -> Result<String> {
+    let text = fmt.value();
+    let mut ret = String::new();
+    let mut count = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                ret.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                ret.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => {
+                            return Err(syn::Error::new(
+                                fmt.span(),
+                                "Unterminated placeholder in format string",
+                            ))
+                        }
+                    }
+                }
+                ret += match spec.as_str() {
+                    "" => "%0d",
+                    ":x" => "%h",
+                    ":#x" => "0x%h",
+                    ":b" => "%b",
+                    ":#b" => "0b%b",
+                    ":?" | ":#?" => {
+                        return Err(syn::Error::new(
+                            fmt.span(),
+                            "Debug formatting {:?} is not supported in HDL format strings (use {} or {:x})",
+                        ))
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            fmt.span(),
+                            format!("Unsupported placeholder {{{}}} in HDL format string", spec),
+                        ))
+                    }
+                };
+                count += 1;
+            }
+            '%' => ret += "%%",
+            _ => ret.push(c),
+        }
+    }
+    if count != num_args {
+        return Err(syn::Error::new(
+            fmt.span(),
+            format!(
+                "Format string has {} placeholders, but {} arguments were given",
+                count, num_args
+            ),
+        ));
+    }
+    Ok(ret)
+}
+
+// Translate a format string and its arguments into the `format` and `args` fields
+// of a `VerilogDisplay`.
+fn hdl_format_args(span: &syn::ExprMacro, args: &[Expr]) -> Result<(String, Vec<TS>)> {
+    match args.first() {
+        Some(Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(fmt), ..
+        })) => {
+            let format = verilog_format(fmt, args.len() - 1)?;
+            let args = args[1..]
+                .iter()
+                .map(hdl_compute)
+                .collect::<Result<Vec<_>>>()?;
+            Ok((format, args))
+        }
+        _ => Err(syn::Error::new(
+            span.span(),
+            "Expected a format string literal",
+        )),
+    }
+}
+
+fn hdl_display(x: &syn::ExprMacro) -> Result<TS> {
+    let args = x
+        .mac
+        .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+    let args: Vec<Expr> = args.into_iter().collect();
+    let (format, args) = if args.is_empty() {
+        (String::new(), vec![])
+    } else {
+        hdl_format_args(x, &args)?
+    };
+    Ok(quote!(ast::VerilogStatement::Display(ast::VerilogDisplay {
+        task: ast::VerilogSystemTask::Display,
+        assertion: None,
+        format: #format.to_string(),
+        args: vec![#(#args),*],
+    })))
+}
+
+fn hdl_assert(x: &syn::ExprMacro) -> Result<TS> {
+    let args = x
+        .mac
+        .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+    let args: Vec<Expr> = args.into_iter().collect();
+    let condition = args
+        .first()
+        .ok_or_else(|| syn::Error::new(x.span(), "assert requires a condition"))?;
+    let test = hdl_compute(condition)?;
+    let (format, args) = if args.len() > 1 {
+        hdl_format_args(x, &args[1..])?
+    } else {
+        let text = squash(&quote!(#condition).to_string())
+            .replace("self.", "")
+            .replace('%', "%%");
+        (format!("assertion failed: {}", text), vec![])
+    };
+    Ok(quote!(ast::VerilogStatement::Display(ast::VerilogDisplay {
+        task: ast::VerilogSystemTask::Error,
+        assertion: Some(#test),
+        format: #format.to_string(),
+        args: vec![#(#args),*],
+    })))
+}
@@ -0,0 +1,141 @@
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{Expr, FnArg, GenericArgument, Ident, PathArguments, Result, Token, Type};
+
+use crate::common::TS;
+
+// The `hdl_test` attribute takes a comma separated list of `name = value` arguments
+pub(crate) struct HdlTestArgs {
+    uut: Option<Expr>,
+    clock: Option<Ident>,
+    interval: Option<Expr>,
+    max_time: Option<Expr>,
+    waves: Option<Ident>,
+    yosys: Option<Expr>,
+}
+
+impl Parse for HdlTestArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = HdlTestArgs {
+            uut: None,
+            clock: None,
+            interval: None,
+            max_time: None,
+            waves: None,
+            yosys: None,
+        };
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match name.to_string().as_str() {
+                "uut" => args.uut = Some(input.parse()?),
+                "clock" => args.clock = Some(input.parse()?),
+                "interval" => args.interval = Some(input.parse()?),
+                "max_time" => args.max_time = Some(input.parse()?),
+                "waves" => {
+                    let waves: Ident = input.parse()?;
+                    if !["never", "on_failure", "always"].contains(&waves.to_string().as_str()) {
+                        return Err(syn::Error::new(
+                            waves.span(),
+                            "waves must be one of never, on_failure or always",
+                        ));
+                    }
+                    args.waves = Some(waves)
+                }
+                "yosys" => args.yosys = Some(input.parse()?),
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "Unknown argument to hdl_test (expected uut, clock, interval, max_time, waves or yosys)",
+                    ))
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+// Find the circuit type `T` from the testbench argument `sim: Sim<T>`
+fn get_uut_type(item: &syn::ItemFn) -> Result<TS> {
+    let err = || {
+        syn::Error::new(
+            item.sig.span(),
+            "hdl_test functions must take a single `Sim<T>` argument",
+        )
+    };
+    if item.sig.inputs.len() != 1 {
+        return Err(err());
+    }
+    if let FnArg::Typed(arg) = &item.sig.inputs[0] {
+        if let Type::Path(path) = arg.ty.as_ref() {
+            if let Some(segment) = path.path.segments.last() {
+                if segment.ident == "Sim" {
+                    if let PathArguments::AngleBracketed(generics) = &segment.arguments {
+                        if let Some(GenericArgument::Type(ty)) = generics.args.first() {
+                            return Ok(ty.to_token_stream());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Err(err())
+}
+
+pub(crate) fn hdl_test_expand(args: HdlTestArgs, item: syn::ItemFn) -> Result<TS> {
+    let uut = args.uut.ok_or_else(|| {
+        syn::Error::new(
+            item.sig.ident.span(),
+            "hdl_test requires a `uut = <expr>` argument",
+        )
+    })?;
+    let uut_type = get_uut_type(&item)?;
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let name = &item.sig.ident;
+    let inputs = &item.sig.inputs;
+    let output = &item.sig.output;
+    let block = &item.block;
+    let interval = args.interval.unwrap_or_else(|| syn::parse_quote!(5));
+    let max_time = args
+        .max_time
+        .unwrap_or_else(|| syn::parse_quote!(1_000_000));
+    let yosys = args.yosys.unwrap_or_else(|| syn::parse_quote!(true));
+    let waves = match args.waves.map(|x| x.to_string()).as_deref() {
+        Some("never") => quote!(WaveCapture::Never),
+        Some("always") => quote!(WaveCapture::Always),
+        _ => quote!(WaveCapture::OnFailure),
+    };
+    let clock = match args.clock {
+        Some(clock) => quote! {
+            __rhdl_sim.add_clock(#interval, |x: &mut Box<#uut_type>| x.#clock.next = !x.#clock.val());
+        },
+        None => quote!(),
+    };
+    Ok(quote! {
+        #(#attrs)*
+        #[test]
+        #vis fn #name() {
+            fn __rhdl_testbench(#inputs) #output #block
+            let __rhdl_uut: #uut_type = #uut;
+            let mut __rhdl_sim: Simulation<#uut_type> = Simulation::new();
+            #clock
+            __rhdl_sim.add_testbench(__rhdl_testbench);
+            let __rhdl_test = HdlTest::new(module_path!(), stringify!(#name), option_env!("CARGO_TARGET_TMPDIR"))
+                .yosys(#yosys)
+                .waves(#waves);
+            if let Err(err) = __rhdl_test.run(__rhdl_uut, __rhdl_sim, #max_time) {
+                panic!(
+                    "hdl_test {} failed: {}\nartifacts in {}",
+                    stringify!(#name),
+                    err,
+                    __rhdl_test.artifact_dir().display()
+                );
+            }
+        }
+    })
+}
@@ -1,17 +1,26 @@
 mod common;
 mod connect_gen;
+#[cfg(feature = "four-state")]
+mod four_state;
+mod hdl_fn;
 mod hdl_gen;
+mod hdl_test;
 mod logic_block;
 mod logic_interface;
 mod logic_state;
 mod logic_struct;
+mod logic_union;
 
 use syn::parse_macro_input;
 use syn::DeriveInput;
 
 use crate::common::TS;
 use crate::connect_gen::connect_gen;
+#[cfg(feature = "four-state")]
+use crate::four_state::four_state_instrument;
+use crate::hdl_fn::hdl_fn_expand;
 use crate::hdl_gen::hdl_gen_process;
+use crate::hdl_test::{hdl_test_expand, HdlTestArgs};
 use crate::logic_block::get_impl_for_logic_block;
 use crate::logic_interface::get_impl_for_logic_interface;
 use crate::logic_state::get_logic_state_impls;
@@ -19,7 +28,7 @@ use crate::logic_struct::get_impl_for_logic_struct;
 use proc_macro::TokenStream;
 use quote::quote;
 
-#[proc_macro_derive(LogicBlock)]
+#[proc_macro_derive(LogicBlock, attributes(verilog_attribute, verilog_parameter))]
 pub fn logic_block(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -39,7 +48,7 @@ pub fn logic_interface(input: TokenStream) -> TokenStream {
     }
 }
 
-#[proc_macro_derive(LogicState)]
+#[proc_macro_derive(LogicState, attributes(logic_state))]
 pub fn logic_state(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -61,12 +70,19 @@ pub fn logic_struct(input: TokenStream) -> TokenStream {
 
 #[proc_macro_attribute]
 pub fn hdl_gen(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let orig = TS::from(item.clone());
     let parse = parse_macro_input!(item as syn::ItemFn);
     let connects = match connect_gen(&parse) {
         Err(e) => return e.to_compile_error().into(),
         Ok(t) => t,
     };
+    // Tracking unknown values is only compiled in for the four-state simulation mode
+    #[cfg(feature = "four-state")]
+    let orig = match four_state_instrument(&parse) {
+        Err(e) => return e.to_compile_error().into(),
+        Ok(t) => t,
+    };
+    #[cfg(not(feature = "four-state"))]
+    let orig = quote!(#parse);
     match hdl_gen_process(parse) {
         Err(e) => e.to_compile_error().into(),
         Ok(hdl_code) => TokenStream::from(quote! {
@@ -85,3 +101,22 @@ pub fn hdl_gen(_attr: TokenStream, item: TokenStream) -> TokenStream {
         }),
     }
 }
+
+#[proc_macro_attribute]
+pub fn hdl_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as HdlTestArgs);
+    let parse = parse_macro_input!(item as syn::ItemFn);
+    match hdl_test_expand(args, parse) {
+        Err(e) => e.to_compile_error().into(),
+        Ok(x) => x.into(),
+    }
+}
+
+#[proc_macro_attribute]
+pub fn hdl_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let parse = parse_macro_input!(item as syn::ItemFn);
+    match hdl_fn_expand(parse) {
+        Err(e) => e.to_compile_error().into(),
+        Ok(x) => x.into(),
+    }
+}
@@ -1,5 +1,8 @@
 use quote::quote;
-use syn::Result;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, Expr, Lit, Meta, NestedMeta, Result, Token};
 
 use crate::common;
 use crate::common::TS;
@@ -10,6 +13,8 @@ pub(crate) fn get_impl_for_logic_block(input: &syn::DeriveInput) -> Result<TS> {
     let has_changed = common::get_has_changed(fields.clone())?;
     let connect_all = common::get_connect_all(fields.clone())?;
     let accept = get_accept(fields.clone())?;
+    let attributes = get_attributes(input)?;
+    let parameters = get_parameters(input)?;
     let name = &input.ident;
     let (impl_generics, ty_generics, _where_clause) = &input.generics.split_for_impl();
     Ok(quote! {
@@ -18,6 +23,8 @@ pub(crate) fn get_impl_for_logic_block(input: &syn::DeriveInput) -> Result<TS> {
             #update_all
             #has_changed
             #accept
+            #attributes
+            #parameters
         }
     })
 }
@@ -30,5 +37,225 @@ fn get_accept(fields: Vec<TS>) -> Result<TS> {
             #(self.#fields.accept(#fields_as_strings, probe);)*
             probe.visit_end_scope(name, self);
         }
+
+        fn accept_mut(&mut self, name: &str, probe: &mut dyn probe::ProbeMut) {
+            probe.visit_start_scope(name);
+            #(self.#fields.accept_mut(#fields_as_strings, probe);)*
+            probe.visit_end_scope(name);
+        }
+    })
+}
+
+// Collect the `#[verilog_attribute(...)]` helper attributes on the struct and its fields
+// into an implementation of `Block::attributes`.
+fn get_attributes(input: &syn::DeriveInput) -> Result<TS> {
+    let mut attributes = parse_verilog_attributes(&input.attrs, quote!(None))?;
+    if let Data::Struct(ds) = &input.data {
+        for field in &ds.fields {
+            if let Some(name) = &field.ident {
+                let name = name.to_string();
+                attributes.extend(parse_verilog_attributes(
+                    &field.attrs,
+                    quote!(Some(#name.to_string())),
+                )?);
+            }
+        }
+    }
+    if attributes.is_empty() {
+        return Ok(quote!());
+    }
+    Ok(quote! {
+        fn attributes(&self) -> Vec<block::BlockAttribute> {
+            vec![#(#attributes),*]
+        }
+    })
+}
+
+fn parse_verilog_attributes(attrs: &[syn::Attribute], field: TS) -> Result<Vec<TS>> {
+    let mut ret = vec![];
+    for attr in attrs {
+        if !attr.path.is_ident("verilog_attribute") {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(syn::Error::new(
+                    meta.span(),
+                    "Expected a list of attributes, e.g., #[verilog_attribute(keep, ram_style = \"block\")]",
+                ))
+            }
+        };
+        for item in list.nested {
+            let attribute = match item {
+                NestedMeta::Meta(Meta::Path(path)) => {
+                    let name = quote!(#path).to_string().replace(' ', "");
+                    quote!(block::Attribute::new(#name))
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) => {
+                    let path = &nv.path;
+                    let name = quote!(#path).to_string().replace(' ', "");
+                    let value = match &nv.lit {
+                        Lit::Str(x) => x.value(),
+                        Lit::Int(x) => x.base10_digits().to_string(),
+                        Lit::Bool(x) => x.value.to_string(),
+                        lit => {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                "Attribute values must be strings, integers or booleans",
+                            ))
+                        }
+                    };
+                    quote!(block::Attribute::with_value(#name, #value))
+                }
+                item => {
+                    return Err(syn::Error::new(
+                        item.span(),
+                        "Expected an attribute name or name = value",
+                    ))
+                }
+            };
+            ret.push(quote!(block::BlockAttribute {
+                field: #field,
+                attribute: #attribute,
+            }));
+        }
+    }
+    Ok(ret)
+}
+
+// A single entry of a `#[verilog_parameter(...)]` list, either `N` or `NAME = expr`
+struct ParameterDecl {
+    name: syn::Ident,
+    value: Option<Expr>,
+}
+
+impl Parse for ParameterDecl {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(ParameterDecl { name, value })
+    }
+}
+
+fn parse_parameter_decls(attrs: &[syn::Attribute]) -> Result<Vec<ParameterDecl>> {
+    let mut ret = vec![];
+    for attr in attrs {
+        if attr.path.is_ident("verilog_parameter") {
+            ret.extend(
+                attr.parse_args_with(Punctuated::<ParameterDecl, Token![,]>::parse_terminated)?,
+            );
+        }
+    }
+    Ok(ret)
+}
+
+fn no_spaces(x: TS) -> String {
+    x.to_string().replace(' ', "")
+}
+
+// Collect the `#[verilog_parameter(...)]` helper attributes into an implementation of
+// `Block::parameters`.  The parameters on the struct are either const generics (`N`), which
+// give the width of the `Bits<N>` and `Signed<N>` signals of the block, or named expressions
+// (`W = T::BITS`), which give the width of the signals of type `T`.  The parameters on
+// fields bind the parameters of a sub-block to those of the block (`W = N`).
+fn get_parameters(input: &syn::DeriveInput) -> Result<TS> {
+    let decls = parse_parameter_decls(&input.attrs)?;
+    let mut bindings = vec![];
+    let mut signals = vec![vec![]; decls.len()];
+    if let Data::Struct(ds) = &input.data {
+        for field in &ds.fields {
+            let field_name = match &field.ident {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            for binding in parse_parameter_decls(&field.attrs)? {
+                let parameter = binding.name.to_string();
+                let value = match &binding.value {
+                    Some(Expr::Path(p)) if p.path.get_ident().is_some() => {
+                        p.path.get_ident().unwrap().to_string()
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            binding.name.span(),
+                            "Expected a parameter of the sub-block bound to a parameter of this block, e.g., #[verilog_parameter(W = N)]",
+                        ))
+                    }
+                };
+                if !decls.iter().any(|x| x.name == value) {
+                    return Err(syn::Error::new(
+                        binding.value.span(),
+                        format!("{} is not a verilog_parameter of this block", value),
+                    ));
+                }
+                bindings.push(quote!(block::ParameterBinding {
+                    field: #field_name.to_string(),
+                    parameter: #parameter.to_string(),
+                    value: #value.to_string(),
+                }));
+            }
+            if let syn::Type::Path(ty) = &field.ty {
+                let last = ty.path.segments.last().unwrap();
+                if last.ident != "Signal" {
+                    continue;
+                }
+                if let syn::PathArguments::AngleBracketed(args) = &last.arguments {
+                    if let Some(syn::GenericArgument::Type(kind)) = args.args.iter().nth(1) {
+                        let kind = no_spaces(quote!(#kind));
+                        for (ndx, decl) in decls.iter().enumerate() {
+                            let name = &decl.name;
+                            let matches = match &decl.value {
+                                None => {
+                                    kind == format!("Bits<{}>", name)
+                                        || kind == format!("Signed<{}>", name)
+                                }
+                                Some(expr) => no_spaces(quote!(#expr)) == format!("{}::BITS", kind),
+                            };
+                            if matches {
+                                signals[ndx].push(field_name.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if decls.is_empty() {
+        if !bindings.is_empty() {
+            return Err(syn::Error::new(
+                input.span(),
+                "Binding the parameters of a sub-block requires a #[verilog_parameter(...)] on the block",
+            ));
+        }
+        return Ok(quote!());
+    }
+    let module = input.ident.to_string();
+    let parameters = decls.iter().zip(signals.iter()).map(|(decl, signals)| {
+        let name = decl.name.to_string();
+        let ident = &decl.name;
+        let value = match &decl.value {
+            None => quote!(#ident),
+            Some(expr) => quote!(#expr),
+        };
+        quote!(block::Parameter {
+            name: #name.to_string(),
+            value: (#value) as usize,
+            signals: vec![#(#signals.to_string()),*],
+        })
+    });
+    Ok(quote! {
+        fn parameters(&self) -> Option<block::BlockParameters> {
+            Some(block::BlockParameters {
+                module: #module.to_string(),
+                path: concat!(module_path!(), "::", #module).to_string(),
+                parameters: vec![#(#parameters),*],
+                bindings: vec![#(#bindings),*],
+            })
+        }
     })
 }
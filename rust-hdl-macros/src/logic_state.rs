@@ -1,25 +1,89 @@
 use crate::common::*;
+use crate::logic_union::get_logic_union_impls;
 use quote::quote;
 use syn::spanned::Spanned;
-use syn::{Data, Result};
+use syn::{Data, Expr, Lit, Meta, NestedMeta, Result};
 
-fn get_variant_names(input: &syn::DeriveInput) -> Result<Vec<TS>> {
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Encoding {
+    Binary,
+    Gray,
+    OneHot,
+}
+
+// The encoding is selected with `#[logic_state(encoding = "binary" | "gray" | "one_hot")]`
+fn get_encoding(input: &syn::DeriveInput) -> Result<Option<Encoding>> {
+    let mut encoding = None;
+    for attr in &input.attrs {
+        if !attr.path.is_ident("logic_state") {
+            continue;
+        }
+        let err = |span| {
+            syn::Error::new(
+                span,
+                "expected #[logic_state(encoding = \"binary\" | \"gray\" | \"one_hot\")]",
+            )
+        };
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(err(meta.span())),
+        };
+        for item in &list.nested {
+            match item {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("encoding") => {
+                    encoding = Some(match &nv.lit {
+                        Lit::Str(x) if x.value() == "binary" => Encoding::Binary,
+                        Lit::Str(x) if x.value() == "gray" => Encoding::Gray,
+                        Lit::Str(x) if x.value() == "one_hot" => Encoding::OneHot,
+                        lit => return Err(err(lit.span())),
+                    })
+                }
+                _ => return Err(err(item.span())),
+            }
+        }
+    }
+    Ok(encoding)
+}
+
+fn get_discriminant(expr: &Expr) -> Result<usize> {
+    if let Expr::Lit(syn::ExprLit {
+        lit: Lit::Int(x), ..
+    }) = expr
+    {
+        return x.base10_parse::<usize>();
+    }
+    Err(syn::Error::new(
+        expr.span(),
+        "enum discriminants must be integer literals",
+    ))
+}
+
+// Returns the variant names, their explicit discriminants (if any were given), and
+// whether any of the variants have fields
+fn get_variants(input: &syn::DeriveInput) -> Result<(Vec<TS>, Option<Vec<usize>>, bool)> {
     let mut variants = vec![];
+    let mut discriminants = vec![];
+    let mut explicit = false;
+    let mut has_fields = false;
     match &input.data {
         Data::Enum(ed) => {
             for variant in &ed.variants {
-                if variant.fields.len() != 0 {
+                has_fields |= !variant.fields.is_empty();
+                // As in Rust, a variant without a discriminant follows the previous one
+                let value = match &variant.discriminant {
+                    Some((_, expr)) => {
+                        explicit = true;
+                        get_discriminant(expr)?
+                    }
+                    None => discriminants.last().map(|x| x + 1).unwrap_or(0),
+                };
+                if discriminants.contains(&value) {
                     return Err(syn::Error::new(
                         variant.span(),
-                        "enum variants cannot have fields",
-                    ));
-                }
-                if variant.discriminant.is_some() {
-                    return Err(syn::Error::new(
-                        variant.span(),
-                        "enum variants cannot have discriminants",
+                        format!("discriminant value {} is used more than once", value),
                     ));
                 }
+                discriminants.push(value);
                 let name = &variant.ident;
                 variants.push(quote!(#name));
             }
@@ -31,14 +95,59 @@ fn get_variant_names(input: &syn::DeriveInput) -> Result<Vec<TS>> {
             ))
         }
     }
-    Ok(variants)
+    if variants.is_empty() {
+        return Err(syn::Error::new(
+            input.span(),
+            "LogicState enums must have at least one variant",
+        ));
+    }
+    Ok((
+        variants,
+        if explicit { Some(discriminants) } else { None },
+        has_fields,
+    ))
 }
 
 pub fn get_logic_state_impls(input: &syn::DeriveInput) -> Result<TS> {
-    let variants = get_variant_names(input)?;
+    let (variants, explicit, has_fields) = get_variants(input)?;
+    let encoding = get_encoding(input)?;
     let first_variant = variants[0].clone();
     let num_variants = variants.len();
-    let discriminants: Vec<usize> = (0_usize..(variants.len() as usize)).collect();
+    let (discriminants, bits) = match (encoding.unwrap_or(Encoding::Binary), explicit) {
+        (Encoding::Binary, Some(values)) => {
+            let max = *values.iter().max().unwrap();
+            (values, quote!(clog2(#max + 1)))
+        }
+        (Encoding::Binary, None) => (
+            (0..num_variants).collect::<Vec<_>>(),
+            quote!(clog2(#num_variants)),
+        ),
+        (Encoding::Gray, None) => (
+            (0..num_variants).map(|x| x ^ (x >> 1)).collect(),
+            quote!(clog2(#num_variants)),
+        ),
+        (Encoding::OneHot, None) => {
+            if num_variants > usize::BITS as usize {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "one hot encoding supports at most 64 variants",
+                ));
+            }
+            (
+                (0..num_variants).map(|x| 1 << x).collect(),
+                quote!(#num_variants),
+            )
+        }
+        (_, Some(_)) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "explicit discriminants can only be used with binary encoding",
+            ))
+        }
+    };
+    if has_fields {
+        return get_logic_union_impls(input, &discriminants, bits);
+    }
     let name = &input.ident;
     let name_as_string = name.to_string();
     let variants_as_strings = variants
@@ -51,11 +160,14 @@ pub fn get_logic_state_impls(input: &syn::DeriveInput) -> Result<TS> {
         .collect::<Vec<String>>();
     Ok(quote!(
         impl Synth for #name {
-            const BITS: usize = clog2(#num_variants);
+            const BITS: usize = #bits;
             fn descriptor() -> TypeDescriptor {
                 TypeDescriptor {
                     name: #name_as_string.to_string(),
-                    kind: TypeKind::Enum(vec![#(#variants_as_strings.to_string(),)*])
+                    kind: TypeKind::Enum(vec![#(EnumVariant {
+                        name: #variants_as_strings.to_string(),
+                        value: #discriminants,
+                    },)*])
                 }
             }
             fn vcd(self) -> VCDValue {
@@ -68,6 +180,12 @@ pub fn get_logic_state_impls(input: &syn::DeriveInput) -> Result<TS> {
                     #(#name::#variants => #discriminants.into(),)*
                 }
             }
+            fn from_vcd(value: &VCDValue) -> Option<Self> {
+                match value {
+                    #(VCDValue::String(x) if x == #variants_only_as_strings => Some(#name::#variants),)*
+                    _ => None,
+                }
+            }
         }
 
         impl Into<Bits<{#name::BITS}>> for #name {
@@ -24,6 +24,7 @@ pub(crate) fn get_impl_for_logic_struct(input: &syn::DeriveInput) -> Result<TS>
     }
     let (impl_generics, ty_generics, _where_clause) = &input.generics.split_for_impl();
     let name = &input.ident;
+    let num_fields = fields.len();
     Ok(quote! {
         impl #impl_generics #name #ty_generics {
             #(
@@ -73,6 +74,18 @@ pub(crate) fn get_impl_for_logic_struct(input: &syn::DeriveInput) -> Result<TS>
                 let t: Bits<{Self::BITS}> = self.into();
                 t.into()
             }
+
+            fn from_vcd(value: &VCDValue) -> Option<Self> {
+                match value {
+                    VCDValue::Composite(x) if x.len() == #num_fields => {
+                        let mut x = x.iter();
+                        Some(Self {
+                            #(#fields: <#field_types>::from_vcd(x.next()?)?,)*
+                        })
+                    }
+                    _ => None,
+                }
+            }
         }
     })
 }
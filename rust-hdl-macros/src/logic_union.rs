@@ -0,0 +1,241 @@
+use crate::common::TS;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, Result};
+
+struct UnionField {
+    // The name used in the width/offset accessors and in VCD (e.g., `addr` or `0`)
+    name: String,
+    // The member as it appears in a pattern or constructor (e.g., `addr` or `0`)
+    member: TS,
+    binding: syn::Ident,
+    ty: TS,
+}
+
+struct UnionVariant {
+    ident: syn::Ident,
+    fields: Vec<UnionField>,
+}
+
+fn get_union_variants(input: &syn::DeriveInput) -> Result<Vec<UnionVariant>> {
+    let mut ret = vec![];
+    if let Data::Enum(ed) = &input.data {
+        for variant in &ed.variants {
+            let mut fields = vec![];
+            for (ndx, field) in variant.fields.iter().enumerate() {
+                let ty = &field.ty;
+                let (name, member) = match &field.ident {
+                    Some(ident) => (ident.to_string(), quote!(#ident)),
+                    None => {
+                        let index = syn::Index::from(ndx);
+                        (ndx.to_string(), quote!(#index))
+                    }
+                };
+                fields.push(UnionField {
+                    binding: format_ident!("_{}", name),
+                    name,
+                    member,
+                    ty: quote!(#ty),
+                });
+            }
+            ret.push(UnionVariant {
+                ident: variant.ident.clone(),
+                fields,
+            });
+        }
+    }
+    if ret.len() < 2 {
+        return Err(syn::Error::new(
+            input.span(),
+            "enums with fields must have at least two variants",
+        ));
+    }
+    Ok(ret)
+}
+
+// An enum with fields is packed as a tag (in the least significant bits) followed by
+// the fields of the active variant.  The tag is encoded the same way as the discriminant
+// of a field-less `LogicState` enum, so that unit variants have the same representation
+// (and Verilog localparam) as they would in a field-less enum.
+pub(crate) fn get_logic_union_impls(
+    input: &syn::DeriveInput,
+    tags: &[usize],
+    tag_bits: TS,
+) -> Result<TS> {
+    let variants = get_union_variants(input)?;
+    let name = &input.ident;
+    let name_as_string = name.to_string();
+    let mut accessors = vec![];
+    let mut payload_widths = vec![];
+    let mut to_bits = vec![];
+    let mut vcd_payloads = vec![];
+    let mut payload_descriptors = vec![];
+    let mut from_vcd_arms = vec![];
+    let mut num_payloads = 0_usize;
+    for (variant, tag) in variants.iter().zip(tags) {
+        let ident = &variant.ident;
+        let variant_as_string = ident.to_string();
+        let members = variant.fields.iter().map(|x| &x.member).collect::<Vec<_>>();
+        let bindings = variant
+            .fields
+            .iter()
+            .map(|x| &x.binding)
+            .collect::<Vec<_>>();
+        let types = variant.fields.iter().map(|x| &x.ty).collect::<Vec<_>>();
+        let field_names = variant.fields.iter().map(|x| &x.name).collect::<Vec<_>>();
+        let pattern = quote!(#name::#ident { #(#members: #bindings),* });
+        let mut offsets = vec![];
+        for (ndx, field) in variant.fields.iter().enumerate() {
+            let get_width_name = format_ident!("get_my_width_{}_{}", ident, field.name);
+            let get_offset_name = format_ident!("get_my_offset_{}_{}", ident, field.name);
+            let ty = &field.ty;
+            let previous = &types[0..ndx];
+            accessors.push(quote! {
+                #[allow(non_snake_case)]
+                pub fn #get_width_name() -> usize {
+                    <#ty>::BITS
+                }
+
+                #[allow(non_snake_case)]
+                pub fn #get_offset_name() -> usize {
+                    #tag_bits #(+<#previous>::BITS)*
+                }
+            });
+            offsets.push(quote!(#name::#get_offset_name()));
+        }
+        payload_widths.push(quote!(0_usize #(+<#types>::BITS)*));
+        to_bits.push(quote! {
+            #pattern => {
+                #tag.to_bits::<{#name::BITS}>() #(|
+                    (bit_cast::<{#name::BITS}, {<#types>::BITS}>(#bindings.into())
+                    << #offsets.to_bits())
+                )*
+            }
+        });
+        if variant.fields.is_empty() {
+            from_vcd_arms.push(quote!(#variant_as_string => Some(#name::#ident {})));
+            continue;
+        }
+        // Only variants with fields have a payload in the descriptor (and the VCD)
+        let payload = num_payloads + 1;
+        num_payloads += 1;
+        let num_fields = variant.fields.len();
+        let payload_name = format!("{}::{}", name_as_string, variant_as_string);
+        let payload_descriptor = quote!(TypeDescriptor {
+            name: #payload_name.to_string(),
+            kind: TypeKind::Composite(vec![#(Box::new(TypeField {
+                fieldname: #field_names.to_string(),
+                kind: <#types>::descriptor(),
+            }),)*]),
+        });
+        vcd_payloads.push(quote! {
+            ret.push(Box::new(match self {
+                #pattern => VCDValue::Composite(vec![#(Box::new(#bindings.vcd()),)*]),
+                _ => vcd_unknown(&#payload_descriptor),
+            }));
+        });
+        payload_descriptors.push(quote!(Box::new(TypeField {
+            fieldname: #variant_as_string.to_string(),
+            kind: #payload_descriptor,
+        })));
+        from_vcd_arms.push(quote! {
+            #variant_as_string => match x[#payload].as_ref() {
+                VCDValue::Composite(p) if p.len() == #num_fields => {
+                    let mut p = p.iter();
+                    Some(#name::#ident { #(#members: <#types>::from_vcd(p.next()?)?,)* })
+                }
+                _ => None,
+            }
+        });
+    }
+    let variant_idents = variants.iter().map(|x| &x.ident).collect::<Vec<_>>();
+    let variants_as_strings = variant_idents
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    let qualified_names = variants_as_strings
+        .iter()
+        .map(|x| format!("{}::{}", name_as_string, x))
+        .collect::<Vec<_>>();
+    let first = &variants[0];
+    let first_ident = &first.ident;
+    let first_members = first.fields.iter().map(|x| &x.member);
+    let first_types = first.fields.iter().map(|x| &x.ty);
+    Ok(quote! {
+        impl #name {
+            pub fn get_my_tag_width() -> usize {
+                #tag_bits
+            }
+
+            #(#accessors)*
+        }
+
+        impl From<#name> for Bits<{<#name>::BITS}> {
+            fn from(x: #name) -> Self {
+                match x {
+                    #(#to_bits)*
+                }
+            }
+        }
+
+        impl Synth for #name {
+            const BITS: usize = #tag_bits + {
+                let mut payload = 0_usize;
+                #(if #payload_widths > payload { payload = #payload_widths; })*
+                payload
+            };
+
+            fn descriptor() -> TypeDescriptor {
+                TypeDescriptor {
+                    name: #name_as_string.to_string(),
+                    kind: TypeKind::Composite(vec![
+                        Box::new(TypeField {
+                            fieldname: "tag".to_string(),
+                            kind: TypeDescriptor {
+                                name: #name_as_string.to_string(),
+                                kind: TypeKind::Enum(vec![#(EnumVariant {
+                                    name: #qualified_names.to_string(),
+                                    value: #tags,
+                                },)*]),
+                            },
+                        }),
+                        #(#payload_descriptors,)*
+                    ]),
+                }
+            }
+
+            fn vcd(self) -> VCDValue {
+                let tag = match self {
+                    #(#name::#variant_idents { .. } => #variants_as_strings,)*
+                };
+                let mut ret = vec![Box::new(VCDValue::String(tag.into()))];
+                #(#vcd_payloads)*
+                VCDValue::Composite(ret)
+            }
+
+            fn verilog(self) -> VerilogLiteral {
+                let t: Bits<{Self::BITS}> = self.into();
+                t.into()
+            }
+
+            fn from_vcd(value: &VCDValue) -> Option<Self> {
+                match value {
+                    VCDValue::Composite(x) if x.len() == #num_payloads + 1 => match x[0].as_ref() {
+                        VCDValue::String(tag) => match tag.as_str() {
+                            #(#from_vcd_arms,)*
+                            _ => None,
+                        },
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+        }
+
+        impl Default for #name {
+            fn default() -> #name {
+                #name::#first_ident { #(#first_members: <#first_types>::default(),)* }
+            }
+        }
+    })
+}
@@ -179,14 +179,14 @@ impl<T: Synth, const N: usize> Logic for LazyFIFOReader<T, N> {
     }
 }
 
-pub fn bursty_rand() -> Bits<32> {
-    if rand::thread_rng().gen::<f64>() < 0.9 {
+pub fn bursty_rand<R: Rng + ?Sized>(rng: &mut R) -> Bits<32> {
+    if rng.gen::<f64>() < 0.9 {
         Bits::from(0)
     } else {
-        Bits::from((rand::thread_rng().gen::<f64>() * 40.0) as LiteralType)
+        Bits::from((rng.gen::<f64>() * 40.0) as LiteralType)
     }
 }
 
-pub fn bursty_vec(len: usize) -> Vec<Bits<32>> {
-    (0..len).map(|_| bursty_rand()).collect()
+pub fn bursty_vec<R: Rng + ?Sized>(rng: &mut R, len: usize) -> Vec<Bits<32>> {
+    (0..len).map(|_| bursty_rand(rng)).collect()
 }
@@ -0,0 +1,49 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use rust_hdl::core::prelude::*;
+use std::hint::black_box;
+
+mod bool_bit_vec;
+use bool_bit_vec::BoolBitVec;
+
+const A: u128 = 0xdead_beef_cafe_babe_0123_4567_89ab_cdef;
+const B: u128 = 0x0f0f_0f0f_f0f0_f0f0_1111_2222_3333_4444;
+
+// Each operation is run on Bits (as "limbs") and on the [bool; N] representation
+// that it replaced (as "bool", without the Bits enum around it)
+macro_rules! bench_op {
+    ($c: expr, $name: expr, $n: expr, ($a: ident, $b: ident) => $op: expr) => {{
+        let mut group = $c.benchmark_group(format!("{} {}", $name, $n));
+        let ($a, $b): (Bits<$n>, Bits<$n>) = (A.to_bits(), B.to_bits());
+        group.bench_function("limbs", |x| x.iter(|| $op));
+        let ($a, $b): (BoolBitVec<$n>, BoolBitVec<$n>) = (A.into(), B.into());
+        group.bench_function("bool", |x| x.iter(|| $op));
+        group.finish();
+    }};
+}
+
+macro_rules! wide_ops {
+    ($fn_name: ident, $n: expr) => {
+        fn $fn_name(c: &mut Criterion) {
+            bench_op!(c, "add", $n, (a, b) => black_box(a) + black_box(b));
+            bench_op!(c, "xor", $n, (a, b) => black_box(a) ^ black_box(b));
+            bench_op!(c, "shift", $n, (a, b) => (black_box(a) << 13) | (black_box(b) >> 71));
+            // By reference, so that this times the comparison rather than the copies
+            bench_op!(c, "compare", $n, (a, b) => black_box(&a) < black_box(&b));
+            bench_op!(c, "get_bits", $n, (a, b) => {
+                let _ = b;
+                black_box(a).get_bits::<32>(black_box(40))
+            });
+            bench_op!(c, "set_bits", $n, (a, b) => {
+                let mut y = black_box(a);
+                y.set_bits::<16>(black_box(70), b.get_bits::<16>(0));
+                y
+            });
+        }
+    };
+}
+
+wide_ops!(wide_ops_128, 128);
+wide_ops!(wide_ops_256, 256);
+
+criterion_group!(benches, wide_ops_128, wide_ops_256);
+criterion_main!(benches);
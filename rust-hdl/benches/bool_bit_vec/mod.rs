@@ -0,0 +1,166 @@
+// The [bool; N] representation that `Bits` used for wide values before it switched to
+// 64 bit limbs, cut down to the operations in the benchmark.  The code is as it was, so
+// that the two can be compared on the same machine.
+#![allow(
+    clippy::assign_op_pattern,
+    clippy::manual_memcpy,
+    clippy::needless_range_loop,
+    clippy::suspicious_arithmetic_impl
+)]
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoolBitVec<const N: usize> {
+    bits: [bool; N],
+}
+
+impl<const N: usize> From<u128> for BoolBitVec<N> {
+    fn from(mut x: u128) -> Self {
+        let mut bits = [false; N];
+        for i in 0..N {
+            bits[i] = (x & 1) != 0;
+            x = x >> 1;
+        }
+        Self { bits }
+    }
+}
+
+impl<const N: usize> BoolBitVec<N> {
+    pub fn resize<const M: usize>(&self) -> BoolBitVec<M> {
+        let mut t = [false; M];
+        for i in 0..M.min(N) {
+            t[i] = self.bits[i];
+        }
+        BoolBitVec { bits: t }
+    }
+
+    pub fn mask() -> Self {
+        Self { bits: [true; N] }
+    }
+
+    fn binop<T>(&self, rhs: &Self, op: T) -> Self
+    where
+        T: Fn(&bool, &bool) -> bool,
+    {
+        let mut bits = [false; N];
+        for i in 0..N {
+            bits[i] = op(&self.bits[i], &rhs.bits[i]);
+        }
+        Self { bits }
+    }
+
+    // As `Bits::get_bits` and `Bits::set_bits` were written
+    pub fn get_bits<const M: usize>(&self, index: usize) -> BoolBitVec<M> {
+        (*self >> index).resize()
+    }
+
+    pub fn set_bits<const M: usize>(&mut self, index: usize, rhs: BoolBitVec<M>) {
+        let mask = !(BoolBitVec::<M>::mask().resize::<N>() << index);
+        let masked = *self & mask;
+        let replace = rhs.resize::<N>() << index;
+        *self = masked | replace
+    }
+}
+
+impl<const N: usize> std::ops::Shr<usize> for BoolBitVec<N> {
+    type Output = BoolBitVec<N>;
+
+    fn shr(self, rhs: usize) -> Self::Output {
+        let mut bits = [false; N];
+        for i in rhs..N {
+            bits[i - rhs] = self.bits[i];
+        }
+        Self { bits }
+    }
+}
+
+impl<const N: usize> std::ops::Shl<usize> for BoolBitVec<N> {
+    type Output = BoolBitVec<N>;
+
+    fn shl(self, rhs: usize) -> Self::Output {
+        let mut bits = [false; N];
+        for i in rhs..N {
+            bits[i] = self.bits[i - rhs];
+        }
+        Self { bits }
+    }
+}
+
+impl<const N: usize> std::ops::BitOr for BoolBitVec<N> {
+    type Output = BoolBitVec<N>;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.binop(&rhs, |a, b| a | b)
+    }
+}
+
+impl<const N: usize> std::ops::BitAnd for BoolBitVec<N> {
+    type Output = BoolBitVec<N>;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.binop(&rhs, |a, b| a & b)
+    }
+}
+
+impl<const N: usize> std::ops::BitXor for BoolBitVec<N> {
+    type Output = BoolBitVec<N>;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.binop(&rhs, |a, b| a ^ b)
+    }
+}
+
+impl<const N: usize> std::ops::Not for BoolBitVec<N> {
+    type Output = BoolBitVec<N>;
+
+    fn not(self) -> Self::Output {
+        let mut bits = [false; N];
+        for i in 0..N {
+            bits[i] = !self.bits[i];
+        }
+        Self { bits }
+    }
+}
+
+impl<const N: usize> std::ops::Add for BoolBitVec<N> {
+    type Output = BoolBitVec<N>;
+
+    fn add(self, rhs: BoolBitVec<N>) -> Self::Output {
+        let mut carry = false;
+        let mut bits = [false; N];
+        for i in 0..N {
+            let a = self.bits[i];
+            let b = rhs.bits[i];
+            let c_i = carry;
+            bits[i] = a ^ b ^ c_i;
+            carry = (a & b) | (b & c_i) | (a & c_i);
+        }
+        Self { bits }
+    }
+}
+
+impl<const N: usize> std::cmp::PartialEq for BoolBitVec<N> {
+    fn eq(&self, other: &Self) -> bool {
+        for i in 0..N {
+            if self.bits[i] != other.bits[i] {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl<const N: usize> std::cmp::PartialOrd for BoolBitVec<N> {
+    fn partial_cmp(&self, other: &BoolBitVec<N>) -> Option<std::cmp::Ordering> {
+        for i in 0..N {
+            let a = self.bits[N - 1 - i];
+            let b = other.bits[N - 1 - i];
+            if a & !b {
+                return Some(std::cmp::Ordering::Greater);
+            }
+            if !a & b {
+                return Some(std::cmp::Ordering::Less);
+            }
+        }
+        Some(std::cmp::Ordering::Equal)
+    }
+}
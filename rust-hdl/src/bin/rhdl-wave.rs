@@ -0,0 +1,15 @@
+//! Render the signals of a VCD file as an SVG, a text diagram, or an interactive
+//! HTML page.  Run `rhdl-wave --help` for the options.
+use rust_hdl::docs::vcd2svg::wave_tool;
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.is_empty() || args.iter().any(|x| x == "-h" || x == "--help") {
+        println!("{}", wave_tool::USAGE);
+        return;
+    }
+    if let Err(err) = wave_tool::run(&args) {
+        eprintln!("rhdl-wave: {}", err);
+        std::process::exit(1);
+    }
+}
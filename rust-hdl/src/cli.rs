@@ -0,0 +1,355 @@
+//! A command line driver for RustHDL projects.
+//!
+//! Instead of writing a custom `main` (or `#[test]`) for each design to produce
+//! Verilog, constraints and bitstreams, register the top level designs of a project
+//! with [register_design](crate::register_design), and add a binary that calls [main]:
+//!
+//! ```rust,no_run
+//! use rust_hdl::prelude::*;
+//!
+//! fn blinky() -> Strobe<32> {
+//!     Strobe::new(1_000_000, 1.0)
+//! }
+//!
+//! rust_hdl::register_design! {
+//!     name: "blinky",
+//!     description: "Blink an LED at 1Hz",
+//!     build: || Box::new(blinky()),
+//! }
+//!
+//! fn main() {
+//!     rust_hdl::cli::main()
+//! }
+//! ```
+//!
+//! Then, e.g., `cargo run --bin rhdl -- verilog blinky` writes `target/rhdl/blinky/top.v`.
+//! Board support packages register their boards with [register_board](crate::register_board),
+//! so that `build blinky --board alchitry_cu` generates a bitstream.  Note that the
+//! crates containing the registrations must be linked into the binary (e.g., with
+//! `use my_bsp as _;`).
+//!
+//! Run the binary with `help` for the list of subcommands.  The exit code is `0` on
+//! success, `1` if the design fails (a check, lint, simulation or build error), and `2`
+//! for usage errors, so that the driver can be used directly in CI.
+use crate::core::block::Block;
+use crate::core::check_error::check_all;
+use crate::core::module_defines::generate_verilog;
+use crate::core::simulate::{panic_message, SimError};
+use crate::core::yosys::yosys_validate;
+use crate::docs::vcd2svg::wave_tool;
+use std::path::{Path, PathBuf};
+
+#[doc(hidden)]
+pub use inventory;
+
+/// Simulate a design, writing the trace to the given VCD file
+pub type SimulateFn = fn(&str) -> Result<(), SimError>;
+
+/// A top level design, registered with [register_design](crate::register_design)
+pub struct Design {
+    /// The name used to select the design on the command line
+    pub name: &'static str,
+    /// A one line description shown by `list`
+    pub description: &'static str,
+    /// Construct the design (with its pin constraints)
+    pub build: fn() -> Box<dyn Block>,
+    /// Simulate the design, writing the trace to the given VCD file
+    pub simulate: Option<SimulateFn>,
+}
+
+inventory::collect!(Design);
+
+/// A target board, registered with [register_board](crate::register_board) by a
+/// board support package
+pub struct Board {
+    /// The name used to select the board on the command line
+    pub name: &'static str,
+    /// The name of the constraints file (e.g., `top.pcf`)
+    pub constraints_file: &'static str,
+    /// Generate the constraints for a design
+    pub constraints: fn(Box<dyn Block>) -> String,
+    /// Build a bitstream for a design in the given directory
+    pub build: fn(Box<dyn Block>, &str) -> anyhow::Result<()>,
+}
+
+inventory::collect!(Board);
+
+/// Register a top level design with the command line driver.  The `simulate`
+/// function is optional, and is given the path of the VCD file to write.
+///
+/// ```rust
+/// # use rust_hdl::prelude::*;
+/// # use std::time::Duration;
+/// fn simulate_pulser(vcd: &str) -> Result<(), SimError> {
+///     let mut sim = Simulation::new();
+///     sim.add_clock(5, |x: &mut Box<Pulser>| x.clock.next = !x.clock.val());
+///     sim.add_testbench(|mut sim: Sim<Pulser>| {
+///         let mut x = sim.init()?;
+///         wait_clock_cycles!(sim, clock, x, 100);
+///         sim.done(x)
+///     });
+///     sim.run_to_file(Box::new(Pulser::new(1_000, 10.0, Duration::from_millis(1))), 10_000, vcd)
+/// }
+///
+/// rust_hdl::register_design! {
+///     name: "pulser",
+///     description: "A pulse generator",
+///     build: || Box::new(Pulser::new(1_000, 10.0, Duration::from_millis(1))),
+///     simulate: simulate_pulser,
+/// }
+/// ```
+#[macro_export]
+macro_rules! register_design {
+    (name: $name: expr, description: $description: expr, build: $build: expr $(,)?) => {
+        $crate::cli::inventory::submit! {
+            $crate::cli::Design {
+                name: $name,
+                description: $description,
+                build: $build,
+                simulate: None,
+            }
+        }
+    };
+    (name: $name: expr, description: $description: expr, build: $build: expr, simulate: $simulate: expr $(,)?) => {
+        $crate::cli::inventory::submit! {
+            $crate::cli::Design {
+                name: $name,
+                description: $description,
+                build: $build,
+                simulate: Some($simulate),
+            }
+        }
+    };
+}
+
+/// Register a board with the command line driver.  This is normally done once by
+/// each board support package.
+#[macro_export]
+macro_rules! register_board {
+    (name: $name: expr, constraints_file: $file: expr, constraints: $constraints: expr, build: $build: expr $(,)?) => {
+        $crate::cli::inventory::submit! {
+            $crate::cli::Board {
+                name: $name,
+                constraints_file: $file,
+                constraints: $constraints,
+                build: $build,
+            }
+        }
+    };
+}
+
+/// An error from the command line driver
+#[derive(Clone, Debug, PartialEq)]
+pub enum CliError {
+    /// The command line was invalid (exit code 2)
+    Usage(String),
+    /// The design failed a check, lint, simulation or build (exit code 1)
+    Failed(String),
+}
+
+impl CliError {
+    /// The process exit code for the error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Failed(_) => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{}", msg),
+            CliError::Failed(msg) => write!(f, "failed: {}", msg),
+        }
+    }
+}
+
+const USAGE: &str = "\
+Usage: <command> [design] [options]
+
+Commands:
+  list                                List the registered designs and boards
+  verilog <design>                    Check the design and write top.v
+  lint <design>                       Check the design and validate top.v with yosys
+  sim <design>                        Run the simulation of the design, writing sim.vcd
+  wave <design> [wave options]        Render sim.vcd (default wave.svg, see rhdl-wave --help)
+  constraints <design> --board <b>    Write the pin constraints for a board
+  build <design> --board <b>          Build a bitstream for a board
+
+Options:
+  --out-dir <dir>                     The output directory (default target/rhdl).  Each
+                                      design is written to <dir>/<design>.
+";
+
+fn find_design(name: &str) -> Result<&'static Design, CliError> {
+    inventory::iter::<Design>
+        .into_iter()
+        .find(|x| x.name == name)
+        .ok_or_else(|| CliError::Usage(format!("unknown design {} (try list)", name)))
+}
+
+fn find_board(name: &str) -> Result<&'static Board, CliError> {
+    inventory::iter::<Board>
+        .into_iter()
+        .find(|x| x.name == name)
+        .ok_or_else(|| CliError::Usage(format!("unknown board {} (try list)", name)))
+}
+
+fn checked_design(design: &Design) -> Result<Box<dyn Block>, CliError> {
+    let mut uut = (design.build)();
+    uut.connect_all();
+    check_all(uut.as_ref()).map_err(|e| CliError::Failed(format!("{:?}", e)))?;
+    Ok(uut)
+}
+
+fn write_file(path: &Path, text: &str) -> Result<(), CliError> {
+    std::fs::write(path, text)
+        .map_err(|e| CliError::Failed(format!("cannot write {}: {}", path.display(), e)))
+}
+
+// Run a step that may panic (e.g., the BSP build functions, or a missing tool)
+fn catch<T, F: FnOnce() -> Result<T, CliError>>(step: &str, f: F) -> Result<T, CliError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|err| {
+        Err(CliError::Failed(format!(
+            "{} panicked: {}",
+            step,
+            panic_message(err.as_ref())
+        )))
+    })
+}
+
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, CliError> {
+    match args.iter().position(|x| x == name) {
+        Some(ndx) if ndx + 1 < args.len() => {
+            let value = args.remove(ndx + 1);
+            args.remove(ndx);
+            Ok(Some(value))
+        }
+        Some(_) => Err(CliError::Usage(format!("missing value for {}", name))),
+        None => Ok(None),
+    }
+}
+
+/// Run the driver with the given arguments (not including the program name), and
+/// return the messages to show the user
+pub fn run(args: &[String]) -> Result<String, CliError> {
+    let mut args = args.to_vec();
+    let out_dir = take_option(&mut args, "--out-dir")?.unwrap_or_else(|| "target/rhdl".to_string());
+    let board = take_option(&mut args, "--board")?;
+    let command = if args.is_empty() {
+        "help".to_string()
+    } else {
+        args.remove(0)
+    };
+    match command.as_str() {
+        "help" | "-h" | "--help" => return Ok(USAGE.to_string()),
+        "list" => {
+            let mut ret = String::from("Designs:\n");
+            for design in inventory::iter::<Design> {
+                ret += &format!("  {:24} {}\n", design.name, design.description);
+            }
+            ret += "Boards:\n";
+            for board in inventory::iter::<Board> {
+                ret += &format!("  {}\n", board.name);
+            }
+            return Ok(ret);
+        }
+        "verilog" | "lint" | "sim" | "wave" | "constraints" | "build" => {}
+        x => {
+            return Err(CliError::Usage(format!(
+                "unknown command {}\n\n{}",
+                x, USAGE
+            )))
+        }
+    }
+    if args.is_empty() {
+        return Err(CliError::Usage(format!("{} needs a design name", command)));
+    }
+    let design = find_design(&args.remove(0))?;
+    let dir = PathBuf::from(out_dir).join(design.name);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| CliError::Failed(format!("cannot create {}: {}", dir.display(), e)))?;
+    let board = || match &board {
+        Some(name) => find_board(name),
+        None => Err(CliError::Usage(format!("{} needs --board", command))),
+    };
+    match command.as_str() {
+        "verilog" => {
+            let uut = checked_design(design)?;
+            let path = dir.join("top.v");
+            write_file(&path, &generate_verilog(&uut))?;
+            Ok(format!("wrote {}", path.display()))
+        }
+        "lint" => {
+            let uut = checked_design(design)?;
+            let verilog = generate_verilog(&uut);
+            write_file(&dir.join("top.v"), &verilog)?;
+            catch("yosys", || {
+                yosys_validate(&format!("rhdl_lint_{}", design.name), &verilog)
+                    .map_err(|e| CliError::Failed(format!("{:?}", e)))
+            })?;
+            Ok(format!("{} passed lint", design.name))
+        }
+        "sim" => {
+            let simulate = design.simulate.ok_or_else(|| {
+                CliError::Usage(format!("design {} has no simulation", design.name))
+            })?;
+            let path = dir.join("sim.vcd");
+            let vcd = path.to_string_lossy().to_string();
+            catch("simulation", || {
+                simulate(&vcd).map_err(|e| CliError::Failed(format!("{:?}", e)))
+            })?;
+            Ok(format!("simulation passed, wrote {}", path.display()))
+        }
+        "wave" => {
+            let vcd = dir.join("sim.vcd");
+            if !vcd.exists() {
+                return Err(CliError::Usage(format!(
+                    "{} does not exist (run sim first)",
+                    vcd.display()
+                )));
+            }
+            let mut wave_args = vec![vcd.to_string_lossy().to_string()];
+            if !args.iter().any(|x| x == "-o" || x == "--output") {
+                wave_args.push("-o".into());
+                wave_args.push(dir.join("wave.svg").to_string_lossy().to_string());
+            }
+            wave_args.append(&mut args);
+            wave_tool::run(&wave_args).map_err(|e| CliError::Usage(e.to_string()))?;
+            Ok(format!("rendered {}", vcd.display()))
+        }
+        "constraints" => {
+            let board = board()?;
+            let uut = checked_design(design)?;
+            let path = dir.join(board.constraints_file);
+            write_file(&path, &(board.constraints)(uut))?;
+            Ok(format!("wrote {}", path.display()))
+        }
+        "build" => {
+            let board = board()?;
+            let uut = checked_design(design)?;
+            let path = dir.join(board.name);
+            let prefix = path.to_string_lossy().to_string();
+            catch("build", || {
+                (board.build)(uut, &prefix).map_err(|e| CliError::Failed(e.to_string()))
+            })?;
+            Ok(format!("built {} in {}", design.name, path.display()))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// The entry point for a project binary.  Runs the driver with the command line
+/// arguments and exits with the appropriate code.
+pub fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match run(&args) {
+        Ok(msg) => println!("{}", msg),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(err.exit_code());
+        }
+    }
+}
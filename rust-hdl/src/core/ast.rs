@@ -1,6 +1,7 @@
 use crate::core::bits::Bits;
 use crate::core::signed::Signed;
-use num_bigint::{BigInt, Sign};
+use crate::core::synth::Synth;
+use num_bigint::{BigInt, BigUint, Sign};
 use std::fmt::{Display, Formatter, LowerHex};
 
 /// The BlackBox struct provides a way to wrap a blackbox,
@@ -594,6 +595,101 @@ pub enum VerilogStatement {
     Comment(String),
     Link(Vec<VerilogLink>),
     Macro(VerilogBlock),
+    Display(VerilogDisplay),
+    Location(SourceLocation),
+}
+
+/// The location of an HDL statement in the Rust source.  `#[hdl_gen]` records the
+/// location of each statement, and Verilog generated with
+/// [VerilogOptions::source_locations](crate::core::module_defines::VerilogOptions::source_locations)
+/// carries it as a `// file:line` comment.  Use [source_location](crate::core::verilog_gen::source_location) to map a
+/// line of the Verilog back to the Rust source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLocation {
+    /// The file, relative to the crate (or workspace) it belongs to
+    pub file: String,
+    /// The line number (starting at 1)
+    pub line: u32,
+}
+
+impl SourceLocation {
+    // Files of crates outside the workspace (e.g., from the registry) have absolute paths,
+    // which are made relative to the crate, so that the generated Verilog does not depend
+    // on where it was built.
+    #[doc(hidden)]
+    pub fn new(file: &str, line: u32, crate_dir: &str) -> Self {
+        let file = file
+            .strip_prefix(crate_dir)
+            .map(|x| x.trim_start_matches(['/', '\\']))
+            .unwrap_or(file);
+        Self {
+            file: file.into(),
+            line,
+        }
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerilogSystemTask {
+    Display,
+    Error,
+}
+
+// A `$display` (from `println!`) or `$error` (from `assert!`) call.  For assertions,
+// the task only runs when the `assertion` is false.
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct VerilogDisplay {
+    pub task: VerilogSystemTask,
+    pub assertion: Option<VerilogExpression>,
+    pub format: String,
+    pub args: Vec<VerilogExpression>,
+}
+
+// A variable of a Verilog `function` generated from an `#[hdl_fn]` - an argument, a
+// local, or the result (which has the name of the function).
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerilogFunctionVariable {
+    pub name: String,
+    pub width: usize,
+    pub signed: bool,
+}
+
+impl VerilogFunctionVariable {
+    pub fn new<T: Synth>(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            width: T::BITS,
+            signed: T::descriptor().kind.is_signed(),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct VerilogFunction {
+    pub name: String,
+    pub result: VerilogFunctionVariable,
+    pub args: Vec<VerilogFunctionVariable>,
+    pub locals: Vec<VerilogFunctionVariable>,
+    pub block: VerilogBlock,
+}
+
+// A call to an `#[hdl_fn]`.  The definition travels with the call, so that each module
+// can emit the functions it uses.
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct VerilogFunctionCall {
+    pub function: VerilogFunction,
+    pub args: Vec<VerilogExpression>,
 }
 
 #[doc(hidden)]
@@ -667,6 +763,13 @@ pub struct VerilogLiteral {
 }
 
 impl VerilogLiteral {
+    /// A `bits` wide literal with the given (unsigned) value
+    pub fn from_raw(val: u64, bits: usize) -> Self {
+        VerilogLiteral {
+            val: val.into(),
+            bits,
+        }
+    }
     pub fn as_usize(&self) -> usize {
         let m = self.val.to_u32_digits();
         assert!(m.0 != Sign::Minus);
@@ -711,6 +814,16 @@ define_literal_from_uint!(usize, 64);
 #[cfg(target_pointer_width = "32")]
 define_literal_from_uint!(usize, 32);
 
+impl From<BigUint> for VerilogLiteral {
+    fn from(x: BigUint) -> Self {
+        let bits = (x.bits() as usize).max(1);
+        VerilogLiteral {
+            val: x.into(),
+            bits,
+        }
+    }
+}
+
 impl<const N: usize> From<Bits<N>> for VerilogLiteral {
     fn from(x: Bits<N>) -> Self {
         let mut z = BigInt::default();
@@ -770,6 +883,9 @@ pub enum VerilogExpression {
         Box<VerilogExpression>,
         Box<VerilogExpression>,
     ),
+    Call(Box<VerilogFunctionCall>),
+    Concat(Vec<VerilogExpression>),
+    Replicate(usize, Box<VerilogExpression>),
 }
 
 #[doc(hidden)]
@@ -785,6 +901,7 @@ pub enum VerilogOp {
     BitOr,
     Shl,
     Shr,
+    AShr,
     Eq,
     Lt,
     Le,
@@ -0,0 +1,488 @@
+//! An alternative testbench API based on `async fn`.
+//!
+//! The threaded [Simulation](crate::core::simulate::Simulation) requires every testbench to
+//! pass a `Box<T>` back and forth with the simulator, and runs each testbench on its own
+//! OS thread.  The [AsyncSimulation] runs all testbenches as futures on a single thread.
+//! Each testbench receives an [AsyncSim] handle that can borrow the circuit, and awaits
+//! simulation events such as a clock edge, a time delay or a condition becoming true.
+//!
+//! ```rust
+//! # use rust_hdl::prelude::*;
+//! #[derive(LogicBlock, Default)]
+//! struct Counter {
+//!     pub clock: Signal<In, Clock>,
+//!     pub count: Signal<Out, Bits<8>>,
+//!     counter: DFF<Bits<8>>,
+//! }
+//!
+//! impl Logic for Counter {
+//!     #[hdl_gen]
+//!     fn update(&mut self) {
+//!         dff_setup!(self, clock, counter);
+//!         self.counter.d.next = self.counter.q.val() + 1;
+//!         self.count.next = self.counter.q.val();
+//!     }
+//! }
+//!
+//! let mut sim = AsyncSimulation::new();
+//! sim.add_clock(5, |x: &mut Counter| x.clock.next = !x.clock.val());
+//! sim.add_testbench(|sim: AsyncSim<Counter>| async move {
+//!     for _ in 0..10 {
+//!         sim.clock_edge(|x| &x.clock).await?;
+//!     }
+//!     sim.until(|x| x.count.val() == 15).await?;
+//!     assert_eq!(sim.circuit().count.val(), 15);
+//!     Ok(())
+//! });
+//! sim.run(Box::new(Counter::default()), 1000).unwrap();
+//! ```
+use crate::core::block::Block;
+use crate::core::check_error::check_all;
+use crate::core::clock::Clock;
+use crate::core::direction::In;
+use crate::core::signal::Signal;
+use crate::core::simulate::{panic_message, CustomLogicFn, Result, SimError};
+use crate::core::vcd_probe::{write_vcd_change, write_vcd_dump, write_vcd_header};
+use std::cell::{Cell, RefCell, RefMut};
+use std::future::Future;
+use std::io::Write;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+enum AsyncTrigger<T> {
+    Never,
+    Time(u64),
+    Function(Box<dyn FnMut(&T) -> bool>),
+    FunctionOrTime(Box<dyn FnMut(&T) -> bool>, u64),
+    Clock(u64),
+    Halt(SimError),
+}
+
+struct Shared<T> {
+    time: Cell<u64>,
+    names: RefCell<Vec<String>>,
+    circuit: RefCell<Option<Box<T>>>,
+    triggers: RefCell<Vec<AsyncTrigger<T>>>,
+    fired: RefCell<Vec<bool>>,
+}
+
+type Testbench = Pin<Box<dyn Future<Output = Result<()>>>>;
+
+/// This type represents a simulation over a circuit `T` in which the testbenches are
+/// `async` functions executed on a single thread.  It is an alternative to
+/// [Simulation](crate::core::simulate::Simulation), and uses the same scheduling rules.
+pub struct AsyncSimulation<T> {
+    shared: Rc<Shared<T>>,
+    testbenches: Vec<Option<Testbench>>,
+    custom_logic: Vec<CustomLogicFn<T>>,
+}
+
+/// The `AsyncSim` handle is given to every testbench in an [AsyncSimulation].  It
+/// provides access to the circuit and the awaitable simulation events.
+pub struct AsyncSim<T> {
+    id: usize,
+    shared: Rc<Shared<T>>,
+}
+
+impl<T> Clone for AsyncSim<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// The future returned by the waiting methods of [AsyncSim].  It resolves once the
+/// simulator has fired the trigger it registered.
+pub struct SimEvent<T> {
+    id: usize,
+    shared: Rc<Shared<T>>,
+    trigger: Option<AsyncTrigger<T>>,
+    timeout: Option<(Rc<dyn Fn(&T) -> bool>, u64)>,
+}
+
+impl<T> Future for SimEvent<T> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.trigger.take() {
+            Some(trigger) => {
+                this.shared.triggers.borrow_mut()[this.id] = trigger;
+                Poll::Pending
+            }
+            None => {
+                if !std::mem::take(&mut this.shared.fired.borrow_mut()[this.id]) {
+                    return Poll::Pending;
+                }
+                if let Some((check, timeout)) = &this.timeout {
+                    let circuit = this.shared.circuit.borrow();
+                    if !check(circuit.as_ref().unwrap()) {
+                        let err = SimError::Timeout {
+                            time: this.shared.time.get(),
+                            testbench: this.shared.names.borrow()[this.id].clone(),
+                            timeout: *timeout,
+                        };
+                        this.shared.triggers.borrow_mut()[this.id] =
+                            AsyncTrigger::Halt(err.clone());
+                        return Poll::Ready(Err(err));
+                    }
+                }
+                Poll::Ready(Ok(()))
+            }
+        }
+    }
+}
+
+impl<T> AsyncSim<T> {
+    fn event(&self, trigger: AsyncTrigger<T>) -> SimEvent<T> {
+        SimEvent {
+            id: self.id,
+            shared: self.shared.clone(),
+            trigger: Some(trigger),
+            timeout: None,
+        }
+    }
+    /// Borrow the circuit.  The borrow must be released before the next `.await`,
+    /// otherwise the simulator cannot update the circuit and the run fails with
+    /// [SimError::SimTerminated].
+    pub fn circuit(&self) -> RefMut<'_, T> {
+        RefMut::map(self.shared.circuit.borrow_mut(), |x| {
+            x.as_mut()
+                .expect("Circuit is only available during a run")
+                .as_mut()
+        })
+    }
+    /// The current simulation time in picoseconds
+    pub fn time(&self) -> u64 {
+        self.shared.time.get()
+    }
+    /// Wait for `delta` picoseconds
+    pub fn wait(&self, delta: u64) -> SimEvent<T> {
+        self.event(AsyncTrigger::Time(self.time() + delta))
+    }
+    /// Wait until the condition evaluates to `true`.  If it is already true,
+    /// the event resolves without advancing time.
+    pub fn until<F>(&self, condition: F) -> SimEvent<T>
+    where
+        F: Fn(&T) -> bool + 'static,
+    {
+        self.event(AsyncTrigger::Function(Box::new(condition)))
+    }
+    /// Wait until the condition evaluates to `true`, but for at most `timeout`
+    /// picoseconds.  If the condition does not occur in time, the simulation is
+    /// halted and the event resolves to a [SimError::Timeout].
+    pub fn until_with_timeout<F>(&self, condition: F, timeout: u64) -> SimEvent<T>
+    where
+        F: Fn(&T) -> bool + 'static,
+    {
+        let check = Rc::new(condition);
+        let watch = check.clone();
+        let mut event = self.event(AsyncTrigger::FunctionOrTime(
+            Box::new(move |x| watch(x)),
+            self.time() + timeout,
+        ));
+        event.timeout = Some((check, timeout));
+        event
+    }
+    /// Wait for the next rising edge of the clock selected by `clock`.
+    pub fn clock_edge<F>(&self, clock: F) -> SimEvent<T>
+    where
+        F: Fn(&T) -> &Signal<In, Clock> + 'static,
+    {
+        let mut last = match self.shared.circuit.try_borrow() {
+            Ok(x) => x.as_ref().map(|x| clock(x).val().clk).unwrap_or(true),
+            Err(_) => true,
+        };
+        self.event(AsyncTrigger::Function(Box::new(move |x| {
+            let now = clock(x).val().clk;
+            let edge = now && !last;
+            last = now;
+            edge
+        })))
+    }
+    /// Wait for `delta` picoseconds as a clock source.  A simulation in which only
+    /// clock sources are waiting is considered finished.
+    pub fn clock(&self, delta: u64) -> SimEvent<T> {
+        self.event(AsyncTrigger::Clock(self.time() + delta))
+    }
+    /// Halt the simulation.  The returned error should be propagated out of the
+    /// testbench, i.e., `return sim.halt();`
+    pub fn halt(&self) -> Result<()> {
+        self.halt_with_message("")
+    }
+    /// Halt the simulation, recording the reason (e.g., the failed assertion)
+    /// in the [SimError::SimHalted] returned by the simulation.
+    pub fn halt_with_message(&self, message: &str) -> Result<()> {
+        let err = SimError::SimHalted {
+            time: self.time(),
+            testbench: self.name(),
+            message: message.into(),
+        };
+        self.shared.triggers.borrow_mut()[self.id] = AsyncTrigger::Halt(err.clone());
+        Err(err)
+    }
+    /// The name of the testbench that owns this handle
+    pub fn name(&self) -> String {
+        self.shared.names.borrow()[self.id].clone()
+    }
+}
+
+impl<T: Block + 'static> Default for AsyncSimulation<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
+
+impl<T: Block + 'static> AsyncSimulation<T> {
+    /// Construct an async simulation struct
+    pub fn new() -> AsyncSimulation<T> {
+        Self {
+            shared: Rc::new(Shared {
+                time: Cell::new(0),
+                names: RefCell::new(vec![]),
+                circuit: RefCell::new(None),
+                triggers: RefCell::new(vec![]),
+                fired: RefCell::new(vec![]),
+            }),
+            testbenches: vec![],
+            custom_logic: vec![],
+        }
+    }
+    /// Add a clock function to the simulation.  The `clock_fn` is called every
+    /// `interval` picoseconds.
+    pub fn add_clock<F>(&mut self, interval: u64, clock_fn: F)
+    where
+        F: Fn(&mut T) + 'static,
+    {
+        self.add_phased_clock(interval, 0, clock_fn)
+    }
+    /// Add a clock function to the simulation that starts toggling after `phase_delay`
+    /// picoseconds.
+    pub fn add_phased_clock<F>(&mut self, interval: u64, phase_delay: u64, clock_fn: F)
+    where
+        F: Fn(&mut T) + 'static,
+    {
+        let name = format!("clock_{}", self.testbenches.len());
+        self.add_named_testbench(&name, move |sim: AsyncSim<T>| async move {
+            if phase_delay > 0 {
+                sim.wait(phase_delay).await?;
+            }
+            loop {
+                sim.clock(interval).await?;
+                clock_fn(&mut sim.circuit());
+            }
+        });
+    }
+    /// Add a testbench to the simulation.  The testbench is an `async` closure that
+    /// receives an [AsyncSim] handle.
+    pub fn add_testbench<F, Fut>(&mut self, testbench: F)
+    where
+        F: FnOnce(AsyncSim<T>) -> Fut,
+        Fut: Future<Output = Result<()>> + 'static,
+    {
+        let name = format!("testbench_{}", self.testbenches.len());
+        self.add_named_testbench(&name, testbench);
+    }
+    /// Add a testbench to the simulation with the given name.  The name is used
+    /// to identify the testbench in the [SimError] returned when it fails.
+    pub fn add_named_testbench<F, Fut>(&mut self, name: &str, testbench: F)
+    where
+        F: FnOnce(AsyncSim<T>) -> Fut,
+        Fut: Future<Output = Result<()>> + 'static,
+    {
+        let sim = self.endpoint(name);
+        self.testbenches.push(Some(Box::pin(testbench(sim))));
+    }
+    /// Add custom logic to the simulation.  See [CustomLogicFn].
+    pub fn add_custom_logic<F>(&mut self, logic: F)
+    where
+        F: Fn(&mut T) + 'static,
+    {
+        self.custom_logic.push(Box::new(logic));
+    }
+    fn endpoint(&mut self, name: &str) -> AsyncSim<T> {
+        let id = self.testbenches.len();
+        self.shared.names.borrow_mut().push(name.into());
+        self.shared.triggers.borrow_mut().push(AsyncTrigger::Never);
+        self.shared.fired.borrow_mut().push(false);
+        AsyncSim {
+            id,
+            shared: self.shared.clone(),
+        }
+    }
+    fn dispatch(&mut self, idx: usize) -> Result<()> {
+        self.shared.fired.borrow_mut()[idx] = true;
+        self.shared.triggers.borrow_mut()[idx] = AsyncTrigger::Never;
+        if let Some(testbench) = &mut self.testbenches[idx] {
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+            let result =
+                std::panic::catch_unwind(AssertUnwindSafe(|| testbench.as_mut().poll(&mut cx)));
+            self.shared.fired.borrow_mut()[idx] = false;
+            match result {
+                Err(e) => {
+                    return Err(SimError::SimPanic {
+                        time: self.shared.time.get(),
+                        testbench: self.shared.names.borrow()[idx].clone(),
+                        message: panic_message(e.as_ref()),
+                    })
+                }
+                Ok(Poll::Ready(result)) => {
+                    self.testbenches[idx] = None;
+                    if let Err(err) = result {
+                        self.shared.triggers.borrow_mut()[idx] = AsyncTrigger::Halt(err);
+                    }
+                }
+                Ok(Poll::Pending) => {}
+            }
+        }
+        let mut circuit = self
+            .shared
+            .circuit
+            .try_borrow_mut()
+            .map_err(|_| SimError::SimTerminated)?;
+        let x = circuit.as_mut().unwrap();
+        for _ in 0..100 {
+            for l in &self.custom_logic {
+                l(x);
+            }
+            x.update_all();
+            if !x.has_changed() {
+                return Ok(());
+            }
+        }
+        Err(SimError::FailedToConverge {
+            time: self.shared.time.get(),
+        })
+    }
+    fn scan_triggers(&self) -> (u64, usize, bool, Option<SimError>) {
+        let x = self.shared.circuit.borrow();
+        let x = x.as_ref().unwrap();
+        let mut min_time = !0_u64;
+        let mut min_idx = 0;
+        let mut only_clock_waiters = true;
+        for (id, trigger) in self.shared.triggers.borrow_mut().iter_mut().enumerate() {
+            match trigger {
+                AsyncTrigger::Halt(err) => return (!0, !0, false, Some(err.clone())),
+                AsyncTrigger::Never => {}
+                AsyncTrigger::Time(t) => {
+                    only_clock_waiters = false;
+                    if *t < min_time {
+                        min_time = *t;
+                        min_idx = id;
+                    }
+                }
+                AsyncTrigger::Function(watch) => {
+                    only_clock_waiters = false;
+                    if watch(x) {
+                        min_idx = id;
+                        min_time = self.shared.time.get();
+                        break;
+                    }
+                }
+                AsyncTrigger::FunctionOrTime(watch, t) => {
+                    only_clock_waiters = false;
+                    if watch(x) {
+                        min_idx = id;
+                        min_time = self.shared.time.get();
+                        break;
+                    }
+                    if *t < min_time {
+                        min_time = *t;
+                        min_idx = id;
+                    }
+                }
+                AsyncTrigger::Clock(t) => {
+                    if *t < min_time {
+                        min_time = *t;
+                        min_idx = id;
+                    }
+                }
+            }
+        }
+        (min_time, min_idx, only_clock_waiters, None)
+    }
+    fn run_loop<F>(&mut self, mut x: Box<T>, max_time: u64, mut on_step: F) -> Result<Box<T>>
+    where
+        F: FnMut(Option<u64>, &T),
+    {
+        x.connect_all();
+        check_all(x.as_ref())?;
+        self.shared.circuit.replace(Some(x));
+        let result = self.run_inner(max_time, &mut on_step);
+        // Drop the testbenches so that they release their handles on the circuit
+        self.testbenches.clear();
+        let x = self.shared.circuit.replace(None).unwrap();
+        result.map(|_| x)
+    }
+    fn run_inner<F>(&mut self, max_time: u64, on_step: &mut F) -> Result<()>
+    where
+        F: FnMut(Option<u64>, &T),
+    {
+        // First start all of the testbenches
+        for id in 0..self.testbenches.len() {
+            self.dispatch(id)?;
+        }
+        on_step(None, self.shared.circuit.borrow().as_ref().unwrap());
+        let mut halted = None;
+        while self.shared.time.get() < max_time {
+            let (time, idx, clocks_only, halt) = self.scan_triggers();
+            if time == !0 || clocks_only || halt.is_some() {
+                halted = halt;
+                break;
+            }
+            self.shared.time.set(time);
+            self.dispatch(idx)?;
+            on_step(Some(time), self.shared.circuit.borrow().as_ref().unwrap());
+        }
+        if self.shared.time.get() >= max_time {
+            return Err(SimError::MaxTimeReached {
+                time: self.shared.time.get(),
+            });
+        }
+        if let Some(err) = halted {
+            return Err(err);
+        }
+        Ok(())
+    }
+    /// Run the simulation on the circuit `x` for at most `max_time` picoseconds.
+    pub fn run(&mut self, x: Box<T>, max_time: u64) -> Result<()> {
+        self.run_loop(x, max_time, |_, _| {}).map(|_| ())
+    }
+    /// Run the simulation and write a VCD trace of the run to the file `name`.
+    pub fn run_to_file(&mut self, x: Box<T>, max_time: u64, name: &str) -> Result<()> {
+        let mut vcd = vec![];
+        let result = self.run_traced(x, max_time, &mut vcd);
+        std::fs::write(name, vcd).unwrap();
+        result
+    }
+    /// Run the simulation and write a VCD trace of the run to `trace`.
+    pub fn run_traced<W: Write>(&mut self, x: Box<T>, max_time: u64, trace: W) -> Result<()> {
+        let mut vcd = Some(write_vcd_header(trace, x.as_ref()));
+        self.run_loop(x, max_time, |time, x| {
+            let mut probe = vcd.take().unwrap();
+            match time {
+                None => probe = write_vcd_dump(probe, x),
+                Some(time) => {
+                    probe.timestamp(time).unwrap();
+                    probe = write_vcd_change(probe, x);
+                }
+            }
+            vcd = Some(probe);
+        })
+        .map(|_| ())
+    }
+}
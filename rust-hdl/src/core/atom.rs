@@ -1,4 +1,5 @@
 use crate::core::ast::VerilogLiteral;
+use crate::core::attribute::Attribute;
 use crate::core::constraint::PinConstraint;
 use crate::core::prelude::TypeKind;
 use crate::core::synth::VCDValue;
@@ -46,6 +47,16 @@ pub trait Atom {
     fn id(&self) -> usize;
     fn verilog(&self) -> VerilogLiteral;
     fn constraints(&self) -> Vec<PinConstraint>;
+    fn attributes(&self) -> Vec<Attribute> {
+        vec![]
+    }
+}
+
+#[doc(hidden)]
+pub trait AtomMut: Atom {
+    /// Set the next value of the atom from a [VCDValue].  Returns `false` if the
+    /// value is not valid for the type of the atom, or the atom cannot be changed.
+    fn force(&mut self, value: &VCDValue) -> bool;
 }
 
 pub fn is_atom_an_enum(atom: &dyn Atom) -> bool {
@@ -56,10 +67,7 @@ pub fn is_atom_an_enum(atom: &dyn Atom) -> bool {
 }
 
 pub fn is_atom_signed(atom: &dyn Atom) -> bool {
-    match atom.descriptor().kind {
-        TypeKind::Signed(_) => true,
-        _ => false,
-    }
+    atom.descriptor().kind.is_signed()
 }
 
 pub fn get_atom_typename(atom: &dyn Atom) -> String {
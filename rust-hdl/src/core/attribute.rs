@@ -0,0 +1,117 @@
+use std::fmt::{Display, Formatter};
+
+/// A Verilog attribute, such as `(* keep *)` or `(* ASYNC_REG = "TRUE" *)`.  Attributes
+/// are directives to the synthesis (or simulation) tools, and do not change the logic.
+///
+/// Attributes can be attached to a [Signal](crate::core::signal::Signal) with
+/// [add_attribute](crate::core::signal::Signal::add_attribute), in which case they are
+/// emitted on the declaration of that signal.  They can also be attached to a
+/// `LogicBlock` struct (and to its fields) with the `verilog_attribute` helper attribute
+/// of the derive:
+/// ```rust
+/// # use rust_hdl::core::prelude::*;
+/// # use rust_hdl::widgets::prelude::*;
+/// #[derive(LogicBlock, Default)]
+/// #[verilog_attribute(keep_hierarchy = "yes")] // <-- on the module
+/// struct Capture {
+///     pub clock: Signal<In, Clock>,
+///     pub data: Signal<In, Bits<8>>,
+///     #[verilog_attribute(mark_debug = "true")] // <-- on the declaration of `seen`
+///     seen: Signal<Local, Bits<8>>,
+///     #[verilog_attribute(keep)] // <-- on the instance of `latch`
+///     latch: DFF<Bits<8>>,
+/// }
+///
+/// impl Logic for Capture {
+///     #[hdl_gen]
+///     fn update(&mut self) {
+///         dff_setup!(self, clock, latch);
+///         self.latch.d.next = self.data.val();
+///         self.seen.next = self.latch.q.val();
+///     }
+/// }
+///
+/// let mut uut = Capture::default();
+/// uut.connect_all();
+/// let vlog = generate_verilog(&uut);
+/// assert!(vlog.contains("(* keep_hierarchy = \"yes\" *)\nmodule top("));
+/// assert!(vlog.contains("(* mark_debug = \"true\" *) reg  [7:0] seen;"));
+/// assert!(vlog.contains("(* keep *) top$latch latch("));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    /// The name of the attribute
+    pub name: String,
+    /// The value of the attribute (written as a string), if it has one
+    pub value: Option<String>,
+}
+
+impl Attribute {
+    /// An attribute with no value, e.g., `(* keep *)`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            value: None,
+        }
+    }
+    /// An attribute with a value, e.g., `(* ram_style = "block" *)`
+    pub fn with_value(name: &str, value: &str) -> Self {
+        Self {
+            name: name.into(),
+            value: Some(value.into()),
+        }
+    }
+    /// `(* keep *)` - do not optimize away the signal
+    pub fn keep() -> Self {
+        Self::new("keep")
+    }
+    /// `(* ASYNC_REG = "TRUE" *)` - the register is part of a synchronizer chain
+    pub fn async_reg() -> Self {
+        Self::with_value("ASYNC_REG", "TRUE")
+    }
+    /// `(* mark_debug = "true" *)` - make the signal visible to on-chip debug tools
+    pub fn mark_debug() -> Self {
+        Self::with_value("mark_debug", "true")
+    }
+    /// `(* ram_style = "<style>" *)` - select how a memory is implemented (e.g., `block`)
+    pub fn ram_style(style: &str) -> Self {
+        Self::with_value("ram_style", style)
+    }
+}
+
+impl Display for Attribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            None => write!(f, "{}", self.name),
+            Some(value) => write!(f, "{} = \"{}\"", self.name, value.replace('"', "\\\"")),
+        }
+    }
+}
+
+/// An [Attribute] attached to a block, either to the block itself (if `field` is `None`),
+/// or to one of its fields.  These are generated by the `verilog_attribute` helper of
+/// `#[derive(LogicBlock)]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockAttribute {
+    /// The field the attribute is attached to, or `None` for the block itself
+    pub field: Option<String>,
+    /// The attribute
+    pub attribute: Attribute,
+}
+
+/// Format a list of attributes as a single Verilog attribute instance, e.g.,
+/// `(* keep, ASYNC_REG = "TRUE" *) `, or an empty string if there are none.
+pub fn verilog_attributes(attributes: &[Attribute]) -> String {
+    if attributes.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "(* {} *) ",
+            attributes
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
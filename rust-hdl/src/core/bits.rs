@@ -332,13 +332,17 @@
 //! assert_eq!(y, bits(0x00DE));
 //! ```
 
+use crate::core::ast::{
+    VerilogBlockOrConditional, VerilogConditional, VerilogExpression, VerilogFunction,
+    VerilogFunctionVariable, VerilogOp, VerilogStatement,
+};
 use crate::core::bitvec::BitVec;
 use crate::core::short_bit_vec::{ShortBitVec, ShortType, SHORT_BITS};
 use crate::core::synth::VCDValue;
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
 use std::cmp::Ordering;
-use std::fmt::{Binary, Debug, Formatter, LowerHex, UpperHex};
+use std::fmt::{Binary, Debug, Display, Formatter, LowerHex, UpperHex};
 use std::hash::Hasher;
 use std::num::Wrapping;
 
@@ -532,7 +536,23 @@ fn test_cast_from_biguint() {
     println!("y = {:x}", y);
 }
 
-/// Allows you to format a [Bits] as a binary string
+/// Allows you to format a [Bits] as a decimal number
+/// ```
+/// # use rust_hdl::core::bits::Bits;
+/// let y = Bits::<16>::from(0x00fe);
+/// assert_eq!(format!("y = {}", y), "y = 254");
+/// ```
+impl<const N: usize> Display for Bits<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bits::Short(x) => Display::fmt(&x.short(), f),
+            Bits::Long(_) => Display::fmt(&BigUint::from(*self), f),
+        }
+    }
+}
+
+/// Allows you to format a [Bits] as a binary string.  All [N] bits are printed (as
+/// the Verilog `%b` does), with a `0b` prefix for `{:#b}`.
 /// ```
 /// # use rust_hdl::core::bits::Bits;
 /// let y = Bits::<16>::from(0b1011_0100_0010_0000);
@@ -540,6 +560,9 @@ fn test_cast_from_biguint() {
 /// ```
 impl<const N: usize> Binary for Bits<N> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "0b")?;
+        }
         for i in 0..N {
             if self.get_bit(N - 1 - i) {
                 write!(f, "1")?;
@@ -558,7 +581,8 @@ fn test_print_as_binary() {
     assert_eq!(p, "x = 1011010010000000")
 }
 
-/// Allows you to format a [Bits] as a lowercase hex string
+/// Allows you to format a [Bits] as a lowercase hex string.  All the nibbles are
+/// printed (as the Verilog `%h` does), with a `0x` prefix for `{:#x}`.
 /// ```
 /// # use rust_hdl::core::bits::Bits;
 /// let y = Bits::<16>::from(0xcafe);
@@ -566,12 +590,15 @@ fn test_print_as_binary() {
 /// ```
 impl<const N: usize> LowerHex for Bits<N> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
         let m: usize = N + (4 - (N % 4)) % 4; // Round up to an integer number of nibbles
         let digits: usize = m / 4;
         for digit in 0..digits {
             let nibble: Bits<4> = self.get_bits(4 * (digits - 1 - digit));
             let nibble_u8: LiteralType = nibble.into();
-            std::fmt::LowerHex::fmt(&nibble_u8, f)?;
+            write!(f, "{:x}", nibble_u8)?;
         }
         Ok(())
     }
@@ -584,6 +611,16 @@ fn test_print_as_lowercase_hex() {
     assert_eq!(p, "x = cafe");
 }
 
+#[test]
+fn test_print_with_leading_zeros() {
+    let x = Bits::<12>::from(0x2a);
+    assert_eq!(format!("{:x} {:#x}", x, x), "02a 0x02a");
+    assert_eq!(format!("{:b} {:#b}", x, x), "000000101010 0b000000101010");
+    assert_eq!(format!("{}", x), "42");
+    let y = Bits::<80>::from(0x2a);
+    assert_eq!(format!("{}", y), "42");
+}
+
 /// Allows you to format a [Bits] as an uppercase hex string
 /// ```
 /// # use rust_hdl::core::bits::Bits;
@@ -626,11 +663,35 @@ fn test_print_as_uppercase_hex() {
 /// let x: Bits<14> = 0xDEA.into();
 /// assert_eq!("0dea", format!("{:x}", x))
 /// ```
+/// For wider values, use [ToBits] on a [u128] (e.g., `0xDEAD_u128.to_bits()`), or [hex_bits].
 pub fn bits<const N: usize>(x: LiteralType) -> Bits<N> {
     let t: Bits<N> = x.into();
     t
 }
 
+/// Construct [Bits] from a string of hex digits.  This is the way to write constants
+/// that are too wide for a [LiteralType] (or even a [u128]).  The string can start with
+/// `0x`, and can contain `_` separators.  It will panic if the string is not valid hex,
+/// or if the value does not fit in `N` bits.  In HDL code, the value is computed when the
+/// Verilog is generated, so the string must be a literal or constant.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let x: Bits<136> = hex_bits("0xAB_DEAD_BEEF_CAFE_BABE_1234_5678_9ABC_DEF0");
+/// assert_eq!(x.get_bits::<8>(128), bits(0xAB));
+/// assert_eq!(x.get_bits::<128>(0), 0xDEAD_BEEF_CAFE_BABE_1234_5678_9ABC_DEF0_u128.to_bits());
+/// ```
+pub fn hex_bits<const N: usize>(x: &str) -> Bits<N> {
+    let digits = x
+        .trim_start_matches("0x")
+        .chars()
+        .filter(|c| *c != '_')
+        .collect::<String>();
+    match BigUint::parse_bytes(digits.as_bytes(), 16) {
+        Some(val) => val.into(),
+        None => panic!("{} is not a valid hex value", x),
+    }
+}
+
 /// The [ToBits] trait is used to provide a way to convert Rust standard unsigned
 /// types (currently `u8, u16, u32, u64, u128`) into [Bits] of different lengths.
 /// Note that RustHDL will panic if you attempt to convert an unsigned type into
@@ -744,6 +805,300 @@ pub fn bit_cast<const M: usize, const N: usize>(x: Bits<N>) -> Bits<M> {
     }
 }
 
+/// Reverse the order of the bits in a [Bits], so that the most significant bit
+/// becomes the least significant bit, and so on.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let x: Bits<8> = bits(0b1100_0101);
+/// assert_eq!(reverse_bits::<8>(x), bits(0b1010_0011));
+/// ```
+pub fn reverse_bits<const N: usize>(x: Bits<N>) -> Bits<N> {
+    match x {
+        Bits::Short(t) => {
+            let t: ShortType = t.into();
+            let k: ShortType = t.reverse_bits() >> (SHORT_BITS - N);
+            Bits::Short(k.into())
+        }
+        Bits::Long(_) => {
+            let mut ret = Bits::default();
+            for i in 0..N {
+                if x.get_bit(i) {
+                    ret = ret.replace_bit(N - 1 - i, true);
+                }
+            }
+            ret
+        }
+    }
+}
+
+/// Rotate a [Bits] to the left by `n` bits, so that the bits shifted off the
+/// most significant end come back in at the least significant end.  The amount
+/// must be less than `N`.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let x: Bits<16> = bits(0xDEAD);
+/// assert_eq!(rotate_left::<16>(x, 4), bits(0xEADD));
+/// ```
+pub fn rotate_left<const N: usize>(x: Bits<N>, n: usize) -> Bits<N> {
+    assert!(n < N, "Cannot rotate a Bits::<{}> by {} bits", N, n);
+    if n == 0 {
+        x
+    } else {
+        (x << n as LiteralType) | (x >> (N - n) as LiteralType)
+    }
+}
+
+/// Rotate a [Bits] to the right by `n` bits, so that the bits shifted off the
+/// least significant end come back in at the most significant end.  The amount
+/// must be less than `N`.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let x: Bits<16> = bits(0xDEAD);
+/// assert_eq!(rotate_right::<16>(x, 4), bits(0xDDEA));
+/// ```
+pub fn rotate_right<const N: usize>(x: Bits<N>, n: usize) -> Bits<N> {
+    assert!(n < N, "Cannot rotate a Bits::<{}> by {} bits", N, n);
+    if n == 0 {
+        x
+    } else {
+        (x >> n as LiteralType) | (x << (N - n) as LiteralType)
+    }
+}
+
+/// Count the number of ones in a [Bits].  The count is returned as a
+/// [Bits<M>], which must be wide enough to hold `N`.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let x: Bits<12> = bits(0b1100_0100_1100);
+/// assert_eq!(count_ones::<4, 12>(x), bits(5));
+/// ```
+pub fn count_ones<const M: usize, const N: usize>(x: Bits<N>) -> Bits<M> {
+    assert!(clog2(N + 1) <= M, "Bits::<{}> cannot hold {}", M, N);
+    let count = match x {
+        Bits::Short(t) => t.short().count_ones() as usize,
+        Bits::Long(_) => (0..N).filter(|i| x.get_bit(*i)).count(),
+    };
+    bits(count as LiteralType)
+}
+
+/// Count the number of zeros above the most significant one in a [Bits].  If
+/// the value is zero, the count is `N`.  The count is returned as a [Bits<M>],
+/// which must be wide enough to hold `N`.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let x: Bits<12> = bits(0b0000_0100_1100);
+/// assert_eq!(leading_zeros::<4, 12>(x), bits(5));
+/// assert_eq!(leading_zeros::<4, 12>(bits(0)), bits(12));
+/// ```
+pub fn leading_zeros<const M: usize, const N: usize>(x: Bits<N>) -> Bits<M> {
+    assert!(clog2(N + 1) <= M, "Bits::<{}> cannot hold {}", M, N);
+    let count = match x {
+        Bits::Short(t) => t.short().leading_zeros() as usize - (SHORT_BITS - N),
+        Bits::Long(_) => (0..N).take_while(|i| !x.get_bit(N - 1 - i)).count(),
+    };
+    bits(count as LiteralType)
+}
+
+/// Build a [Bits<M>] out of copies of a [Bits<N>].  `M` must be a
+/// multiple of `N`.  This is the Verilog replication operator, and is handy
+/// for building masks.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let x: Bits<4> = bits(0xA);
+/// assert_eq!(replicate::<16, 4>(x), bits(0xAAAA));
+/// let y: Bits<8> = replicate::<8, 1>(true.into());
+/// assert_eq!(y, bits(0xFF));
+/// ```
+pub fn replicate<const M: usize, const N: usize>(x: Bits<N>) -> Bits<M> {
+    assert_eq!(
+        M % N,
+        0,
+        "Cannot build a Bits::<{}> out of Bits::<{}>",
+        M,
+        N
+    );
+    let mut ret = Bits::<M>::default();
+    for i in 0..M / N {
+        ret.set_bits::<N>(i * N, x);
+    }
+    ret
+}
+
+/// Concatenate [Bits] values (and `bool`s), with the first argument ending up
+/// in the most significant bits.  The width of the result is inferred from where
+/// it is used, and must match the total width of the arguments.  This is the
+/// Verilog concatenation operator, and in HDL code, the arguments must all have
+/// a definite width, so literals must be written as `bits::<N>(..)`.  It is not called
+/// `concat!` to avoid clashing with the standard library macro.
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let start = true;
+/// let data: Bits<8> = bits(0xA5);
+/// let parity: Bits<2> = bits(0b01);
+/// let frame: Bits<11> = concat_bits!(start, data, parity);
+/// assert_eq!(frame, bits(0b1_1010_0101_01));
+/// ```
+#[macro_export]
+macro_rules! concat_bits {
+    ($($x: expr),+ $(,)?) => {
+        $crate::core::bits::Concat::default()$(.push($x))+.finish()
+    };
+}
+
+#[doc(hidden)]
+pub trait ConcatPart: Copy {
+    const WIDTH: usize;
+    fn widen<const N: usize>(self) -> Bits<N>;
+}
+
+impl<const M: usize> ConcatPart for Bits<M> {
+    const WIDTH: usize = M;
+    fn widen<const N: usize>(self) -> Bits<N> {
+        bit_cast(self)
+    }
+}
+
+impl ConcatPart for bool {
+    const WIDTH: usize = 1;
+    fn widen<const N: usize>(self) -> Bits<N> {
+        bits(self as LiteralType)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Default)]
+pub struct Concat<const N: usize> {
+    value: Bits<N>,
+    width: usize,
+}
+
+impl<const N: usize> Concat<N> {
+    pub fn push<T: ConcatPart>(mut self, x: T) -> Self {
+        self.width += T::WIDTH;
+        assert!(
+            self.width <= N,
+            "The arguments to concat_bits! are wider than the Bits::<{}> result",
+            N
+        );
+        self.value = self.value | (x.widen::<N>() << (N - self.width) as LiteralType);
+        self
+    }
+    pub fn finish(self) -> Bits<N> {
+        assert_eq!(
+            self.width, N,
+            "The arguments to concat_bits! do not fill the Bits::<{}> result",
+            N
+        );
+        self.value
+    }
+}
+
+// The Verilog functions for the intrinsics above that depend on the width of their
+// argument.  These are called by the code generated by `hdl_gen`.
+
+fn bit_of(name: &str, ndx: usize) -> VerilogExpression {
+    VerilogExpression::Index(
+        Box::new(VerilogExpression::Signal(name.into())),
+        Box::new(VerilogExpression::Literal(ndx.into())),
+    )
+}
+
+fn bits_function<const M: usize, const N: usize>(
+    name: String,
+    args: Vec<VerilogFunctionVariable>,
+    block: Vec<VerilogStatement>,
+) -> VerilogFunction {
+    VerilogFunction {
+        result: VerilogFunctionVariable::new::<Bits<M>>(&name),
+        args: [vec![VerilogFunctionVariable::new::<Bits<N>>("x")], args].concat(),
+        locals: vec![],
+        block,
+        name,
+    }
+}
+
+#[doc(hidden)]
+pub fn reverse_bits_hdl<const N: usize>() -> VerilogFunction {
+    let name = format!("reverse_bits${}", N);
+    let value = VerilogExpression::Concat((0..N).map(|i| bit_of("x", i)).collect());
+    let block = vec![VerilogStatement::Assignment(
+        VerilogExpression::Signal(name.clone()),
+        value,
+    )];
+    bits_function::<N, N>(name, vec![], block)
+}
+
+fn rotate_hdl<const N: usize>(base: &str, first: VerilogOp, second: VerilogOp) -> VerilogFunction {
+    let name = format!("{}${}", base, N);
+    let x = || Box::new(VerilogExpression::Signal("x".into()));
+    let n = || Box::new(VerilogExpression::Signal("n".into()));
+    let paren = |x: VerilogExpression| Box::new(VerilogExpression::Paren(Box::new(x)));
+    let rest = paren(VerilogExpression::Binary(
+        Box::new(VerilogExpression::Literal(N.into())),
+        VerilogOp::Sub,
+        n(),
+    ));
+    let value = VerilogExpression::Binary(
+        paren(VerilogExpression::Binary(x(), first, n())),
+        VerilogOp::BitOr,
+        paren(VerilogExpression::Binary(x(), second, rest)),
+    );
+    let block = vec![VerilogStatement::Assignment(
+        VerilogExpression::Signal(name.clone()),
+        value,
+    )];
+    bits_function::<N, N>(
+        name,
+        vec![VerilogFunctionVariable::new::<Bits<LITERAL_BITS>>("n")],
+        block,
+    )
+}
+
+#[doc(hidden)]
+pub fn rotate_left_hdl<const N: usize>() -> VerilogFunction {
+    rotate_hdl::<N>("rotate_left", VerilogOp::Shl, VerilogOp::Shr)
+}
+
+#[doc(hidden)]
+pub fn rotate_right_hdl<const N: usize>() -> VerilogFunction {
+    rotate_hdl::<N>("rotate_right", VerilogOp::Shr, VerilogOp::Shl)
+}
+
+#[doc(hidden)]
+pub fn count_ones_hdl<const M: usize, const N: usize>() -> VerilogFunction {
+    let name = format!("count_ones${}${}", M, N);
+    // The sum is as wide as the function result, so it cannot overflow
+    let value = (1..N).fold(bit_of("x", 0), |acc, i| {
+        VerilogExpression::Binary(Box::new(acc), VerilogOp::Add, Box::new(bit_of("x", i)))
+    });
+    let block = vec![VerilogStatement::Assignment(
+        VerilogExpression::Signal(name.clone()),
+        value,
+    )];
+    bits_function::<M, N>(name, vec![], block)
+}
+
+#[doc(hidden)]
+pub fn leading_zeros_hdl<const M: usize, const N: usize>() -> VerilogFunction {
+    let name = format!("leading_zeros${}${}", M, N);
+    let assign = |count: usize| {
+        VerilogStatement::Assignment(
+            VerilogExpression::Signal(name.clone()),
+            VerilogExpression::Literal(count.into()),
+        )
+    };
+    // Later assignments win, so the most significant one sets the count
+    let mut block = vec![assign(N)];
+    for i in 0..N {
+        block.push(VerilogStatement::If(VerilogConditional {
+            test: bit_of("x", i),
+            then: vec![assign(N - 1 - i)],
+            otherwise: VerilogBlockOrConditional::None,
+        }));
+    }
+    bits_function::<M, N>(name, vec![], block)
+}
+
 #[doc(hidden)]
 impl<const N: usize> Into<VCDValue> for Bits<N> {
     fn into(self) -> VCDValue {
@@ -964,7 +1319,7 @@ impl<const N: usize> Bits<N> {
         if N <= SHORT_BITS {
             Bits::Short(ShortBitVec::<N>::mask())
         } else {
-            Bits::Long([true; N].into())
+            Bits::Long(BitVec::mask())
         }
     }
 
@@ -1,69 +1,143 @@
-#[derive(Debug, Clone, Copy, Hash)]
+use std::convert::TryInto;
+
+// Bit vectors wider than a [ShortBitVec](crate::core::short_bit_vec::ShortBitVec) are
+// stored as little endian 64 bit limbs, so that the logic and arithmetic operate on a word
+// at a time.  Stable Rust cannot (yet) declare an array of `(N + 63) / 64` limbs (that
+// needs `generic_const_exprs`), and an array of length `N` takes at least `N` bytes, so
+// the limbs are packed into the first `(N + 7) / 8` bytes of an `N` byte array instead.
+// The remaining bytes, and the bits above `N`, are always zero.  The array is 8 byte
+// aligned, so reading or writing a limb is a single load or store.
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[repr(C, align(8))]
 pub struct BitVec<const N: usize> {
-    bits: [bool; N],
+    bytes: [u8; N],
 }
 
 impl<const N: usize> From<[bool; N]> for BitVec<N> {
     fn from(x: [bool; N]) -> Self {
-        BitVec { bits: x }
+        let mut ret = Self::zero();
+        for (ndx, bit) in x.iter().enumerate() {
+            if *bit {
+                ret.bytes[ndx / 8] |= 1 << (ndx % 8);
+            }
+        }
+        ret
     }
 }
 
 impl<const N: usize> BitVec<N> {
-    pub fn to_u128(&self) -> u128 {
-        assert!(N <= 128);
-        let mut ret = 0_u128;
-        for i in 0..N {
-            if self.bits[N - 1 - i] {
-                ret = ret | (1 << (N - 1 - i));
-            }
+    const LIMBS: usize = N.div_ceil(64);
+    const BYTES: usize = N.div_ceil(8);
+
+    fn zero() -> Self {
+        BitVec { bytes: [0; N] }
+    }
+
+    #[inline(always)]
+    fn limb(&self, ndx: usize) -> u64 {
+        if ndx >= Self::LIMBS {
+            return 0;
+        }
+        if N >= 8 {
+            // There is always room for a whole limb (and the bytes past the end are zero)
+            let start = ndx * 8;
+            return u64::from_le_bytes(self.bytes[start..start + 8].try_into().unwrap());
+        }
+        let mut buf = [0_u8; 8];
+        buf[..Self::BYTES].copy_from_slice(&self.bytes[..Self::BYTES]);
+        u64::from_le_bytes(buf)
+    }
+
+    // The limbs, least significant first, straight out of the array.  Only for vectors of
+    // at least 8 bits, which have room for all of their limbs.
+    #[inline(always)]
+    fn limbs(&self) -> impl DoubleEndedIterator<Item = u64> + '_ {
+        self.bytes[..Self::LIMBS * 8]
+            .chunks_exact(8)
+            .map(|x| u64::from_le_bytes(x.try_into().unwrap()))
+    }
+
+    // The bits of the last limb that are part of the vector
+    #[inline(always)]
+    fn top_mask() -> u64 {
+        match N % 64 {
+            0 => !0,
+            r => (1 << r) - 1,
+        }
+    }
+
+    #[inline(always)]
+    fn set_limb(&mut self, ndx: usize, mut val: u64) {
+        if ndx + 1 == Self::LIMBS {
+            val &= Self::top_mask();
+        }
+        let start = ndx * 8;
+        if N >= 8 {
+            self.bytes[start..start + 8].copy_from_slice(&val.to_le_bytes());
+        } else {
+            self.bytes[..Self::BYTES].copy_from_slice(&val.to_le_bytes()[..Self::BYTES]);
+        }
+    }
+
+    #[inline(always)]
+    fn from_limbs<F: Fn(usize) -> u64>(f: F) -> Self {
+        let mut ret = Self::zero();
+        for ndx in 0..Self::LIMBS {
+            ret.set_limb(ndx, f(ndx));
         }
         ret
     }
 
+    pub fn mask() -> Self {
+        Self::from_limbs(|_| !0)
+    }
+
+    pub fn to_u128(&self) -> u128 {
+        assert!(N <= 128);
+        self.low_u128()
+    }
+
+    fn low_u128(&self) -> u128 {
+        (self.limb(0) as u128) | ((self.limb(1) as u128) << 64)
+    }
+
     pub fn all(&self) -> bool {
-        for i in 0..N {
-            if !self.bits[i] {
-                return false;
-            }
-        }
-        true
+        (0..Self::LIMBS).all(|ndx| {
+            let mask = if ndx + 1 == Self::LIMBS {
+                Self::top_mask()
+            } else {
+                !0
+            };
+            self.limb(ndx) == mask
+        })
     }
 
     pub fn any(&self) -> bool {
-        for i in 0..N {
-            if self.bits[i] {
-                return true;
-            }
-        }
-        false
+        self.bytes[..Self::BYTES].iter().any(|x| *x != 0)
     }
 
     pub fn xor(&self) -> bool {
-        let mut ret = false;
-        for i in 0..N {
-            ret = ret ^ self.bits[i];
-        }
-        ret
+        (0..Self::LIMBS).fold(0, |acc, ndx| acc ^ self.limb(ndx).count_ones()) & 1 == 1
     }
 
     pub fn get_bit(&self, ndx: usize) -> bool {
         assert!(ndx < N);
-        self.bits[ndx]
+        self.bytes[ndx / 8] & (1 << (ndx % 8)) != 0
     }
 
     pub fn replace_bit(&self, ndx: usize, val: bool) -> BitVec<N> {
-        let mut t = self.bits.clone();
-        t[ndx] = val;
-        BitVec { bits: t }
+        assert!(ndx < N);
+        let mut t = *self;
+        if val {
+            t.bytes[ndx / 8] |= 1 << (ndx % 8);
+        } else {
+            t.bytes[ndx / 8] &= !(1 << (ndx % 8));
+        }
+        t
     }
 
     pub fn resize<const M: usize>(&self) -> BitVec<M> {
-        let mut t = [false; M];
-        for i in 0..M.min(N) {
-            t[i] = self.bits[i];
-        }
-        BitVec { bits: t }
+        BitVec::<M>::from_limbs(|ndx| self.limb(ndx))
     }
 }
 
@@ -71,11 +145,18 @@ impl<const N: usize> std::ops::Shr<usize> for BitVec<N> {
     type Output = BitVec<N>;
 
     fn shr(self, rhs: usize) -> Self::Output {
-        let mut bits = [false; N];
-        for i in rhs..N {
-            bits[i - rhs] = self.bits[i];
+        if rhs >= N {
+            return Self::zero();
         }
-        Self { bits }
+        let (words, bits) = (rhs / 64, rhs % 64);
+        Self::from_limbs(|ndx| {
+            let lo = self.limb(ndx + words);
+            if bits == 0 {
+                lo
+            } else {
+                (lo >> bits) | (self.limb(ndx + words + 1) << (64 - bits))
+            }
+        })
     }
 }
 
@@ -83,8 +164,7 @@ impl<const N: usize, const M: usize> std::ops::Shr<BitVec<M>> for BitVec<N> {
     type Output = BitVec<N>;
 
     fn shr(self, rhs: BitVec<M>) -> Self::Output {
-        let rhs_u32: usize = rhs.into();
-        self >> rhs_u32
+        self >> rhs.shift_amount()
     }
 }
 
@@ -92,11 +172,21 @@ impl<const N: usize> std::ops::Shl<usize> for BitVec<N> {
     type Output = BitVec<N>;
 
     fn shl(self, rhs: usize) -> Self::Output {
-        let mut bits = [false; N];
-        for i in rhs..N {
-            bits[i] = self.bits[i - rhs];
+        if rhs >= N {
+            return Self::zero();
         }
-        Self { bits }
+        let (words, bits) = (rhs / 64, rhs % 64);
+        Self::from_limbs(|ndx| {
+            if ndx < words {
+                return 0;
+            }
+            let hi = self.limb(ndx - words);
+            if bits == 0 || ndx == words {
+                hi << bits
+            } else {
+                (hi << bits) | (self.limb(ndx - words - 1) >> (64 - bits))
+            }
+        })
     }
 }
 
@@ -104,8 +194,7 @@ impl<const N: usize, const M: usize> std::ops::Shl<BitVec<M>> for BitVec<N> {
     type Output = BitVec<N>;
 
     fn shl(self, rhs: BitVec<M>) -> Self::Output {
-        let rhs: usize = rhs.into();
-        self << rhs
+        self << rhs.shift_amount()
     }
 }
 
@@ -137,38 +226,23 @@ impl<const N: usize> std::ops::Not for BitVec<N> {
     type Output = BitVec<N>;
 
     fn not(self) -> Self::Output {
-        let mut bits = [false; N];
-        for i in 0..N {
-            bits[i] = !self.bits[i];
-        }
-        Self { bits }
+        Self::from_limbs(|ndx| !self.limb(ndx))
     }
 }
 
-// Add with cary
-// A  B  C  | X  Co | A ^ (B ^ C) | A B + B C-IN + A C-IN
-// 0  0  0  | 0  0  | 0           | 0
-// 0  0  1  | 1  0  | 1           | 0
-// 0  1  0  | 1  0  | 1           | 0
-// 0  1  1  | 0  1  | 0           | 1
-// 1  0  0  | 1  0  | 1           | 0
-// 1  0  1  | 0  1  | 0           | 1
-// 1  1  0  | 0  1  | 0           | 1
-// 1  1  1  | 1  1  | 1           | 1
 impl<const N: usize> std::ops::Add<BitVec<N>> for BitVec<N> {
     type Output = BitVec<N>;
 
     fn add(self, rhs: BitVec<N>) -> Self::Output {
         let mut carry = false;
-        let mut bits = [false; N];
-        for i in 0..N {
-            let a = self.bits[i];
-            let b = rhs.bits[i];
-            let c_i = carry;
-            bits[i] = a ^ b ^ c_i;
-            carry = (a & b) | (b & c_i) | (a & c_i);
+        let mut ret = Self::zero();
+        for ndx in 0..Self::LIMBS {
+            let (sum, c1) = self.limb(ndx).overflowing_add(rhs.limb(ndx));
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            ret.set_limb(ndx, sum);
+            carry = c1 || c2;
         }
-        Self { bits }
+        ret
     }
 }
 
@@ -176,111 +250,102 @@ impl<const N: usize> std::ops::Sub<BitVec<N>> for BitVec<N> {
     type Output = BitVec<N>;
 
     fn sub(self, rhs: BitVec<N>) -> Self::Output {
-        self + !rhs + 1_u32.into()
-    }
-}
-
-impl<const N: usize> std::cmp::PartialEq for BitVec<N> {
-    fn eq(&self, other: &Self) -> bool {
-        for i in 0..N {
-            if self.bits[i] != other.bits[i] {
-                return false;
-            }
+        let mut borrow = false;
+        let mut ret = Self::zero();
+        for ndx in 0..Self::LIMBS {
+            let (diff, b1) = self.limb(ndx).overflowing_sub(rhs.limb(ndx));
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            ret.set_limb(ndx, diff);
+            borrow = b1 || b2;
         }
-        true
+        ret
     }
 }
 
 impl<const N: usize> std::cmp::PartialOrd for BitVec<N> {
     fn partial_cmp(&self, other: &BitVec<N>) -> Option<std::cmp::Ordering> {
-        for i in 0..N {
-            let a = self.bits[N - 1 - i];
-            let b = other.bits[N - 1 - i];
-            if a & !b {
-                return Some(std::cmp::Ordering::Greater);
-            }
-            if !a & b {
-                return Some(std::cmp::Ordering::Less);
-            }
+        if N < 8 {
+            // Everything is in the first byte
+            return self.bytes.partial_cmp(&other.bytes);
         }
-        Some(std::cmp::Ordering::Equal)
+        Some(self.limbs().rev().cmp(other.limbs().rev()))
     }
 }
 
 impl<const N: usize> BitVec<N> {
+    // The logical operations work a byte at a time (which the compiler vectorizes)
     fn binop<T>(&self, rhs: &Self, op: T) -> Self
     where
-        T: Fn(&bool, &bool) -> bool,
+        T: Fn(u8, u8) -> u8,
     {
-        let mut bits = [false; N];
-        for i in 0..N {
-            bits[i] = op(&self.bits[i], &rhs.bits[i]);
+        let mut ret = *self;
+        for (x, y) in ret.bytes[..Self::BYTES]
+            .iter_mut()
+            .zip(&rhs.bytes[..Self::BYTES])
+        {
+            *x = op(*x, *y);
+        }
+        ret
+    }
+
+    // Shift amounts that do not fit in a usize shift everything out
+    fn shift_amount(&self) -> usize {
+        if (1..Self::LIMBS).any(|ndx| self.limb(ndx) != 0) || self.limb(0) > usize::MAX as u64 {
+            usize::MAX
+        } else {
+            self.limb(0) as usize
         }
-        Self { bits }
     }
 }
 
+// Signed values are sign extended to the width of the vector
 macro_rules! define_vec_from_uint {
-    ($name:ident) => {
+    ($name:ident, $wide: ident) => {
         impl<const N: usize> From<$name> for BitVec<N> {
-            fn from(mut x: $name) -> Self {
-                let mut bits = [false; N];
-                for i in 0..N {
-                    bits[i] = (x & 1) != 0;
-                    x = x >> 1;
-                }
-                Self { bits }
+            fn from(x: $name) -> Self {
+                let x = x as $wide;
+                let fill = if x < (0 as $wide) { !0 } else { 0 };
+                Self::from_limbs(|ndx| match ndx {
+                    0 => x as u64,
+                    1 => (x >> 64) as u64,
+                    _ => fill,
+                })
             }
         }
     };
 }
 
-define_vec_from_uint!(u8);
-define_vec_from_uint!(u16);
-define_vec_from_uint!(u32);
-define_vec_from_uint!(u64);
-define_vec_from_uint!(u128);
-define_vec_from_uint!(usize);
-define_vec_from_uint!(i8);
-define_vec_from_uint!(i16);
-define_vec_from_uint!(i32);
-define_vec_from_uint!(i64);
-define_vec_from_uint!(i128);
-
+define_vec_from_uint!(u8, u128);
+define_vec_from_uint!(u16, u128);
+define_vec_from_uint!(u32, u128);
+define_vec_from_uint!(u64, u128);
+define_vec_from_uint!(u128, u128);
+define_vec_from_uint!(usize, u128);
+define_vec_from_uint!(i8, i128);
+define_vec_from_uint!(i16, i128);
+define_vec_from_uint!(i32, i128);
+define_vec_from_uint!(i64, i128);
+define_vec_from_uint!(i128, i128);
+
+// Unsigned conversions keep the least significant bits
 macro_rules! define_uint_from_vec {
     ($name:ident, $width: expr) => {
         impl<const N: usize> From<BitVec<N>> for $name {
             fn from(t: BitVec<N>) -> Self {
-                let mut x: $name = 0;
-                for i in 0..N {
-                    x = x << 1;
-                    x = x | if t.bits[N - 1 - i] { 1 } else { 0 }
-                }
-                x
+                t.low_u128() as $name
             }
         }
     };
 }
 
+// Signed conversions treat the vector as a twos complement value
 macro_rules! define_int_from_vec {
     ($name: ident, $width: expr) => {
         impl<const N: usize> From<BitVec<N>> for $name {
             fn from(t: BitVec<N>) -> Self {
                 assert!(N <= $width);
-                let mut x: $name = 0;
-                if t.bits[N - 1] {
-                    for i in 0..N {
-                        x = x << 1;
-                        x = x | if t.bits[N - 1 - i] { 0 } else { 1 }
-                    }
-                    x = -x + 1
-                } else {
-                    for i in 0..N {
-                        x = x << 1;
-                        x = x | if t.bits[N - 1 - i] { 1 } else { 0 }
-                    }
-                }
-                x
+                let shift = 128 - N;
+                (((t.low_u128() << shift) as i128) >> shift) as $name
             }
         }
     };
@@ -306,6 +371,9 @@ define_int_from_vec!(i128, 128);
 mod tests {
     use std::num::Wrapping;
 
+    use num_bigint::BigUint;
+    use rand::random;
+
     use super::BitVec;
 
     #[test]
@@ -408,9 +476,73 @@ mod tests {
         assert_ne!(a, c)
     }
     #[test]
+    fn cmp_works() {
+        let a: BitVec<4> = 3_u32.into();
+        let b: BitVec<4> = 12_u32.into();
+        assert!(a < b);
+        assert!(b > a);
+        let c: BitVec<64> = 0x8000_0000_0000_0000_u64.into();
+        let d: BitVec<64> = 0x7FFF_FFFF_FFFF_FFFF_u64.into();
+        assert!(c > d);
+        assert!(d <= c);
+    }
+    #[test]
     fn all_works() {
         let a: BitVec<48> = 0xFFFF_FFFF_FFFF_u64.into();
         assert!(a.all());
         assert!(a.any());
     }
+
+    fn wide(x: &BigUint) -> BitVec<200> {
+        let mut ret = [false; 200];
+        for (ndx, bit) in ret.iter_mut().enumerate() {
+            *bit = x.bit(ndx as u64);
+        }
+        ret.into()
+    }
+
+    fn big(x: BitVec<200>) -> BigUint {
+        let mut ret = BigUint::default();
+        for ndx in 0..200 {
+            ret.set_bit(ndx as u64, x.get_bit(ndx));
+        }
+        ret
+    }
+
+    #[test]
+    fn wide_ops_match_biguint() {
+        let modulus = BigUint::from(1_u32) << 200;
+        for _ in 0..100 {
+            let x = BigUint::from_bytes_le(&random::<[u8; 25]>());
+            let y = BigUint::from_bytes_le(&random::<[u8; 25]>());
+            let (a, b) = (wide(&x), wide(&y));
+            assert_eq!(big(a + b), (&x + &y) % &modulus);
+            assert_eq!(big(a - b), (&x + &modulus - &y) % &modulus);
+            assert_eq!(big(a ^ b), &x ^ &y);
+            assert_eq!(big(!a), &modulus - 1_u32 - &x);
+            assert_eq!(a < b, x < y);
+            for shift in [0, 1, 63, 64, 65, 130, 199, 200] {
+                assert_eq!(big(a >> shift), &x >> shift);
+                assert_eq!(big(a << shift), (&x << shift) % &modulus);
+            }
+            assert_eq!(a.xor(), x.count_ones() % 2 == 1);
+        }
+    }
+
+    #[test]
+    fn wide_conversions() {
+        let a: BitVec<100> = (-2_i32).into();
+        assert!(!a.get_bit(0));
+        assert!(a.get_bit(99));
+        assert_eq!(a + 2_u8.into(), 0_u8.into());
+        let b: BitVec<72> = 0xAB_CDEF_0123_4567_89AB_u128.into();
+        assert_eq!(b.to_u128(), 0xAB_CDEF_0123_4567_89AB);
+        assert_eq!(u64::from(b), 0xCDEF_0123_4567_89AB);
+        assert_eq!(b.resize::<40>().to_u128(), 0x23_4567_89AB);
+        assert_eq!(u128::from(b.resize::<136>()), 0xAB_CDEF_0123_4567_89AB);
+        let c: BitVec<40> = 0xFF_FFFF_FFFE_u64.into();
+        assert_eq!(i64::from(c), -2);
+        assert!(BitVec::<72>::mask().all());
+        assert!(!BitVec::<72>::mask().replace_bit(71, false).all());
+    }
 }
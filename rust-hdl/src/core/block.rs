@@ -1,11 +1,24 @@
+pub use crate::core::attribute::{Attribute, BlockAttribute};
 use crate::core::logic::Logic;
-use crate::core::probe::Probe;
+pub use crate::core::parameter::{BlockParameters, Parameter, ParameterBinding};
+use crate::core::probe::{Probe, ProbeMut};
 
 pub trait Block: Logic {
     fn connect_all(&mut self);
     fn update_all(&mut self);
     fn has_changed(&self) -> bool;
     fn accept(&self, name: &str, probe: &mut dyn Probe);
+    /// Visit the signals of the block with mutable access to them.  This is implemented
+    /// by `#[derive(LogicBlock)]`.  Hand written blocks that do not implement it are
+    /// skipped, so their signals cannot be set with
+    /// [force_signal](crate::core::signal_path::force_signal).
+    fn accept_mut(&mut self, _name: &str, _probe: &mut dyn ProbeMut) {}
+    fn attributes(&self) -> Vec<BlockAttribute> {
+        vec![]
+    }
+    fn parameters(&self) -> Option<BlockParameters> {
+        None
+    }
 }
 
 impl<B: Block> Block for Vec<B> {
@@ -36,6 +49,13 @@ impl<B: Block> Block for Vec<B> {
             x.1.accept(&name, probe);
         }
     }
+
+    fn accept_mut(&mut self, name: &str, probe: &mut dyn ProbeMut) {
+        for x in self.iter_mut().enumerate() {
+            let name = format!("{}${}", name, x.0);
+            x.1.accept_mut(&name, probe);
+        }
+    }
 }
 
 impl<B: Block, const P: usize> Block for [B; P] {
@@ -66,4 +86,43 @@ impl<B: Block, const P: usize> Block for [B; P] {
             x.1.accept(&name, probe);
         }
     }
+
+    fn accept_mut(&mut self, name: &str, probe: &mut dyn ProbeMut) {
+        for x in self.iter_mut().enumerate() {
+            let name = format!("{}${}", name, x.0);
+            x.1.accept_mut(&name, probe);
+        }
+    }
+}
+
+// A boxed block behaves like the block itself, so that designs can be
+// handled as `Box<dyn Block>` (e.g., by the command line driver).
+impl<B: Block + ?Sized> Block for Box<B> {
+    fn connect_all(&mut self) {
+        self.as_mut().connect_all()
+    }
+
+    fn update_all(&mut self) {
+        self.as_mut().update_all()
+    }
+
+    fn has_changed(&self) -> bool {
+        self.as_ref().has_changed()
+    }
+
+    fn accept(&self, name: &str, probe: &mut dyn Probe) {
+        self.as_ref().accept(name, probe)
+    }
+
+    fn accept_mut(&mut self, name: &str, probe: &mut dyn ProbeMut) {
+        self.as_mut().accept_mut(name, probe)
+    }
+
+    fn attributes(&self) -> Vec<BlockAttribute> {
+        self.as_ref().attributes()
+    }
+
+    fn parameters(&self) -> Option<BlockParameters> {
+        self.as_ref().parameters()
+    }
 }
@@ -333,38 +333,31 @@ impl Probe for TimingChecker {
             }
             _ => {}
         }
-        let descriptor = &signal.descriptor();
-        match &descriptor.kind {
-            TypeKind::Enum(x) => {
-                for label in x {
-                    let label = label.replace("::", "$");
-                    let my_id = self.graph.add_signal_node(&SignalNode {
-                        name: format!("{}${}", module_path, label),
-                        kind: SignalNodeKind::Normal,
-                    });
-                    self.graph.add_signal_edge(
-                        &SignalNode {
-                            name: format!("const${}", label),
-                            kind: SignalNodeKind::Source,
-                        },
-                        my_id,
-                        SignalEdgeKind::Constant,
-                    );
-                    let my_id = self.graph.add_signal_node(&SignalNode {
-                        name: format!("{}${}", self.path.parent(), label),
-                        kind: SignalNodeKind::Normal,
-                    });
-                    self.graph.add_signal_edge(
-                        &SignalNode {
-                            name: format!("const${}", label),
-                            kind: SignalNodeKind::Source,
-                        },
-                        my_id,
-                        SignalEdgeKind::Constant,
-                    );
-                }
-            }
-            _ => {}
+        for label in enum_labels(&signal.descriptor().kind) {
+            let my_id = self.graph.add_signal_node(&SignalNode {
+                name: format!("{}${}", module_path, label),
+                kind: SignalNodeKind::Normal,
+            });
+            self.graph.add_signal_edge(
+                &SignalNode {
+                    name: format!("const${}", label),
+                    kind: SignalNodeKind::Source,
+                },
+                my_id,
+                SignalEdgeKind::Constant,
+            );
+            let my_id = self.graph.add_signal_node(&SignalNode {
+                name: format!("{}${}", self.path.parent(), label),
+                kind: SignalNodeKind::Normal,
+            });
+            self.graph.add_signal_edge(
+                &SignalNode {
+                    name: format!("const${}", label),
+                    kind: SignalNodeKind::Source,
+                },
+                my_id,
+                SignalEdgeKind::Constant,
+            );
         }
     }
     fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
@@ -375,6 +368,16 @@ impl Probe for TimingChecker {
     }
 }
 
+// The names of the enum variants (as Verilog localparams) in a type, including those
+// nested in composite types
+fn enum_labels(kind: &TypeKind) -> Vec<String> {
+    match kind {
+        TypeKind::Enum(x) => x.iter().map(|x| x.name.replace("::", "$")).collect(),
+        TypeKind::Composite(x) => x.iter().flat_map(|x| enum_labels(&x.kind.kind)).collect(),
+        _ => vec![],
+    }
+}
+
 pub fn check_timing<U: Block>(uut: &U) {
     let mut scan = TimingChecker::default();
     uut.accept("top", &mut scan);
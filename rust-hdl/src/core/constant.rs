@@ -1,10 +1,11 @@
 use crate::core::ast::VerilogLiteral;
-use crate::core::atom::{Atom, AtomKind};
+use crate::core::atom::{Atom, AtomKind, AtomMut};
 use crate::core::block::Block;
 use crate::core::constraint::PinConstraint;
+use crate::core::four_state::UnknownMask;
 use crate::core::logic::Logic;
 use crate::core::prelude::TypeDescriptor;
-use crate::core::probe::Probe;
+use crate::core::probe::{Probe, ProbeMut};
 use crate::core::signal::get_signal_id;
 use crate::core::synth::{Synth, VCDValue};
 
@@ -24,6 +25,14 @@ impl<T: Synth> Constant<T> {
     pub fn val(&self) -> T {
         self.val
     }
+    /// Constants are never unknown (see [four_state](crate::core::four_state))
+    pub fn is_unknown(&self) -> bool {
+        false
+    }
+    /// Constants have no unknown bits
+    pub fn unknown_mask(&self) -> UnknownMask {
+        UnknownMask::default()
+    }
 }
 
 impl<T: Synth> Logic for Constant<T> {
@@ -70,6 +79,12 @@ impl<T: Synth> Atom for Constant<T> {
     }
 }
 
+impl<T: Synth> AtomMut for Constant<T> {
+    fn force(&mut self, _value: &VCDValue) -> bool {
+        false
+    }
+}
+
 impl<T: Synth> Block for Constant<T> {
     fn connect_all(&mut self) {}
 
@@ -82,4 +97,8 @@ impl<T: Synth> Block for Constant<T> {
     fn accept(&self, name: &str, probe: &mut dyn Probe) {
         probe.visit_atom(name, self);
     }
+
+    fn accept_mut(&mut self, name: &str, probe: &mut dyn ProbeMut) {
+        probe.visit_atom(name, self);
+    }
 }
@@ -0,0 +1,584 @@
+//! Fixed point numbers
+//!
+//! A [Fixed] value with `I` integer bits (including the sign bit) and `F` fraction bits is
+//! stored as an `I + F` bit twos complement integer, scaled by `2^-F`.  [UFixed] is the
+//! unsigned equivalent.  Values of the same type can be added and subtracted (with the
+//! usual wrap around), and compared.  Anything that changes the number of integer or fraction
+//! bits goes through [fixed_cast] or [fixed_mul] (or their unsigned counterparts), which take
+//! the [Rounding] and [Overflow] behavior to use.  Just like [bit_cast](crate::core::bits::bit_cast),
+//! these need all of the widths spelled out, so that the HDL kernel can generate the right
+//! shifts and sign extension:
+//! ```
+//! # use rust_hdl::core::prelude::*;
+//! let gain = Fixed::<2, 14>::from_f64(0.75);
+//! let x = Fixed::<4, 12>::from_f64(-2.5);
+//! // The full product has 6 integer and 26 fraction bits
+//! let y = fixed_mul::<4, 12, 2, 14, 4, 12>(gain, x, Rounding::Nearest, Overflow::Saturate);
+//! assert_eq!(y.to_f64(), -1.875);
+//! ```
+//! Constants are easiest to build with [Fixed::from_f64], which rounds to the nearest
+//! representable value, and saturates if the value is out of range.
+use crate::core::ast::{
+    VerilogBlockOrConditional, VerilogConditional, VerilogExpression, VerilogFunction,
+    VerilogFunctionVariable, VerilogLiteral, VerilogOp, VerilogOpUnary, VerilogStatement,
+};
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, BitAnd, Shl, Shr};
+
+/// How the fraction bits dropped by a conversion are handled
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Rounding {
+    /// Drop the bits (i.e., round towards negative infinity)
+    Truncate,
+    /// Round to the nearest value (with ties rounded up)
+    Nearest,
+}
+
+/// What happens when the result of a conversion does not fit in the integer bits
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Overflow {
+    /// Keep the low bits of the result
+    Wrap,
+    /// Clamp the result to the largest (or smallest) value of the type
+    Saturate,
+}
+
+/// A signed fixed point number with `I` integer bits (including the sign bit) and `F`
+/// fraction bits.  At most 64 bits in total are supported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+pub struct Fixed<const I: usize, const F: usize>(i64);
+
+/// An unsigned fixed point number with `I` integer bits and `F` fraction bits.  At most
+/// 64 bits in total are supported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+pub struct UFixed<const I: usize, const F: usize>(u64);
+
+// The range of a `bits` wide integer
+fn limits(bits: usize, signed: bool) -> (i128, i128) {
+    if signed {
+        (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+    } else {
+        (0, (1 << bits) - 1)
+    }
+}
+
+// Keep the low `bits` of `x`, and sign extend them if the type is signed
+fn wrap(x: i128, bits: usize, signed: bool) -> i128 {
+    let x = x & ((1 << bits) - 1);
+    if signed && (x >> (bits - 1)) != 0 {
+        x - (1 << bits)
+    } else {
+        x
+    }
+}
+
+// Move the binary point of `x` from `from_frac` to `frac` fraction bits.  Rounding adds
+// the last bit shifted out, which is the same as adding a half before shifting (as the
+// Verilog does), but cannot overflow for the 128 bit product of two unsigned values.
+fn rescale<T>(x: T, from_frac: usize, frac: usize, rounding: Rounding) -> T
+where
+    T: Copy
+        + From<u8>
+        + Add<Output = T>
+        + BitAnd<Output = T>
+        + Shl<usize, Output = T>
+        + Shr<usize, Output = T>,
+{
+    if from_frac > frac {
+        let x = x >> (from_frac - frac - 1);
+        match rounding {
+            Rounding::Truncate => x >> 1,
+            Rounding::Nearest => (x >> 1) + (x & T::from(1)),
+        }
+    } else {
+        x << (frac - from_frac)
+    }
+}
+
+// Convert a raw value with `from_frac` fraction bits into a `bits` wide value with
+// `frac` fraction bits.  This is the same calculation as the Verilog built by `resize`.
+fn convert(
+    raw: i128,
+    from_frac: usize,
+    bits: usize,
+    frac: usize,
+    rounding: Rounding,
+    overflow: Overflow,
+) -> i128 {
+    let x = rescale(raw, from_frac, frac, rounding);
+    match overflow {
+        Overflow::Wrap => wrap(x, bits, true),
+        Overflow::Saturate => {
+            let (min, max) = limits(bits, true);
+            x.clamp(min, max)
+        }
+    }
+}
+
+// The unsigned version of `convert`, which takes all 128 bits of the product of two
+// 64 bit values.
+fn convert_unsigned(
+    raw: u128,
+    from_frac: usize,
+    bits: usize,
+    frac: usize,
+    rounding: Rounding,
+    overflow: Overflow,
+) -> u64 {
+    let x = rescale(raw, from_frac, frac, rounding);
+    let max = limits(bits, false).1 as u128;
+    match overflow {
+        Overflow::Wrap => (x & max) as u64,
+        Overflow::Saturate => x.min(max) as u64,
+    }
+}
+
+fn from_f64(x: f64, bits: usize, frac: usize, signed: bool) -> i128 {
+    let (min, max) = limits(bits, signed);
+    let x = (x * 2.0_f64.powi(frac as i32)).round();
+    if x.is_nan() {
+        0
+    } else if x <= min as f64 {
+        min
+    } else if x >= max as f64 {
+        max
+    } else {
+        x as i128
+    }
+}
+
+impl<const I: usize, const F: usize> Fixed<I, F> {
+    const VALID: () = assert!(I >= 1 && I + F <= 64, "Fixed supports 1 to 64 bits");
+
+    /// Build a value from its twos complement representation (which is wrapped
+    /// to `I + F` bits)
+    pub fn from_raw(x: i64) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+        Self(wrap(x as i128, I + F, true) as i64)
+    }
+    /// The value as an integer, scaled by `2^F`
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+    /// The nearest value to `x`, saturating if `x` is out of range
+    pub fn from_f64(x: f64) -> Self {
+        Self::from_raw(from_f64(x, I + F, F, true) as i64)
+    }
+    /// The value as a floating point number
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / 2.0_f64.powi(F as i32)
+    }
+    /// The smallest value of the type
+    pub fn min_value() -> Self {
+        Self::from_raw(limits(I + F, true).0 as i64)
+    }
+    /// The largest value of the type
+    pub fn max_value() -> Self {
+        Self::from_raw(limits(I + F, true).1 as i64)
+    }
+}
+
+impl<const I: usize, const F: usize> UFixed<I, F> {
+    const VALID: () = assert!(I + F >= 1 && I + F <= 64, "UFixed supports 1 to 64 bits");
+
+    /// Build a value from its representation (which is wrapped to `I + F` bits)
+    pub fn from_raw(x: u64) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+        Self(wrap(x as i128, I + F, false) as u64)
+    }
+    /// The value as an integer, scaled by `2^F`
+    pub fn raw(self) -> u64 {
+        self.0
+    }
+    /// The nearest value to `x`, saturating if `x` is out of range
+    pub fn from_f64(x: f64) -> Self {
+        Self::from_raw(from_f64(x, I + F, F, false) as u64)
+    }
+    /// The value as a floating point number
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / 2.0_f64.powi(F as i32)
+    }
+    /// The smallest value of the type
+    pub fn min_value() -> Self {
+        Self::from_raw(0)
+    }
+    /// The largest value of the type
+    pub fn max_value() -> Self {
+        Self::from_raw(limits(I + F, false).1 as u64)
+    }
+}
+
+macro_rules! fixed_ops {
+    ($name: ident) => {
+        impl<const I: usize, const F: usize> From<f64> for $name<I, F> {
+            fn from(x: f64) -> Self {
+                Self::from_f64(x)
+            }
+        }
+
+        impl<const I: usize, const F: usize> Display for $name<I, F> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                Display::fmt(&self.to_f64(), f)
+            }
+        }
+
+        impl<const I: usize, const F: usize> std::ops::Add<$name<I, F>> for $name<I, F> {
+            type Output = $name<I, F>;
+
+            fn add(self, rhs: $name<I, F>) -> Self::Output {
+                Self::from_raw(self.0.wrapping_add(rhs.0))
+            }
+        }
+
+        impl<const I: usize, const F: usize> std::ops::Sub<$name<I, F>> for $name<I, F> {
+            type Output = $name<I, F>;
+
+            fn sub(self, rhs: $name<I, F>) -> Self::Output {
+                Self::from_raw(self.0.wrapping_sub(rhs.0))
+            }
+        }
+    };
+}
+
+fixed_ops!(Fixed);
+fixed_ops!(UFixed);
+
+impl<const I: usize, const F: usize> std::ops::Neg for Fixed<I, F> {
+    type Output = Fixed<I, F>;
+
+    fn neg(self) -> Self::Output {
+        Self::from_raw(self.0.wrapping_neg())
+    }
+}
+
+/// Convert a [Fixed] value to a different number of integer and/or fraction bits.
+/// Note that the output widths come first, as with [bit_cast](crate::core::bits::bit_cast).
+pub fn fixed_cast<const I: usize, const F: usize, const IN_I: usize, const IN_F: usize>(
+    x: Fixed<IN_I, IN_F>,
+    rounding: Rounding,
+    overflow: Overflow,
+) -> Fixed<I, F> {
+    Fixed::from_raw(convert(x.0 as i128, IN_F, I + F, F, rounding, overflow) as i64)
+}
+
+/// Convert a [UFixed] value to a different number of integer and/or fraction bits.
+pub fn ufixed_cast<const I: usize, const F: usize, const IN_I: usize, const IN_F: usize>(
+    x: UFixed<IN_I, IN_F>,
+    rounding: Rounding,
+    overflow: Overflow,
+) -> UFixed<I, F> {
+    UFixed::from_raw(convert_unsigned(
+        x.0 as u128,
+        IN_F,
+        I + F,
+        F,
+        rounding,
+        overflow,
+    ))
+}
+
+/// Multiply two [Fixed] values.  The full product (with `IA + IB` integer and `FA + FB`
+/// fraction bits) is converted to the output type as in [fixed_cast].
+pub fn fixed_mul<
+    const I: usize,
+    const F: usize,
+    const IA: usize,
+    const FA: usize,
+    const IB: usize,
+    const FB: usize,
+>(
+    a: Fixed<IA, FA>,
+    b: Fixed<IB, FB>,
+    rounding: Rounding,
+    overflow: Overflow,
+) -> Fixed<I, F> {
+    let product = a.0 as i128 * b.0 as i128;
+    Fixed::from_raw(convert(product, FA + FB, I + F, F, rounding, overflow) as i64)
+}
+
+/// Multiply two [UFixed] values.  The full product (with `IA + IB` integer and `FA + FB`
+/// fraction bits) is converted to the output type as in [ufixed_cast].
+pub fn ufixed_mul<
+    const I: usize,
+    const F: usize,
+    const IA: usize,
+    const FA: usize,
+    const IB: usize,
+    const FB: usize,
+>(
+    a: UFixed<IA, FA>,
+    b: UFixed<IB, FB>,
+    rounding: Rounding,
+    overflow: Overflow,
+) -> UFixed<I, F> {
+    let product = a.0 as u128 * b.0 as u128;
+    UFixed::from_raw(convert_unsigned(
+        product,
+        FA + FB,
+        I + F,
+        F,
+        rounding,
+        overflow,
+    ))
+}
+
+impl<const I: usize, const F: usize> From<Fixed<I, F>> for VerilogLiteral {
+    fn from(x: Fixed<I, F>) -> Self {
+        VerilogLiteral::from_raw(wrap(x.0 as i128, I + F, false) as u64, I + F)
+    }
+}
+
+impl<const I: usize, const F: usize> From<UFixed<I, F>> for VerilogLiteral {
+    fn from(x: UFixed<I, F>) -> Self {
+        VerilogLiteral::from_raw(x.0, I + F)
+    }
+}
+
+// The Verilog functions that implement the conversions.  These are called by the
+// code generated by `hdl_gen` for `fixed_cast`, `fixed_mul`, etc.
+
+fn variable(name: &str, width: usize, signed: bool) -> VerilogFunctionVariable {
+    VerilogFunctionVariable {
+        name: name.into(),
+        width,
+        signed,
+    }
+}
+
+fn literal(x: u128) -> Box<VerilogExpression> {
+    let x = if x <= u64::MAX as u128 {
+        (x as usize).into()
+    } else {
+        x.into()
+    };
+    Box::new(VerilogExpression::Literal(x))
+}
+
+// Converts `value` (with `from` integer and fraction bits) into the function result
+// (with `to` integer and fraction bits), using a local that is wide enough for every
+// intermediate value.
+fn resize(
+    name: String,
+    args: Vec<VerilogFunctionVariable>,
+    value: VerilogExpression,
+    from: (usize, usize),
+    to: (usize, usize),
+    signed: bool,
+    (rounding, overflow): (Rounding, Overflow),
+) -> VerilogFunction {
+    let ext = || Box::new(VerilogExpression::Signal("ext".into()));
+    let assign = |target: &str, value: VerilogExpression| {
+        VerilogStatement::Assignment(VerilogExpression::Signal(target.into()), value)
+    };
+    let bits = to.0 + to.1;
+    let width = from.0.max(to.0) + from.1.max(to.1) + 1;
+    let mut block = vec![assign("ext", value)];
+    if from.1 > to.1 {
+        let shift = from.1 - to.1;
+        if rounding == Rounding::Nearest {
+            let half = literal(1 << (shift - 1));
+            block.push(assign(
+                "ext",
+                VerilogExpression::Binary(ext(), VerilogOp::Add, half),
+            ));
+        }
+        let op = if signed {
+            VerilogOp::AShr
+        } else {
+            VerilogOp::Shr
+        };
+        block.push(assign(
+            "ext",
+            VerilogExpression::Binary(ext(), op, literal(shift as u128)),
+        ));
+    } else if to.1 > from.1 {
+        block.push(assign(
+            "ext",
+            VerilogExpression::Binary(ext(), VerilogOp::Shl, literal((to.1 - from.1) as u128)),
+        ));
+    }
+    let (min, max) = limits(bits, signed);
+    let min = literal(wrap(min, bits, false) as u128);
+    let max = literal(max as u128);
+    let result = |x: Box<VerilogExpression>| vec![assign(&name, *x)];
+    block.push(match (overflow, signed) {
+        (Overflow::Wrap, _) => assign(&name, *ext()),
+        // The value fits if all the bits above the sign bit are copies of it
+        (Overflow::Saturate, true) => {
+            let high = || {
+                Box::new(VerilogExpression::Slice(
+                    ext(),
+                    width - bits + 1,
+                    literal((bits - 1) as u128),
+                ))
+            };
+            let all_ones = Box::new(VerilogExpression::Unary(VerilogOpUnary::All, high()));
+            let all_zeros = Box::new(VerilogExpression::Unary(
+                VerilogOpUnary::Not,
+                Box::new(VerilogExpression::Unary(VerilogOpUnary::Any, high())),
+            ));
+            VerilogStatement::If(VerilogConditional {
+                test: VerilogExpression::Binary(all_ones, VerilogOp::BitOr, all_zeros),
+                then: result(ext()),
+                otherwise: VerilogBlockOrConditional::Conditional(Box::new(VerilogStatement::If(
+                    VerilogConditional {
+                        test: VerilogExpression::Index(ext(), literal((width - 1) as u128)),
+                        then: result(min),
+                        otherwise: VerilogBlockOrConditional::Block(result(max)),
+                    },
+                ))),
+            })
+        }
+        // The value fits if all the bits above it are zero
+        (Overflow::Saturate, false) => VerilogStatement::If(VerilogConditional {
+            test: VerilogExpression::Unary(
+                VerilogOpUnary::Any,
+                Box::new(VerilogExpression::Slice(
+                    ext(),
+                    width - bits,
+                    literal(bits as u128),
+                )),
+            ),
+            then: result(max),
+            otherwise: VerilogBlockOrConditional::Block(result(ext())),
+        }),
+    });
+    VerilogFunction {
+        result: variable(&name, bits, signed),
+        args,
+        locals: vec![variable("ext", width, signed)],
+        block,
+        name,
+    }
+}
+
+fn function_name(base: &str, widths: &[usize], rounding: Rounding, overflow: Overflow) -> String {
+    let widths = widths.iter().map(|x| format!("${}", x)).collect::<String>();
+    let rounding = match rounding {
+        Rounding::Truncate => "trunc",
+        Rounding::Nearest => "round",
+    };
+    let overflow = match overflow {
+        Overflow::Wrap => "wrap",
+        Overflow::Saturate => "sat",
+    };
+    format!("{}{}${}${}", base, widths, rounding, overflow)
+}
+
+fn cast_hdl(
+    base: &str,
+    signed: bool,
+    to: (usize, usize),
+    from: (usize, usize),
+    rounding: Rounding,
+    overflow: Overflow,
+) -> VerilogFunction {
+    resize(
+        function_name(base, &[to.0, to.1, from.0, from.1], rounding, overflow),
+        vec![variable("x", from.0 + from.1, signed)],
+        VerilogExpression::Signal("x".into()),
+        from,
+        to,
+        signed,
+        (rounding, overflow),
+    )
+}
+
+fn mul_hdl(
+    base: &str,
+    signed: bool,
+    to: (usize, usize),
+    a: (usize, usize),
+    b: (usize, usize),
+    rounding: Rounding,
+    overflow: Overflow,
+) -> VerilogFunction {
+    // Both arguments are extended to the width of the (wider) local before multiplying
+    resize(
+        function_name(base, &[to.0, to.1, a.0, a.1, b.0, b.1], rounding, overflow),
+        vec![
+            variable("a", a.0 + a.1, signed),
+            variable("b", b.0 + b.1, signed),
+        ],
+        VerilogExpression::Binary(
+            Box::new(VerilogExpression::Signal("a".into())),
+            VerilogOp::Mul,
+            Box::new(VerilogExpression::Signal("b".into())),
+        ),
+        (a.0 + b.0, a.1 + b.1),
+        to,
+        signed,
+        (rounding, overflow),
+    )
+}
+
+#[doc(hidden)]
+pub fn fixed_cast_hdl<const I: usize, const F: usize, const IN_I: usize, const IN_F: usize>(
+    rounding: Rounding,
+    overflow: Overflow,
+) -> VerilogFunction {
+    cast_hdl("fixed_cast", true, (I, F), (IN_I, IN_F), rounding, overflow)
+}
+
+#[doc(hidden)]
+pub fn ufixed_cast_hdl<const I: usize, const F: usize, const IN_I: usize, const IN_F: usize>(
+    rounding: Rounding,
+    overflow: Overflow,
+) -> VerilogFunction {
+    cast_hdl(
+        "ufixed_cast",
+        false,
+        (I, F),
+        (IN_I, IN_F),
+        rounding,
+        overflow,
+    )
+}
+
+#[doc(hidden)]
+pub fn fixed_mul_hdl<
+    const I: usize,
+    const F: usize,
+    const IA: usize,
+    const FA: usize,
+    const IB: usize,
+    const FB: usize,
+>(
+    rounding: Rounding,
+    overflow: Overflow,
+) -> VerilogFunction {
+    mul_hdl(
+        "fixed_mul",
+        true,
+        (I, F),
+        (IA, FA),
+        (IB, FB),
+        rounding,
+        overflow,
+    )
+}
+
+#[doc(hidden)]
+pub fn ufixed_mul_hdl<
+    const I: usize,
+    const F: usize,
+    const IA: usize,
+    const FA: usize,
+    const IB: usize,
+    const FB: usize,
+>(
+    rounding: Rounding,
+    overflow: Overflow,
+) -> VerilogFunction {
+    mul_hdl(
+        "ufixed_mul",
+        false,
+        (I, F),
+        (IA, FA),
+        (IB, FB),
+        rounding,
+        overflow,
+    )
+}
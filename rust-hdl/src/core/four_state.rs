@@ -0,0 +1,141 @@
+//! Support for an opt-in four-state (0/1/X/Z) simulation mode.
+//!
+//! By default, the simulator is two-state.  Every [Signal](crate::core::signal::Signal)
+//! starts at its default value, so an uninitialised register or an undriven tristate
+//! net reads as zero.  In four-state mode, each signal additionally tracks if its value
+//! is unknown (X):
+//!
+//! - The output of a [DFF](crate::widgets::dff::DFF) is unknown until the register is loaded
+//! with a known value.
+//! - An undriven tristate signal (Z) reads as unknown.
+//! - Any signal assigned in an `#[hdl_gen]` function from an expression that reads an unknown
+//! signal (or inside an `if`/`match` whose condition is unknown) becomes unknown.
+//! - An `if` or `match` whose condition reads an unknown signal is reported (see
+//! [take_unknown_conditions]).
+//! - The VCD writer emits `x` for unknown values and `z` for undriven ones.
+//!
+//! Unknown values are tracked for each bit of a signal (see [UnknownMask]).  Copying a
+//! signal (`self.a.next = self.b.val()`) copies its unknown bits, writing bits with
+//! `set_bit` or `set_bits` only affects those bits, and reading bits with `get_bit` or
+//! `get_bits` only depends on those bits.  Any other expression is unknown as a whole if it
+//! reads an unknown bit.  The mode applies to the current thread, and must be enabled before
+//! the circuit is constructed, since registers decide their initial state when they are built.
+//!
+//! The instrumentation of `#[hdl_gen]` functions that propagates unknown values is only
+//! compiled in with the `four-state` cargo feature of `rust-hdl`, so that it costs nothing
+//! in the default (two-state) simulation.  Without the feature, registers still start out
+//! unknown, but the unknown values do not propagate through `#[hdl_gen]` code.
+//!
+//! Unknown values are tracked by the signals, and not by [Bits](crate::core::bits::Bits)
+//! itself, so they only propagate through `#[hdl_gen]` code.  A `Logic::update` written by
+//! hand (such as the one of a [RAM](crate::widgets::ramrom::ram::RAM) or a
+//! [ROM](crate::widgets::ramrom::rom::ROM)) produces known outputs, even from unknown
+//! inputs, unless it marks them with
+//! [set_next_unknown](crate::core::signal::Signal::set_next_unknown) (as the
+//! [DFF](crate::widgets::dff::DFF) does).
+//!
+//! ```rust
+//! # use rust_hdl::prelude::*;
+//! four_state::set_four_state(true);
+//! let dff: DFF<Bits<8>> = DFF::default();
+//! assert!(dff.q.is_unknown());
+//! four_state::set_four_state(false);
+//! ```
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+    static FOUR_STATE: Cell<bool> = Cell::new(false);
+    static UNKNOWN_CONDITIONS: RefCell<Vec<UnknownCondition>> = RefCell::new(vec![]);
+}
+
+/// Enable or disable four-state simulation mode for the current thread.
+pub fn set_four_state(enabled: bool) {
+    FOUR_STATE.with(|x| x.set(enabled));
+}
+
+/// Returns `true` if four-state simulation mode is enabled for the current thread.
+pub fn is_four_state() -> bool {
+    FOUR_STATE.with(|x| x.get())
+}
+
+/// A report of an `if` or `match` condition that read an unknown value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownCondition {
+    /// The source location (file and line) of the condition
+    pub location: String,
+    /// The text of the condition
+    pub condition: String,
+}
+
+#[doc(hidden)]
+pub fn report_unknown_condition(location: &str, condition: &str) {
+    if !is_four_state() {
+        return;
+    }
+    UNKNOWN_CONDITIONS.with(|x| {
+        let mut reports = x.borrow_mut();
+        if !reports.iter().any(|r| r.location == location) {
+            eprintln!("X in condition {} at {}", condition, location);
+            reports.push(UnknownCondition {
+                location: location.into(),
+                condition: condition.into(),
+            });
+        }
+    })
+}
+
+/// Retrieve (and clear) the list of conditions that read an unknown value on
+/// the current thread.  Each condition is reported once.
+pub fn take_unknown_conditions() -> Vec<UnknownCondition> {
+    UNKNOWN_CONDITIONS.with(|x| std::mem::take(&mut *x.borrow_mut()))
+}
+
+/// The unknown (X) bits of a signal in four-state mode, one flag per bit (least
+/// significant first).  A mask with no unknown bits is empty, and so does not allocate.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnknownMask(Vec<u64>);
+
+impl UnknownMask {
+    /// A mask in which all `width` bits are unknown
+    pub fn all(width: usize) -> UnknownMask {
+        let mut ret = UnknownMask::default();
+        ret.set_bits(0, width, true);
+        ret
+    }
+    /// Returns `true` if any bit is unknown
+    pub fn any(&self) -> bool {
+        !self.0.is_empty()
+    }
+    /// Returns `true` if the bit at `index` is unknown
+    pub fn get_bit(&self, index: usize) -> bool {
+        self.get_bits(index, 1)
+    }
+    /// Returns `true` if any of the `count` bits starting at `index` is unknown
+    pub fn get_bits(&self, index: usize, count: usize) -> bool {
+        (index..index + count).any(|i| {
+            self.0
+                .get(i / 64)
+                .is_some_and(|limb| limb & (1 << (i % 64)) != 0)
+        })
+    }
+    /// Mark the `count` bits starting at `index` as unknown (or known)
+    pub fn set_bits(&mut self, index: usize, count: usize, unknown: bool) {
+        if !unknown && !self.any() {
+            return;
+        }
+        let limbs = (index + count).div_ceil(64);
+        if self.0.len() < limbs {
+            self.0.resize(limbs, 0);
+        }
+        for i in index..index + count {
+            if unknown {
+                self.0[i / 64] |= 1 << (i % 64);
+            } else {
+                self.0[i / 64] &= !(1 << (i % 64));
+            }
+        }
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Support for the `#[hdl_test]` attribute.
+//!
+//! Most design tests follow the same recipe: build the circuit, `connect_all`, run
+//! the structural checks, generate Verilog and validate it with `yosys`, then
+//! simulate it with a clock and a testbench while capturing a VCD.  The `#[hdl_test]`
+//! attribute turns a testbench function into a complete `#[test]` that runs all of
+//! those steps through an [HdlTest].
+//!
+//! ```rust,no_run
+//! # use rust_hdl::prelude::*;
+//! #[hdl_test(uut = Strobe::<32>::new(1_000_000, 1000.0), clock = clock, max_time = 100_000)]
+//! fn strobe_fires(mut sim: Sim<Strobe<32>>) -> Result<(), SimError> {
+//!     let mut x = sim.init()?;
+//!     x.enable.next = true;
+//!     x = sim.watch(|x| x.strobe.val(), x)?;
+//!     sim.done(x)
+//! }
+//! ```
+//!
+//! The arguments to the attribute are
+//!  - `uut = <expr>` - the expression that constructs the circuit (required),
+//!  - `clock = <field>` - the clock input to toggle (optional; no clock if omitted),
+//!  - `interval = <expr>` - the time between clock edges, as for `add_clock` (default 5),
+//!  - `max_time = <expr>` - the maximum simulation time (default 1_000_000),
+//!  - `waves = never | on_failure | always` - when to keep the VCD (default `on_failure`),
+//!  - `yosys = <bool>` - whether to validate the Verilog with `yosys` (default `true`).
+//!
+//! Each test gets its own artifact directory (see [HdlTest::artifact_dir]) holding the
+//! generated Verilog, the waveform (if kept) and a short report.  If `yosys` is not
+//! installed, validation is skipped with a notice rather than failing the test.
+use crate::core::block::Block;
+use crate::core::check_error::{check_all, CheckError};
+use crate::core::module_defines::generate_verilog;
+use crate::core::simulate::{SimError, Simulation};
+use crate::core::yosys::{yosys_validate, SynthError};
+use std::env::temp_dir;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// When to keep the waveform of an [HdlTest] simulation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WaveCapture {
+    /// Do not trace the simulation at all
+    Never,
+    /// Trace the simulation, but only write the VCD if the test fails
+    OnFailure,
+    /// Always write the VCD
+    Always,
+}
+
+/// The step of an [HdlTest] that failed.
+#[derive(Debug)]
+pub enum HdlTestError {
+    /// The structural checks (see [check_all]) failed
+    Check(CheckError),
+    /// `yosys` rejected the generated Verilog
+    Synth(SynthError),
+    /// The simulation failed
+    Sim(SimError),
+    /// An artifact could not be written
+    IO(std::io::Error),
+}
+
+impl From<std::io::Error> for HdlTestError {
+    fn from(x: std::io::Error) -> Self {
+        HdlTestError::IO(x)
+    }
+}
+
+impl Display for HdlTestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HdlTestError::Check(e) => write!(f, "structural checks failed: {:?}", e),
+            HdlTestError::Synth(e) => write!(f, "yosys validation failed: {:?}", e),
+            HdlTestError::Sim(e) => write!(f, "simulation failed: {:?}", e),
+            HdlTestError::IO(e) => write!(f, "unable to write test artifacts: {}", e),
+        }
+    }
+}
+
+/// Returns true if a `yosys` executable can be run.
+pub fn yosys_available() -> bool {
+    Command::new("yosys").arg("-V").output().is_ok()
+}
+
+/// Runs the standard checks, validation and simulation for a design.  This is what
+/// the `#[hdl_test]` attribute expands into, but it can be used directly as well.
+pub struct HdlTest {
+    name: String,
+    dir: PathBuf,
+    yosys: bool,
+    waves: WaveCapture,
+}
+
+impl HdlTest {
+    /// Create a test named `name` in the module `module`.  The artifacts go into
+    /// `hdl_test/<module>/<name>` under `target_dir` (typically `CARGO_TARGET_TMPDIR`),
+    /// or under the system temporary directory if `target_dir` is `None`.
+    pub fn new(module: &str, name: &str, target_dir: Option<&str>) -> Self {
+        let mut dir = target_dir
+            .map(PathBuf::from)
+            .unwrap_or_else(temp_dir)
+            .join("hdl_test");
+        for part in module.split("::") {
+            dir = dir.join(part);
+        }
+        Self {
+            name: name.into(),
+            dir: dir.join(name),
+            yosys: true,
+            waves: WaveCapture::OnFailure,
+        }
+    }
+    /// Enable or disable the `yosys` validation step.
+    pub fn yosys(self, yosys: bool) -> Self {
+        Self { yosys, ..self }
+    }
+    /// Select when the waveform is kept.
+    pub fn waves(self, waves: WaveCapture) -> Self {
+        Self { waves, ..self }
+    }
+    /// The directory that holds the artifacts for this test.
+    pub fn artifact_dir(&self) -> &Path {
+        &self.dir
+    }
+    /// Check, validate and simulate `uut` with `sim` for up to `max_time`.  A one line
+    /// summary is printed, and a `report.txt` is written to the artifact directory.
+    pub fn run<T: Send + 'static + Block>(
+        &self,
+        uut: T,
+        sim: Simulation<T>,
+        max_time: u64,
+    ) -> Result<(), HdlTestError> {
+        let _ = std::fs::remove_dir_all(&self.dir);
+        std::fs::create_dir_all(&self.dir)?;
+        let mut report = vec![];
+        let result = self.run_steps(uut, sim, max_time, &mut report);
+        match &result {
+            Ok(_) => report.push("passed".to_string()),
+            Err(e) => report.push(format!("FAILED: {}", e)),
+        }
+        std::fs::write(self.dir.join("report.txt"), report.join("\n") + "\n")?;
+        println!(
+            "hdl_test {}: {} (artifacts in {})",
+            self.name,
+            if result.is_ok() { "passed" } else { "FAILED" },
+            self.dir.display()
+        );
+        result
+    }
+    fn run_steps<T: Send + 'static + Block>(
+        &self,
+        mut uut: T,
+        mut sim: Simulation<T>,
+        max_time: u64,
+        report: &mut Vec<String>,
+    ) -> Result<(), HdlTestError> {
+        uut.connect_all();
+        check_all(&uut).map_err(HdlTestError::Check)?;
+        report.push("checks: ok".into());
+        let verilog = generate_verilog(&uut);
+        let verilog_file = self.dir.join("top.v");
+        std::fs::write(&verilog_file, &verilog)?;
+        report.push(format!("verilog: {}", verilog_file.display()));
+        if !self.yosys {
+            report.push("yosys: disabled".into());
+        } else if !yosys_available() {
+            println!(
+                "hdl_test {}: notice - yosys not found, skipping validation",
+                self.name
+            );
+            report.push("yosys: skipped (not found)".into());
+        } else {
+            let prefix = format!("hdl_test_{}", self.name);
+            yosys_validate(&prefix, &verilog).map_err(HdlTestError::Synth)?;
+            report.push("yosys: ok".into());
+        }
+        let result = if self.waves == WaveCapture::Never {
+            sim.run(Box::new(uut), max_time)
+        } else {
+            let mut trace = vec![];
+            let result = sim.run_traced(Box::new(uut), max_time, &mut trace);
+            if result.is_err() || self.waves == WaveCapture::Always {
+                let vcd_file = self.dir.join("waves.vcd");
+                std::fs::write(&vcd_file, trace)?;
+                report.push(format!("waves: {}", vcd_file.display()));
+            }
+            result
+        };
+        result.map_err(HdlTestError::Sim)?;
+        report.push("simulation: ok".into());
+        Ok(())
+    }
+}
@@ -0,0 +1,551 @@
+//! Export the structure of a design for documentation and review.
+//!
+//! [design_hierarchy] walks a circuit and collects the module hierarchy, the ports of
+//! each module (with their directions, types and pin constraints), and the connections
+//! between the ports of each module and its children.  The connections come from
+//! the links between interfaces (see [LogicLink](crate::core::logic::LogicLink)) and
+//! from the assignments in the `update` function of each module.
+//!
+//! The hierarchy can be written as JSON with [generate_hierarchy_json] (e.g., for
+//! interface control documents), or as a Graphviz DOT block diagram with
+//! [generate_block_diagram].
+//!
+//! ```rust
+//! # use rust_hdl::prelude::*;
+//! let uut = Strobe::<32>::new(1_000_000, 1000.0);
+//! let json = generate_hierarchy_json(&uut);
+//! assert!(json.contains(r#""module": "top$counter""#));
+//! let dot = generate_block_diagram(&uut, 1);
+//! assert!(dot.starts_with("digraph"));
+//! ```
+use crate::core::ast::{Verilog, VerilogExpression, VerilogLink};
+use crate::core::atom::{Atom, AtomKind};
+use crate::core::block::Block;
+use crate::core::constraint::{Constraint, PinConstraint};
+use crate::core::named_path::NamedPath;
+use crate::core::probe::Probe;
+use crate::core::type_descriptor::{TypeDescriptor, TypeKind};
+use crate::core::verilog_gen::verilog_link_extraction;
+use crate::core::verilog_visitor::{walk_expression, VerilogVisitor};
+
+/// The direction of a port, as seen from inside the module
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PortDirection {
+    /// An input (`Signal<In, T>`)
+    Input,
+    /// An output (`Signal<Out, T>`)
+    Output,
+    /// A tristate port (`Signal<InOut, T>`)
+    InOut,
+}
+
+/// A port of a module in the design hierarchy
+#[derive(Clone, Debug)]
+pub struct HierarchyPort {
+    /// The name of the port, as it appears in the generated Verilog
+    pub name: String,
+    /// The direction of the port
+    pub direction: PortDirection,
+    /// The number of bits in the port
+    pub width: usize,
+    /// The type of the data carried by the port
+    pub descriptor: TypeDescriptor,
+    /// The pin constraints attached to the port (usually only on the top level)
+    pub constraints: Vec<PinConstraint>,
+}
+
+/// How a connection between two ports is made
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConnectionKind {
+    /// The `to` port is assigned from an expression that uses the `from` port
+    Assign,
+    /// The ports are joined by a link between interfaces
+    Link,
+}
+
+/// A connection inside a module.  The ends of the connection are named
+/// `instance.port` for the ports of a child module, or `port` for the ports
+/// of the module itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HierarchyConnection {
+    /// How the connection is made
+    pub kind: ConnectionKind,
+    /// The port that drives the connection
+    pub from: String,
+    /// The port that is driven
+    pub to: String,
+}
+
+/// A module in the design hierarchy
+#[derive(Clone, Debug)]
+pub struct HierarchyNode {
+    /// The name of the instance in the parent module (`top` for the top level)
+    pub instance: String,
+    /// The name of the Verilog module for this instance
+    pub module: String,
+    /// The ports of the module
+    pub ports: Vec<HierarchyPort>,
+    /// The connections between the ports of the module and its children
+    pub connections: Vec<HierarchyConnection>,
+    /// The child modules, in declaration order
+    pub children: Vec<HierarchyNode>,
+}
+
+impl HierarchyNode {
+    fn new(instance: &str, module: &str) -> Self {
+        Self {
+            instance: instance.into(),
+            module: module.into(),
+            ports: vec![],
+            connections: vec![],
+            children: vec![],
+        }
+    }
+    // Map a flattened signal name from the Verilog code to a connection endpoint
+    fn endpoint(&self, signal: &str) -> Option<String> {
+        if self.ports.iter().any(|x| x.name == signal) {
+            return Some(signal.into());
+        }
+        self.children
+            .iter()
+            .filter(|child| signal.starts_with(&format!("{}$", child.instance)))
+            .max_by_key(|child| child.instance.len())
+            .map(|child| {
+                let port = &signal[child.instance.len() + 1..];
+                format!("{}.{}", child.instance, port)
+            })
+    }
+    fn connect(&mut self, kind: ConnectionKind, from: &str, to: &str) {
+        if let (Some(from), Some(to)) = (self.endpoint(from), self.endpoint(to)) {
+            let connection = HierarchyConnection { kind, from, to };
+            if connection.from != connection.to && !self.connections.contains(&connection) {
+                self.connections.push(connection);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct SignalCollector {
+    signals: Vec<String>,
+}
+
+impl VerilogVisitor for SignalCollector {
+    fn visit_signal(&mut self, c: &str) {
+        self.signals.push(
+            c.trim_end_matches("$next")
+                .replace("[", "$")
+                .replace("]", ""),
+        );
+    }
+}
+
+#[derive(Default)]
+struct AssignmentCollector {
+    assignments: Vec<(String, Vec<String>)>,
+}
+
+impl VerilogVisitor for AssignmentCollector {
+    fn visit_assignment(&mut self, l: &VerilogExpression, r: &VerilogExpression) {
+        let mut lhs = SignalCollector::default();
+        walk_expression(&mut lhs, l);
+        let mut rhs = SignalCollector::default();
+        walk_expression(&mut rhs, r);
+        for target in lhs.signals {
+            self.assignments.push((target, rhs.signals.clone()));
+        }
+    }
+}
+
+#[derive(Default)]
+struct HierarchyProbe {
+    path: NamedPath,
+    namespace: NamedPath,
+    stack: Vec<(HierarchyNode, Verilog)>,
+    root: Option<HierarchyNode>,
+}
+
+impl Probe for HierarchyProbe {
+    fn visit_start_scope(&mut self, name: &str, node: &dyn Block) {
+        self.path.push(name);
+        self.namespace.reset();
+        let code = node.hdl();
+        let module = match &code {
+            Verilog::Blackbox(b) => b.name.clone(),
+            _ => self.path.to_string(),
+        };
+        self.stack.push((HierarchyNode::new(name, &module), code));
+    }
+
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        self.namespace.push(name);
+    }
+
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        let direction = match signal.kind() {
+            AtomKind::InputParameter => PortDirection::Input,
+            AtomKind::OutputParameter | AtomKind::OutputPassthrough => PortDirection::Output,
+            AtomKind::InOutParameter => PortDirection::InOut,
+            _ => return,
+        };
+        let namespace = self.namespace.flat("$");
+        let name = if namespace.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}${}", namespace, name)
+        };
+        if let Some((node, _)) = self.stack.last_mut() {
+            node.ports.push(HierarchyPort {
+                name,
+                direction,
+                width: signal.bits(),
+                descriptor: signal.descriptor(),
+                constraints: signal.constraints(),
+            });
+        }
+    }
+
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        self.namespace.pop();
+    }
+
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.path.pop();
+        let (mut node, code) = self.stack.pop().unwrap();
+        if let Verilog::Combinatorial(code) = &code {
+            for link in verilog_link_extraction(code) {
+                let (from, to) = link_endpoints(&link);
+                node.connect(ConnectionKind::Link, &from, &to);
+            }
+            let mut assignments = AssignmentCollector::default();
+            assignments.visit_block(code);
+            for (target, sources) in assignments.assignments {
+                for source in sources {
+                    node.connect(ConnectionKind::Assign, &source, &target);
+                }
+            }
+        }
+        match self.stack.last_mut() {
+            Some((parent, _)) => parent.children.push(node),
+            None => self.root = Some(node),
+        }
+    }
+}
+
+fn link_endpoints(link: &VerilogLink) -> (String, String) {
+    let name = |x: &str| x.replace("[", "$").replace("]", "");
+    match link {
+        VerilogLink::Forward(x) => (
+            format!("{}${}", name(&x.owner_name), x.my_name),
+            format!("{}${}", name(&x.other_name), x.my_name),
+        ),
+        VerilogLink::Backward(x) => (
+            format!("{}${}", name(&x.other_name), x.my_name),
+            format!("{}${}", name(&x.owner_name), x.my_name),
+        ),
+        VerilogLink::Bidirectional(x) => {
+            if x.my_name.is_empty() {
+                (x.owner_name.clone(), x.other_name.clone())
+            } else {
+                (
+                    format!("{}${}", x.owner_name, x.my_name),
+                    format!("{}${}", x.other_name, x.my_name),
+                )
+            }
+        }
+    }
+}
+
+/// Collect the module hierarchy of a design
+pub fn design_hierarchy(uut: &dyn Block) -> HierarchyNode {
+    let mut probe = HierarchyProbe::default();
+    uut.accept("top", &mut probe);
+    probe.root.unwrap()
+}
+
+fn json_string(x: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in x.chars() {
+        match c {
+            '"' => ret += "\\\"",
+            '\\' => ret += "\\\\",
+            '\n' => ret += "\\n",
+            c if (c as u32) < 0x20 => ret += &format!("\\u{:04x}", c as u32),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+// A minimal JSON document model, so that the output can be pretty printed
+enum Json {
+    Number(usize),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn str(x: &str) -> Json {
+        Json::String(x.into())
+    }
+    fn write(&self, indent: usize, out: &mut String) {
+        let pad = "  ".repeat(indent + 1);
+        match self {
+            Json::Number(x) => *out += &x.to_string(),
+            Json::String(x) => *out += &json_string(x),
+            Json::Array(x) if x.is_empty() => *out += "[]",
+            Json::Array(x) => {
+                *out += "[\n";
+                for (ndx, item) in x.iter().enumerate() {
+                    *out += &pad;
+                    item.write(indent + 1, out);
+                    *out += if ndx + 1 < x.len() { ",\n" } else { "\n" };
+                }
+                *out += &format!("{}]", "  ".repeat(indent));
+            }
+            Json::Object(x) => {
+                *out += "{\n";
+                for (ndx, (key, item)) in x.iter().enumerate() {
+                    *out += &format!("{}{}: ", pad, json_string(key));
+                    item.write(indent + 1, out);
+                    *out += if ndx + 1 < x.len() { ",\n" } else { "\n" };
+                }
+                *out += &format!("{}}}", "  ".repeat(indent));
+            }
+        }
+    }
+}
+
+fn type_json(descriptor: &TypeDescriptor) -> Json {
+    let name = ("name", Json::str(&descriptor.name));
+    match &descriptor.kind {
+        TypeKind::Bits(width) => Json::Object(vec![
+            name,
+            ("kind", Json::str("bits")),
+            ("width", Json::Number(*width)),
+        ]),
+        TypeKind::Signed(width) => Json::Object(vec![
+            name,
+            ("kind", Json::str("signed")),
+            ("width", Json::Number(*width)),
+        ]),
+        TypeKind::Enum(variants) => Json::Object(vec![
+            name,
+            ("kind", Json::str("enum")),
+            (
+                "variants",
+                Json::Array(
+                    variants
+                        .iter()
+                        .map(|x| {
+                            Json::Object(vec![
+                                ("name", Json::str(&x.name)),
+                                ("value", Json::Number(x.value)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ]),
+        TypeKind::Composite(fields) => Json::Object(vec![
+            name,
+            ("kind", Json::str("composite")),
+            (
+                "fields",
+                Json::Array(
+                    fields
+                        .iter()
+                        .map(|x| {
+                            Json::Object(vec![
+                                ("name", Json::str(&x.fieldname)),
+                                ("type", type_json(&x.kind)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ]),
+        TypeKind::Fixed {
+            width,
+            fraction,
+            signed,
+        } => Json::Object(vec![
+            name,
+            ("kind", Json::str(if *signed { "fixed" } else { "ufixed" })),
+            ("width", Json::Number(*width)),
+            ("fraction", Json::Number(*fraction)),
+        ]),
+    }
+}
+
+fn constraint_json(x: &PinConstraint) -> Json {
+    let (kind, value) = match &x.constraint {
+        Constraint::Location(x) => ("location", x.clone()),
+        Constraint::Kind(x) => ("signal_type", format!("{:?}", x)),
+        Constraint::Timing(x) => ("timing", format!("{:?}", x)),
+        Constraint::Custom(x) => ("custom", x.clone()),
+        Constraint::Slew(x) => ("slew", format!("{:?}", x)),
+    };
+    Json::Object(vec![
+        ("index", Json::Number(x.index)),
+        ("kind", Json::str(kind)),
+        ("value", Json::String(value)),
+    ])
+}
+
+fn direction_name(x: PortDirection) -> &'static str {
+    match x {
+        PortDirection::Input => "input",
+        PortDirection::Output => "output",
+        PortDirection::InOut => "inout",
+    }
+}
+
+fn node_json(node: &HierarchyNode) -> Json {
+    Json::Object(vec![
+        ("instance", Json::str(&node.instance)),
+        ("module", Json::str(&node.module)),
+        (
+            "ports",
+            Json::Array(
+                node.ports
+                    .iter()
+                    .map(|x| {
+                        Json::Object(vec![
+                            ("name", Json::str(&x.name)),
+                            ("direction", Json::str(direction_name(x.direction))),
+                            ("width", Json::Number(x.width)),
+                            ("type", type_json(&x.descriptor)),
+                            (
+                                "constraints",
+                                Json::Array(x.constraints.iter().map(constraint_json).collect()),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "connections",
+            Json::Array(
+                node.connections
+                    .iter()
+                    .map(|x| {
+                        let kind = match x.kind {
+                            ConnectionKind::Assign => "assign",
+                            ConnectionKind::Link => "link",
+                        };
+                        Json::Object(vec![
+                            ("kind", Json::str(kind)),
+                            ("from", Json::str(&x.from)),
+                            ("to", Json::str(&x.to)),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "children",
+            Json::Array(node.children.iter().map(node_json).collect()),
+        ),
+    ])
+}
+
+/// Write the module hierarchy of a design (with ports, types, pin constraints and
+/// connections) as JSON
+pub fn generate_hierarchy_json(uut: &dyn Block) -> String {
+    let mut out = String::new();
+    node_json(&design_hierarchy(uut)).write(0, &mut out);
+    out.push('\n');
+    out
+}
+
+fn dot_id(path: &str) -> String {
+    json_string(path)
+}
+
+fn dot_node(node: &HierarchyNode, path: &str, level: usize, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(level + 1);
+    if depth == 0 || node.children.is_empty() {
+        let ports = |dir: PortDirection| {
+            node.ports
+                .iter()
+                .filter(|x| x.direction == dir)
+                .map(|x| x.name.replace("$", "."))
+                .collect::<Vec<_>>()
+                .join("\\n")
+        };
+        *out += &format!(
+            "{}{} [shape=record, label=\"{{{}|{}|{{{}|{}}}}}\"];\n",
+            indent,
+            dot_id(path),
+            node.instance,
+            node.module.replace("$", "."),
+            ports(PortDirection::Input),
+            ports(PortDirection::Output)
+        );
+    } else {
+        *out += &format!(
+            "{}subgraph {} {{\n",
+            indent,
+            dot_id(&format!("cluster_{}", path))
+        );
+        *out += &format!(
+            "{}  label=\"{} ({})\";\n",
+            indent,
+            node.instance,
+            node.module.replace("$", ".")
+        );
+        *out += &format!("{}  {} [shape=point];\n", indent, dot_id(path));
+        for child in &node.children {
+            dot_node(
+                child,
+                &format!("{}.{}", path, child.instance),
+                level + 1,
+                depth - 1,
+                out,
+            );
+        }
+        let node_of = |endpoint: &str| match endpoint.split_once('.') {
+            Some((instance, port)) => (format!("{}.{}", path, instance), port.to_string()),
+            None => (path.to_string(), endpoint.to_string()),
+        };
+        let mut edges: Vec<(String, String, String)> = vec![];
+        for connection in &node.connections {
+            let (from, from_port) = node_of(&connection.from);
+            let (to, to_port) = node_of(&connection.to);
+            let label = if from_port == to_port {
+                from_port
+            } else {
+                format!("{} -> {}", from_port, to_port)
+            };
+            let edge = (from, to, label.replace("$", "."));
+            if edge.0 != edge.1 && !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+        for (from, to, label) in edges {
+            *out += &format!(
+                "{}  {} -> {} [label={}];\n",
+                indent,
+                dot_id(&from),
+                dot_id(&to),
+                json_string(&label)
+            );
+        }
+        *out += &format!("{}}}\n", indent);
+    }
+}
+
+/// Render a Graphviz DOT block diagram of a design.  Modules are expanded into
+/// their children down to `depth` levels below the top, and the connections
+/// between the children are drawn as edges.
+pub fn generate_block_diagram(uut: &dyn Block, depth: usize) -> String {
+    let root = design_hierarchy(uut);
+    let mut out = String::from("digraph top {\n  rankdir=LR;\n  node [fontsize=10];\n");
+    dot_node(&root, &root.instance, 0, depth, &mut out);
+    out += "}\n";
+    out
+}
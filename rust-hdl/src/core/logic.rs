@@ -24,6 +24,21 @@ impl<L: Logic> Logic for Vec<L> {
     fn update(&mut self) {}
 }
 
+impl<L: Logic + ?Sized> Logic for Box<L> {
+    fn update(&mut self) {
+        self.as_mut().update()
+    }
+    fn connect(&mut self) {
+        self.as_mut().connect()
+    }
+    fn hdl(&self) -> Verilog {
+        self.as_ref().hdl()
+    }
+    fn timing(&self) -> Vec<TimingInfo> {
+        self.as_ref().timing()
+    }
+}
+
 /*
  A link is always
  In --> In
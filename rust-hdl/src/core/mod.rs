@@ -1,6 +1,8 @@
 pub mod ast;
+pub mod async_simulate;
 #[doc(hidden)]
 pub mod atom;
+pub mod attribute;
 /// Module that supports arbitrary width bit vectors
 pub mod bits;
 #[doc(hidden)]
@@ -16,16 +18,23 @@ pub mod code_writer;
 pub mod constant;
 pub mod constraint;
 pub mod direction;
+pub mod fixed;
+pub mod four_state;
+pub mod hdl_test;
+pub mod hierarchy;
 pub mod logic;
 pub mod module_defines;
 pub mod named_path;
+pub mod parameter;
 pub mod path_tools;
 pub mod prelude;
 pub mod probe;
 #[doc(hidden)]
 pub mod short_bit_vec;
 pub mod signal;
+pub mod signal_path;
 pub mod signed;
+pub mod sim_debugger;
 pub mod simulate;
 pub mod struct_valued;
 pub mod synth;
@@ -33,5 +42,6 @@ pub mod timing;
 pub mod type_descriptor;
 pub mod vcd_probe;
 pub mod verilog_gen;
+pub mod verilog_snapshot;
 pub mod verilog_visitor;
 pub mod yosys;
@@ -1,14 +1,17 @@
 use crate::core::ast::{Verilog, VerilogLink, VerilogLiteral};
 use crate::core::atom::AtomKind::{StubInputSignal, StubOutputSignal};
 use crate::core::atom::{is_atom_signed, Atom, AtomKind};
+use crate::core::attribute::{verilog_attributes, Attribute, BlockAttribute};
 use crate::core::block::Block;
 use crate::core::check_error::check_all;
 use crate::core::code_writer::CodeWriter;
 use crate::core::named_path::NamedPath;
+use crate::core::parameter::BlockParameters;
 use crate::core::probe::Probe;
 use crate::core::type_descriptor::{TypeDescriptor, TypeKind};
-use crate::core::verilog_gen::{verilog_combinatorial, verilog_link_extraction};
-use std::collections::BTreeMap;
+use crate::core::verilog_gen::{verilog_combinatorial_with_options, verilog_link_extraction};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Clone, Debug, Default)]
 struct SubModuleInvocation {
@@ -23,6 +26,9 @@ struct ModuleDetails {
     enums: Vec<EnumDefinition>,
     code: Verilog,
     links: Vec<VerilogLink>,
+    attributes: Vec<Attribute>,
+    field_attributes: Vec<(String, Attribute)>,
+    parameters: Option<BlockParameters>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -39,6 +45,8 @@ struct AtomDetails {
     width: usize,
     const_val: VerilogLiteral,
     signed: bool,
+    attributes: Vec<Attribute>,
+    width_param: Option<String>,
 }
 
 fn verilog_atom_name(x: &AtomKind) -> &str {
@@ -54,7 +62,25 @@ fn verilog_atom_name(x: &AtomKind) -> &str {
     }
 }
 
+// Field attributes apply to the field itself, and to everything inside it (e.g., the
+// signals of an interface, or the elements of a vector of blocks)
+fn field_attributes(attributes: &[(String, Attribute)], name: &str) -> Vec<Attribute> {
+    attributes
+        .iter()
+        .filter(|(field, _)| is_field(field, name))
+        .map(|(_, attribute)| attribute.clone())
+        .collect()
+}
+
+fn is_field(field: &str, name: &str) -> bool {
+    name == field || name.starts_with(&format!("{}$", field))
+}
+
 fn decl(x: &AtomDetails) -> String {
+    format!("{}{}", verilog_attributes(&x.attributes), decl_body(x))
+}
+
+fn decl_body(x: &AtomDetails) -> String {
     let signed = if x.signed { "signed" } else { "" };
     if x.kind == AtomKind::Constant {
         format!(
@@ -65,22 +91,50 @@ fn decl(x: &AtomDetails) -> String {
             x.const_val
         )
     } else {
-        if x.width == 1 {
-            format!("{} {} {};", verilog_atom_name(&x.kind), signed, x.name)
-        } else {
-            format!(
+        match &x.width_param {
+            Some(param) => format!(
+                "{} {} [{}-1:0] {};",
+                verilog_atom_name(&x.kind),
+                signed,
+                param,
+                x.name
+            ),
+            None if x.width == 1 => {
+                format!("{} {} {};", verilog_atom_name(&x.kind), signed, x.name)
+            }
+            None => format!(
                 "{} {} [{}:0] {};",
                 verilog_atom_name(&x.kind),
                 signed,
                 x.width - 1,
                 x.name
-            )
+            ),
         }
     }
 }
 
+/// Options for [generate_verilog_with_options].
+#[derive(Clone, Debug, Default)]
+pub struct VerilogOptions {
+    /// Give the registers (the outputs of clocked custom Verilog, such as RAMs, ROMs, and
+    /// user primitives) their simulation start values as power-on values, with an `initial`
+    /// block.  Most FPGAs support this, so that the hardware starts in the same state as the
+    /// simulation, without a reset.  Registers that the custom code initializes itself in an
+    /// `initial begin ... end` block (such as a [DFF](crate::widgets::dff::DFF), which
+    /// otherwise starts from `T::default()`) get their start values in that block.
+    pub initial_values: bool,
+    /// Write the Rust source location of each statement of the `#[hdl_gen]` update code
+    /// as a `// file:line` comment above it.  [yosys_validate](crate::core::yosys::yosys_validate)
+    /// then points its errors back to the Rust source, and
+    /// [source_location](crate::core::verilog_gen::source_location) does the same for any
+    /// line of the Verilog.  The comments change whenever the Rust source moves, so they are
+    /// off by default.
+    pub source_locations: bool,
+}
+
 #[derive(Default)]
 pub struct ModuleDefines {
+    options: VerilogOptions,
     path: NamedPath,
     namespace: NamedPath,
     details: BTreeMap<String, ModuleDetails>,
@@ -103,11 +157,11 @@ impl ModuleDefines {
         let enum_name = descriptor.name.clone();
         match &descriptor.kind {
             TypeKind::Enum(x) => {
-                for (ndx, label) in x.iter().enumerate() {
+                for variant in x {
                     let def = EnumDefinition {
                         type_name: enum_name.clone(),
-                        discriminant: label.into(),
-                        value: ndx,
+                        discriminant: variant.name.clone(),
+                        value: variant.value,
                     };
                     if !entry.enums.contains(&def) {
                         entry.enums.push(def);
@@ -132,6 +186,19 @@ impl ModuleDefines {
         };
         entry.code = code;
     }
+    fn add_attributes(&mut self, module: &str, attributes: Vec<BlockAttribute>) {
+        let entry = self.details.entry(module.into()).or_default();
+        for attribute in attributes {
+            match attribute.field {
+                None => entry.attributes.push(attribute.attribute),
+                Some(field) => entry.field_attributes.push((field, attribute.attribute)),
+            }
+        }
+    }
+    fn add_parameters(&mut self, module: &str, parameters: Option<BlockParameters>) {
+        let entry = self.details.entry(module.into()).or_default();
+        entry.parameters = parameters;
+    }
 }
 
 impl Probe for ModuleDefines {
@@ -141,6 +208,8 @@ impl Probe for ModuleDefines {
         self.namespace.reset();
         self.add_submodule(&top_level, name, &self.path.to_string());
         self.add_code(&self.path.to_string(), node.hdl());
+        self.add_attributes(&self.path.to_string(), node.attributes());
+        self.add_parameters(&self.path.to_string(), node.parameters());
     }
 
     fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
@@ -156,12 +225,38 @@ impl Probe for ModuleDefines {
         } else {
             format!("{}${}", namespace, name)
         };
+        let mut attributes = signal.attributes();
+        let mut width_param = None;
+        if let Some(details) = self.details.get(&module_path) {
+            attributes.extend(field_attributes(&details.field_attributes, &name));
+            width_param = details.parameters.as_ref().and_then(|x| {
+                x.parameters
+                    .iter()
+                    .find(|p| p.signals.contains(&name))
+                    .map(|p| p.name.clone())
+            });
+        }
+        // The stub in the parent can only use the width parameter if it is bound to one
+        // of the parameters of the parent
+        let parent_width_param = width_param.as_ref().and_then(|param| {
+            self.details
+                .get(&self.path.parent())
+                .and_then(|x| x.parameters.as_ref())
+                .and_then(|x| {
+                    x.bindings
+                        .iter()
+                        .find(|b| is_field(&b.field, &module_name) && &b.parameter == param)
+                        .map(|b| b.value.clone())
+                })
+        });
         let param = AtomDetails {
             name: name.clone(),
             kind: signal.kind(),
             width: signal.bits(),
             const_val: signal.verilog(),
             signed: is_atom_signed(signal),
+            attributes,
+            width_param,
         };
         if param.kind.is_parameter() {
             let kind = if param.kind == AtomKind::InputParameter {
@@ -175,6 +270,8 @@ impl Probe for ModuleDefines {
                 width: signal.bits(),
                 const_val: signal.verilog(),
                 signed: is_atom_signed(signal),
+                attributes: vec![],
+                width_param: parent_width_param,
             };
             let parent_name = self.path.parent();
             self.add_atom(&parent_name, parent_param);
@@ -193,6 +290,39 @@ impl Probe for ModuleDefines {
     }
 }
 
+fn initial_blocks() -> Regex {
+    Regex::new(r"(?s)\binitial\s+begin\b.*?\bend\b").unwrap()
+}
+
+fn blocking_assignment(name: &str) -> Regex {
+    Regex::new(&format!(r"\b{}\s*=[^=][^;]*;", regex::escape(name))).unwrap()
+}
+
+// A register is written (with `<=`) on a clock edge by the custom code.  If the code
+// also assigns it with `=` (outside of an `initial` block), it drives it combinatorially.
+fn is_clocked_register(code: &str, name: &str) -> bool {
+    let clocked = Regex::new(&format!(r"\b{}\s*<=", regex::escape(name))).unwrap();
+    let code_after_power_on = initial_blocks().replace_all(code, "");
+    (code.contains("posedge") || code.contains("negedge"))
+        && clocked.is_match(code)
+        && !blocking_assignment(name).is_match(&code_after_power_on)
+}
+
+// Sets the power-on value of a register in the `initial` blocks of the custom code, if
+// it initializes the register itself
+fn set_initial_value(code: &str, name: &str, value: &VerilogLiteral) -> Option<String> {
+    let assignment = blocking_assignment(name);
+    let mut found = false;
+    let code = initial_blocks().replace_all(code, |block: &regex::Captures| {
+        let block = &block[0];
+        found |= assignment.is_match(block);
+        assignment
+            .replace_all(block, format!("{} = {:x};", name, value).as_str())
+            .to_string()
+    });
+    found.then(|| code.to_string())
+}
+
 fn get_link_equivalence(link: &VerilogLink) -> (String, String) {
     match link {
         VerilogLink::Forward(link) => (
@@ -221,12 +351,29 @@ impl ModuleDefines {
         &self,
         module_details: &ModuleDetails,
         child: &SubModuleInvocation,
+        names: &BTreeMap<String, String>,
         io: &mut CodeWriter,
     ) {
         let entry = self.details.get(&child.kind).unwrap();
         let submodule_kind = match &entry.code {
-            Verilog::Blackbox(b) => &b.name,
-            _ => &child.kind,
+            Verilog::Blackbox(b) => b.name.clone(),
+            _ => names[&child.kind].clone(),
+        };
+        let overrides = match &entry.parameters {
+            Some(params) => format!(
+                " #({})",
+                params
+                    .parameters
+                    .iter()
+                    .map(|x| format!(
+                        ".{}({})",
+                        x.name,
+                        self.parameter_override(module_details, child, &x.name, x.value)
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            None => String::new(),
         };
         let child_args = entry
             .atoms
@@ -244,12 +391,106 @@ impl ModuleDefines {
             })
             .collect::<Vec<_>>()
             .join(",\n");
-        io.add(format!("{} {}(\n", submodule_kind, child.name));
+        let attributes = field_attributes(&module_details.field_attributes, &child.name);
+        io.add(format!(
+            "{}{}{} {}(\n",
+            verilog_attributes(&attributes),
+            submodule_kind,
+            overrides,
+            child.name
+        ));
         io.push();
         io.add(child_args);
         io.pop();
         io.add(");\n");
     }
+    // The value of a parameter of a sub-block, which is either bound to one of our own
+    // parameters, or is a plain number
+    fn parameter_override(
+        &self,
+        module_details: &ModuleDetails,
+        child: &SubModuleInvocation,
+        parameter: &str,
+        value: usize,
+    ) -> String {
+        let params = match &module_details.parameters {
+            Some(params) => params,
+            None => return value.to_string(),
+        };
+        match params
+            .bindings
+            .iter()
+            .find(|b| is_field(&b.field, &child.name) && b.parameter == parameter)
+        {
+            Some(binding) => {
+                let mine = params
+                    .parameters
+                    .iter()
+                    .find(|x| x.name == binding.value)
+                    .unwrap();
+                assert_eq!(
+                    mine.value, value,
+                    "Parameter {} of {} is bound to {}, but their values differ",
+                    parameter, child.name, binding.value
+                );
+                binding.value.clone()
+            }
+            None => value.to_string(),
+        }
+    }
+    // The innermost parameterised block containing the path (or the path itself), which
+    // shares its module with the other instances of its type.  The blocks in `unshared`
+    // could not, and are treated like unparameterised blocks.
+    fn shared_root<'a>(&self, path: &'a str, unshared: &BTreeSet<String>) -> Option<&'a str> {
+        let mut prefix = path;
+        while let Some(ndx) = prefix.rfind('$') {
+            if !unshared.contains(prefix)
+                && self
+                    .details
+                    .get(prefix)
+                    .is_some_and(|x| x.parameters.is_some())
+            {
+                return Some(prefix);
+            }
+            prefix = &prefix[..ndx];
+        }
+        None
+    }
+    // Modules are named after their path in the design, except for parameterised blocks,
+    // which are named after their type, and the modules inside them, which are named
+    // relative to the parameterised block.  Types are named by their full (Rust) path
+    // when two of them have the same name.
+    fn module_names(&self, unshared: &BTreeSet<String>) -> BTreeMap<String, String> {
+        let mut types: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for path in self.details.keys() {
+            if let Some(root) = self.shared_root(path, unshared) {
+                let params = self.details[root].parameters.as_ref().unwrap();
+                types
+                    .entry(&params.module)
+                    .or_default()
+                    .insert(&params.path);
+            }
+        }
+        self.details
+            .keys()
+            .filter(|x| !x.is_empty())
+            .map(|path| {
+                let name = match self.shared_root(path, unshared) {
+                    Some(root) => {
+                        let params = self.details[root].parameters.as_ref().unwrap();
+                        let type_name = if types[params.module.as_str()].len() > 1 {
+                            params.path.replace("::", "$")
+                        } else {
+                            params.module.clone()
+                        };
+                        format!("{}{}", type_name, &path[root.len()..])
+                    }
+                    None => path.clone(),
+                };
+                (path.clone(), name)
+            })
+            .collect()
+    }
     fn module_argument_is_passed_through_to_submodule(
         &self,
         module_details: &ModuleDetails,
@@ -315,6 +556,8 @@ impl ModuleDefines {
         &self,
         module_name: &str,
         module_details: &ModuleDetails,
+        defaults: Option<&BlockParameters>,
+        names: &BTreeMap<String, String>,
         io: &mut CodeWriter,
     ) {
         // Remap the output parameters to pass through (net type) in case we have a wrapper
@@ -361,7 +604,26 @@ impl ModuleDefines {
             .map(|x| x.name.to_owned())
             .collect::<Vec<_>>()
             .join(",");
-        io.add(format!("\n\nmodule {}({});", module_name, module_args));
+        io.add("\n\n");
+        if !module_details.attributes.is_empty() {
+            io.add(verilog_attributes(&module_details.attributes).trim_end());
+        }
+        let parameters = match defaults {
+            Some(params) => format!(
+                " #({})",
+                params
+                    .parameters
+                    .iter()
+                    .map(|x| format!("parameter {} = {}", x.name, x.value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            None => String::new(),
+        };
+        io.add(format!(
+            "module {}{}({});",
+            module_name, parameters, module_args
+        ));
         io.push();
         if !args.is_empty() {
             io.add("\n// Module arguments");
@@ -387,10 +649,16 @@ impl ModuleDefines {
         if !module_details.enums.is_empty() & !wrapper_mode {
             io.add("\n// Enums");
             module_details.enums.iter().for_each(|x| {
+                // Unsized literals are only 32 bits, which is too small for wide one-hot states
+                let value = if x.value > i32::MAX as usize {
+                    format!("64'd{}", x.value)
+                } else {
+                    x.value.to_string()
+                };
                 io.add(format!(
                     "localparam {} = {};",
                     x.discriminant.replace("::", "$"),
-                    x.value
+                    value
                 ))
             });
         }
@@ -406,20 +674,52 @@ impl ModuleDefines {
             io.add("\n// Local signals");
             locals.iter().for_each(|x| io.add(decl(x)));
         }
+        let mut custom_code = match &module_details.code {
+            Verilog::Custom(code) => code.clone(),
+            _ => String::new(),
+        };
+        if self.options.initial_values {
+            if let Verilog::Custom(code) = &module_details.code {
+                let mut registers = vec![];
+                for atom in atoms
+                    .iter()
+                    .filter(|x| matches!(x.kind, AtomKind::OutputParameter | AtomKind::LocalSignal))
+                    .filter(|x| is_clocked_register(code, &x.name))
+                {
+                    match set_initial_value(&custom_code, &atom.name, &atom.const_val) {
+                        Some(code) => custom_code = code,
+                        None => registers.push(atom),
+                    }
+                }
+                if !registers.is_empty() {
+                    io.add("\n// Power-on values");
+                    io.add("initial begin");
+                    io.push();
+                    registers
+                        .iter()
+                        .for_each(|x| io.add(format!("{} = {};", x.name, x.const_val)));
+                    io.pop();
+                    io.add("end");
+                }
+            }
+        }
         if !submodules.is_empty() & !wrapper_mode {
             io.add("\n// Sub module instances");
             for child in submodules {
-                self.sub_module_invocation(module_details, child, io);
+                self.sub_module_invocation(module_details, child, names, io);
             }
         }
         match &module_details.code {
             Verilog::Combinatorial(code) => {
                 io.add("\n// Update code");
-                io.add(verilog_combinatorial(code));
+                io.add(verilog_combinatorial_with_options(
+                    code,
+                    self.options.source_locations,
+                ));
             }
-            Verilog::Custom(code) => {
+            Verilog::Custom(_) => {
                 io.add("\n// Update code (custom)");
-                io.add(code);
+                io.add(custom_code);
             }
             Verilog::Wrapper(c) => {
                 io.add("\n// Update code (wrapper)");
@@ -471,27 +771,106 @@ impl ModuleDefines {
     }
 
     pub fn defines(&self) -> String {
+        let mut unshared = BTreeSet::new();
+        let mut rendered = BTreeMap::new();
+        loop {
+            match self.render(&unshared, &mut rendered) {
+                Ok(text) => return text,
+                Err(roots) => unshared.extend(roots),
+            }
+        }
+    }
+
+    // All instances of a parameterised block (and everything inside them) share a module,
+    // which is declared with the parameter values of the first instance.  The instances
+    // that render differently are returned, so that they can get modules of their own.
+    // Modules are kept in `rendered` with the names they were rendered with, and are
+    // only rendered again when one of those names changes.
+    fn render(
+        &self,
+        unshared: &BTreeSet<String>,
+        rendered: &mut BTreeMap<String, (Vec<String>, String)>,
+    ) -> Result<String, Vec<String>> {
+        let names = self.module_names(unshared);
+        let mut first_instance: BTreeMap<&str, &str> = BTreeMap::new();
+        for (path, name) in &names {
+            first_instance.entry(name).or_insert(path);
+        }
+        let mut text = String::new();
+        let mut emitted: BTreeMap<&str, String> = BTreeMap::new();
+        let mut differ = vec![];
+        for (path, module_details) in self.details.iter().filter(|x| !x.0.is_empty()) {
+            let module_name = names[path].as_str();
+            if matches!(module_details.code, Verilog::Blackbox(_)) {
+                continue;
+            }
+            let first = first_instance[module_name];
+            let key = [module_name, first]
+                .iter()
+                .copied()
+                .chain(
+                    module_details
+                        .sub_modules
+                        .iter()
+                        .map(|x| names[&x.kind].as_str()),
+                )
+                .map(String::from)
+                .collect::<Vec<_>>();
+            let module = match rendered.get(path) {
+                Some((prev_key, module)) if prev_key == &key => module.clone(),
+                _ => {
+                    let defaults = self.details[first].parameters.as_ref();
+                    let mut io = CodeWriter::new();
+                    self.process_module(module_name, module_details, defaults, &names, &mut io);
+                    let module = io.to_string();
+                    rendered.insert(path.clone(), (key, module.clone()));
+                    module
+                }
+            };
+            match emitted.get(module_name) {
+                None => {
+                    text += &module;
+                    emitted.insert(module_name, module);
+                }
+                Some(prev) if prev != &module => {
+                    differ.push(self.shared_root(path, unshared).unwrap().to_string());
+                }
+                Some(_) => {}
+            }
+        }
+        if !differ.is_empty() {
+            return Err(differ);
+        }
         let mut io = CodeWriter::new();
-        self.details
-            .iter()
-            .filter(|x| x.0.len() != 0)
-            .filter(|x| !matches!(x.1.code, Verilog::Blackbox(_)))
-            .for_each(|k| {
-                let module_name = k.0;
-                let module_details = k.1;
-                self.process_module(module_name, module_details, &mut io);
-            });
-        self.details.iter().for_each(|x| match &x.1.code {
-            Verilog::Blackbox(b) => io.add(&b.code),
-            Verilog::Wrapper(w) => io.add(&w.cores),
-            _ => {}
-        });
-        io.to_string()
+        for (path, module_details) in self.details.iter().filter(|x| !x.0.is_empty()) {
+            if first_instance[names[path].as_str()] != path {
+                continue;
+            }
+            match &module_details.code {
+                Verilog::Blackbox(b) => io.add(&b.code),
+                Verilog::Wrapper(w) => io.add(&w.cores),
+                _ => {}
+            }
+        }
+        Ok(text + &io.to_string())
     }
 }
 
+/// Generate Verilog for a design.  The instances of a parameterised block (see
+/// [Parameter](crate::core::parameter::Parameter)) share one module, unless their modules
+/// differ in more than the widths of their signals.  Then they fall back to a module of
+/// their own, named after their path in the design (e.g., `top$second`), as if they had
+/// no parameters.  Look for those names in the output if you expected a single module.
 pub fn generate_verilog<U: Block>(uut: &U) -> String {
-    let mut defines = ModuleDefines::default();
+    generate_verilog_with_options(uut, VerilogOptions::default())
+}
+
+/// Generate Verilog for a design, with the given [VerilogOptions].
+pub fn generate_verilog_with_options<U: Block>(uut: &U, options: VerilogOptions) -> String {
+    let mut defines = ModuleDefines {
+        options,
+        ..Default::default()
+    };
     check_all(uut).unwrap(); // TODO - make this not panic...
     uut.accept("top", &mut defines);
     defines.defines()
@@ -0,0 +1,68 @@
+/// A Verilog `parameter` of a block.  Blocks declare their parameters with the
+/// `verilog_parameter` helper attribute of `#[derive(LogicBlock)]`, which maps const
+/// generics (or expressions like `T::BITS`) onto module parameters.  All instances of
+/// the block then share a single parameterised Verilog module (named after the block
+/// type, or after its full path if two block types of the same name are used in a
+/// design), and each instance overrides the parameters with `#(...)`:
+/// ```rust
+/// # use rust_hdl::core::prelude::*;
+/// #[derive(LogicBlock)]
+/// #[verilog_parameter(W)]
+/// struct Adder<const W: usize> {
+///     pub a: Signal<In, Bits<W>>,
+///     pub b: Signal<In, Bits<W>>,
+///     pub sum: Signal<Out, Bits<W>>,
+/// }
+///
+/// impl<const W: usize> Logic for Adder<W> {
+///     #[hdl_gen]
+///     fn update(&mut self) {
+///         self.sum.next = self.a.val() + self.b.val();
+///     }
+/// }
+/// ```
+/// An `Adder<16>` named `wide` is then instantiated as `Adder #(.W(16)) wide(...)`, and
+/// the ports of the `Adder` module are declared as `input wire [W-1:0] a`, etc.
+///
+/// Only the widths of the block's own signals (of type `Bits<N>`, `Signed<N>` or `T`
+/// for a parameter `P = T::BITS`) are expressed in terms of the parameters.  Everything
+/// else (the update code, constants, the widths of sub-blocks) must be the same for all
+/// instances, or be passed on to a parameterised sub-block by putting
+/// `#[verilog_parameter(CHILD = MINE)]` on its field.  Instances that cannot share the
+/// module get a module of their own (named after their path in the design, like
+/// unparameterised blocks), see [generate_verilog](crate::core::module_defines::generate_verilog).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    /// The name of the parameter
+    pub name: String,
+    /// The value of the parameter for this instance
+    pub value: usize,
+    /// The signals of the block whose width is given by the parameter
+    pub signals: Vec<String>,
+}
+
+/// Binds a parameter of a sub-block (in the `field` of a block) to one of the parameters
+/// of the block itself, so that the sub-block instance is written as `#(.W(N))`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterBinding {
+    /// The field holding the sub-block
+    pub field: String,
+    /// The parameter of the sub-block
+    pub parameter: String,
+    /// The parameter of the block it is bound to
+    pub value: String,
+}
+
+/// The Verilog parameters of a block, as generated by `#[derive(LogicBlock)]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockParameters {
+    /// The name of the shared Verilog module (the name of the block type)
+    pub module: String,
+    /// The full path of the block type (e.g., `my_crate::widgets::Adder`), which names
+    /// the module instead if two block types share a name
+    pub path: String,
+    /// The parameters, in declaration order
+    pub parameters: Vec<Parameter>,
+    /// The parameters passed on to sub-blocks
+    pub bindings: Vec<ParameterBinding>,
+}
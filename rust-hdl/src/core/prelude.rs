@@ -1,15 +1,23 @@
 pub use crate::clock;
+pub use crate::concat_bits;
 pub use crate::core::ast;
 pub use crate::core::ast::BlackBox;
+pub use crate::core::ast::SourceLocation;
 pub use crate::core::ast::Verilog;
 pub use crate::core::ast::VerilogLiteral;
 pub use crate::core::ast::Wrapper;
+pub use crate::core::async_simulate::{AsyncSim, AsyncSimulation};
 pub use crate::core::atom::{Atom, AtomKind};
+pub use crate::core::attribute::{Attribute, BlockAttribute};
 pub use crate::core::bits::bit_cast;
 pub use crate::core::bits::bits;
 pub use crate::core::bits::clog2;
+pub use crate::core::bits::hex_bits;
 pub use crate::core::bits::LiteralType;
 pub use crate::core::bits::ToBits;
+pub use crate::core::bits::{count_ones, leading_zeros, replicate, reverse_bits};
+pub use crate::core::bits::{count_ones_hdl, leading_zeros_hdl, reverse_bits_hdl};
+pub use crate::core::bits::{rotate_left, rotate_left_hdl, rotate_right, rotate_right_hdl};
 pub use crate::core::bits::{Bit, Bits};
 pub use crate::core::block;
 pub use crate::core::block::Block;
@@ -22,30 +30,51 @@ pub use crate::core::constant::Constant;
 pub use crate::core::constraint::Timing::*;
 pub use crate::core::constraint::*;
 pub use crate::core::direction::{Direction, In, InOut, Local, Out};
+pub use crate::core::fixed::{
+    fixed_cast, fixed_mul, ufixed_cast, ufixed_mul, Fixed, Overflow, Rounding, UFixed,
+};
+pub use crate::core::fixed::{fixed_cast_hdl, fixed_mul_hdl, ufixed_cast_hdl, ufixed_mul_hdl};
+pub use crate::core::four_state;
+pub use crate::core::hdl_test::{HdlTest, HdlTestError, WaveCapture};
+pub use crate::core::hierarchy::{
+    design_hierarchy, generate_block_diagram, generate_hierarchy_json,
+};
 pub use crate::core::logic;
 pub use crate::core::logic::Logic;
 pub use crate::core::logic::LogicJoin;
 pub use crate::core::logic::LogicLink;
 pub use crate::core::module_defines::ModuleDefines;
-pub use crate::core::module_defines::{generate_verilog, generate_verilog_unchecked};
+pub use crate::core::module_defines::{
+    generate_verilog, generate_verilog_unchecked, generate_verilog_with_options, VerilogOptions,
+};
 pub use crate::core::named_path::NamedPath;
 pub use crate::core::probe;
-pub use crate::core::probe::Probe;
+pub use crate::core::probe::{Probe, ProbeMut};
 pub use crate::core::signal::Signal;
+pub use crate::core::signal_path::{
+    force_signal, read_signal, read_signal_as, signal_paths, SignalPathError,
+};
 pub use crate::core::signed::ToSignedBits;
 pub use crate::core::signed::{
     signed, signed_bit_cast, signed_cast, unsigned_bit_cast, unsigned_cast, Signed,
 };
+pub use crate::core::sim_debugger::Debugger;
 pub use crate::core::simulate::sim_time;
 pub use crate::core::simulate::simulate;
 pub use crate::core::simulate::SIMULATION_TIME_ONE_SECOND;
-pub use crate::core::simulate::{Sim, SimError, Simulation};
+pub use crate::core::simulate::{run_with_seeds, Sim, SimError, Simulation};
+pub use crate::core::synth::vcd_unknown;
 pub use crate::core::synth::Synth;
 pub use crate::core::synth::VCDValue;
-pub use crate::core::type_descriptor::{TypeDescriptor, TypeField, TypeKind};
+pub use crate::core::type_descriptor::{EnumVariant, TypeDescriptor, TypeField, TypeKind};
 pub use crate::core::vcd_probe::{write_vcd_change, write_vcd_dump, write_vcd_header};
 pub use crate::core::verilog_gen::filter_blackbox_directives;
+pub use crate::core::verilog_gen::source_location;
 pub use crate::core::verilog_gen::VerilogCodeGenerator;
+pub use crate::core::verilog_snapshot::{
+    assert_verilog_snapshot, check_verilog_snapshot, normalize_verilog, unified_diff,
+    SnapshotError, SnapshotStatus,
+};
 pub use crate::core::verilog_visitor::VerilogVisitor;
 pub use crate::core::yosys::*;
 pub use crate::dff_setup;
@@ -54,8 +83,11 @@ pub use crate::sim_assert_eq;
 pub use crate::simple_sim;
 pub use crate::target_path;
 pub use crate::vcd_path;
+pub use crate::verilog_snapshot;
 pub use crate::wait_clock_cycle;
 pub use crate::wait_clock_cycles;
 pub use crate::wait_clock_false;
 pub use crate::wait_clock_true;
-pub use rust_hdl_macros::{hdl_gen, LogicBlock, LogicInterface, LogicState, LogicStruct};
+pub use rust_hdl_macros::{
+    hdl_fn, hdl_gen, hdl_test, LogicBlock, LogicInterface, LogicState, LogicStruct,
+};
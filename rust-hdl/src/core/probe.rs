@@ -1,4 +1,4 @@
-use crate::core::atom::Atom;
+use crate::core::atom::{Atom, AtomMut};
 use crate::core::block::Block;
 
 pub trait Probe {
@@ -8,3 +8,12 @@ pub trait Probe {
     fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {}
     fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {}
 }
+
+/// A visitor with mutable access to the signals of a circuit.  See [Block::accept_mut].
+pub trait ProbeMut {
+    fn visit_start_scope(&mut self, _name: &str) {}
+    fn visit_start_namespace(&mut self, _name: &str) {}
+    fn visit_atom(&mut self, _name: &str, _signal: &mut dyn AtomMut) {}
+    fn visit_end_namespace(&mut self, _name: &str) {}
+    fn visit_end_scope(&mut self, _name: &str) {}
+}
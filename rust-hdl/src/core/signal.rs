@@ -2,16 +2,18 @@ use std::marker::PhantomData;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::core::ast::{VerilogLink, VerilogLinkDetails, VerilogLiteral};
-use crate::core::atom::{Atom, AtomKind};
+use crate::core::atom::{Atom, AtomKind, AtomMut};
+use crate::core::attribute::Attribute;
 use crate::core::bits::Bit;
 use crate::core::block::Block;
 use crate::core::clock::Clock;
 use crate::core::constraint::{Constraint, PinConstraint, SignalType};
 use crate::core::direction::{Direction, In, Local, Out};
+use crate::core::four_state::{is_four_state, UnknownMask};
 use crate::core::logic::{Logic, LogicJoin, LogicLink};
 use crate::core::prelude::{InOut, TypeDescriptor};
-use crate::core::probe::Probe;
-use crate::core::synth::{Synth, VCDValue};
+use crate::core::probe::{Probe, ProbeMut};
+use crate::core::synth::{vcd_fill, vcd_mark_unknown, Synth, VCDValue};
 
 static GLOBAL_THREAD_COUNT: AtomicUsize = AtomicUsize::new(1);
 
@@ -29,13 +31,17 @@ pub struct Signal<D: Direction, T: Synth> {
     id: usize,
     tristate_is_output: bool,
     signal_is_undriven: bool,
+    unknown: UnknownMask,
+    next_unknown: UnknownMask,
     constraints: Vec<PinConstraint>,
+    attributes: Vec<Attribute>,
     dir: std::marker::PhantomData<D>,
 }
 
 impl<T: Synth> Signal<In, T> {
     pub fn join(&mut self, other: &mut Signal<Out, T>) {
         self.next = other.val();
+        self.next_unknown = other.unknown_mask();
     }
     pub fn join_hdl(my_name: &str, owner_name: &str, other_name: &str) -> Vec<VerilogLink> {
         let details = VerilogLinkDetails {
@@ -50,6 +56,7 @@ impl<T: Synth> Signal<In, T> {
 impl<T: Synth> Signal<Out, T> {
     pub fn join(&mut self, other: &mut Signal<In, T>) {
         other.next = self.val();
+        other.next_unknown = self.unknown_mask();
     }
     pub fn join_hdl(my_name: &str, owner_name: &str, other_name: &str) -> Vec<VerilogLink> {
         let details = VerilogLinkDetails {
@@ -79,6 +86,7 @@ impl<T: Synth> LogicJoin for Signal<InOut, T> {
 impl<T: Synth> LogicLink for Signal<In, T> {
     fn link(&mut self, other: &mut Self) {
         other.next = self.val();
+        other.next_unknown = self.unknown_mask();
     }
     fn link_hdl(my_name: &str, owner_name: &str, other_name: &str) -> Vec<VerilogLink> {
         let details = VerilogLinkDetails {
@@ -99,6 +107,7 @@ impl<T: Synth> LogicLink for Signal<In, T> {
 impl<T: Synth> LogicLink for Signal<Out, T> {
     fn link(&mut self, other: &mut Self) {
         self.next = other.val();
+        self.next_unknown = other.unknown_mask();
     }
     fn link_hdl(my_name: &str, owner_name: &str, other_name: &str) -> Vec<VerilogLink> {
         let details = VerilogLinkDetails {
@@ -123,8 +132,10 @@ impl<T: Synth> LogicLink for Signal<InOut, T> {
         self.tristate_is_output = other.tristate_is_output;
         if other.tristate_is_output {
             self.next = other.val();
+            self.next_unknown = other.unknown.clone();
         } else {
             other.next = self.val();
+            other.next_unknown = self.unknown.clone();
         }
         self.signal_is_undriven = other.signal_is_undriven;
     }
@@ -163,6 +174,33 @@ impl<D: Direction, T: Synth> Signal<D, T> {
             constraint: Constraint::Kind(signal),
         });
     }
+    /// Attach a Verilog [Attribute] (such as `(* keep *)`) to the declaration of this
+    /// signal in the generated HDL.
+    pub fn add_attribute(&mut self, attribute: Attribute) {
+        self.attributes.push(attribute);
+    }
+    /// Mark all bits of the next value of the signal as unknown (X) or known.  Used by the
+    /// four-state simulation mode (see [four_state](crate::core::four_state)).
+    pub fn set_next_unknown(&mut self, unknown: bool) {
+        self.next_unknown = if unknown {
+            UnknownMask::all(T::BITS)
+        } else {
+            UnknownMask::default()
+        };
+    }
+    /// Mark `count` bits of the next value of the signal, starting at `index`, as unknown
+    /// (X) or known.
+    pub fn set_next_unknown_bits(&mut self, index: usize, count: usize, unknown: bool) {
+        self.next_unknown.set_bits(index, count, unknown);
+    }
+    /// Set the unknown (X) bits of the next value of the signal.
+    pub fn set_next_unknown_mask(&mut self, mask: UnknownMask) {
+        self.next_unknown = mask;
+    }
+    /// Returns `true` if any bit of the next value of the signal is unknown (X).
+    pub fn is_next_unknown(&self) -> bool {
+        self.next_unknown.any()
+    }
 }
 
 impl<D: Direction, T: Synth> Atom for Signal<D, T> {
@@ -187,10 +225,12 @@ impl<D: Direction, T: Synth> Atom for Signal<D, T> {
     }
 
     fn vcd(&self) -> VCDValue {
-        if !self.signal_is_undriven {
-            self.val.vcd()
+        if self.signal_is_undriven {
+            vcd_fill(&T::descriptor(), vcd::Value::Z)
+        } else if self.unknown.any() {
+            vcd_mark_unknown(self.val.vcd(), &T::descriptor(), &self.unknown)
         } else {
-            VCDValue::Vector(vec![vcd::Value::Z; T::BITS])
+            self.val.vcd()
         }
     }
 
@@ -205,6 +245,23 @@ impl<D: Direction, T: Synth> Atom for Signal<D, T> {
     fn constraints(&self) -> Vec<PinConstraint> {
         self.constraints.clone()
     }
+
+    fn attributes(&self) -> Vec<Attribute> {
+        self.attributes.clone()
+    }
+}
+
+impl<D: Direction, T: Synth> AtomMut for Signal<D, T> {
+    fn force(&mut self, value: &VCDValue) -> bool {
+        match T::from_vcd(value) {
+            Some(x) => {
+                self.next = x;
+                self.next_unknown = UnknownMask::default();
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 impl<D: Direction, T: Synth> Logic for Signal<D, T> {
@@ -218,10 +275,11 @@ impl<D: Direction, T: Synth> Block for Signal<D, T> {
     fn connect_all(&mut self) {}
 
     fn update_all(&mut self) {
-        self.changed = self.val != self.next;
+        self.changed = self.val != self.next || self.unknown != self.next_unknown;
         if self.changed {
             self.prev = self.val;
             self.val = self.next;
+            self.unknown = self.next_unknown.clone();
         }
     }
 
@@ -232,6 +290,10 @@ impl<D: Direction, T: Synth> Block for Signal<D, T> {
     fn accept(&self, name: &str, probe: &mut dyn Probe) {
         probe.visit_atom(name, self);
     }
+
+    fn accept_mut(&mut self, name: &str, probe: &mut dyn ProbeMut) {
+        probe.visit_atom(name, self);
+    }
 }
 
 impl Signal<In, Clock> {
@@ -256,12 +318,29 @@ impl<T: Synth> Signal<Out, T> {
             id: get_signal_id(),
             tristate_is_output: false,
             signal_is_undriven: false,
+            unknown: UnknownMask::default(),
+            next_unknown: UnknownMask::default(),
             constraints: vec![],
+            attributes: vec![],
             dir: PhantomData,
         }
     }
 }
 
+impl<T: Synth> Signal<Out, T> {
+    /// Construct an output signal whose initial value is not defined, such as
+    /// the output of a register.  In four-state simulation mode (see
+    /// [four_state](crate::core::four_state)) the signal starts as unknown (X).
+    pub fn new_uninitialized() -> Signal<Out, T> {
+        let mut ret = Self::default();
+        if is_four_state() {
+            ret.unknown = UnknownMask::all(T::BITS);
+            ret.next_unknown = ret.unknown.clone();
+        }
+        ret
+    }
+}
+
 impl<D: Direction> Signal<D, Bit> {
     pub fn pin_signal(location: &str, kind: SignalType) -> Signal<D, Bit> {
         let mut ret = Signal::default();
@@ -282,7 +361,10 @@ impl<D: Direction, T: Synth> Default for Signal<D, T> {
             id: get_signal_id(),
             tristate_is_output: false,
             signal_is_undriven: false,
+            unknown: UnknownMask::default(),
+            next_unknown: UnknownMask::default(),
             constraints: vec![],
+            attributes: vec![],
             dir: PhantomData,
         }
     }
@@ -303,10 +385,12 @@ impl<T: Synth> Signal<InOut, T> {
         //        assert!(!(self.is_driving_tristate() & other.is_driving_tristate()));
         if self.is_driving_tristate() {
             other.next = self.val();
+            other.next_unknown = self.unknown.clone();
             self.signal_is_undriven = false;
             other.signal_is_undriven = false;
         } else if other.is_driving_tristate() {
             self.next = other.val();
+            self.next_unknown = other.unknown.clone();
             self.signal_is_undriven = false;
             other.signal_is_undriven = false;
         } else {
@@ -341,22 +425,59 @@ impl<T: Synth> Signal<Local, T> {
     pub fn val(&self) -> T {
         self.next
     }
+    /// Returns `true` if any bit of the value returned by `val()` is unknown (X)
+    pub fn is_unknown(&self) -> bool {
+        self.next_unknown.any()
+    }
+    /// The unknown (X) bits of the value returned by `val()`
+    pub fn unknown_mask(&self) -> UnknownMask {
+        self.next_unknown.clone()
+    }
 }
 
 impl<T: Synth> Signal<In, T> {
     pub fn val(&self) -> T {
         self.val
     }
+    /// Returns `true` if any bit of the value returned by `val()` is unknown (X)
+    pub fn is_unknown(&self) -> bool {
+        self.unknown.any()
+    }
+    /// The unknown (X) bits of the value returned by `val()`
+    pub fn unknown_mask(&self) -> UnknownMask {
+        self.unknown.clone()
+    }
 }
 
 impl<T: Synth> Signal<Out, T> {
     pub fn val(&self) -> T {
         self.next
     }
+    /// Returns `true` if any bit of the value returned by `val()` is unknown (X)
+    pub fn is_unknown(&self) -> bool {
+        self.next_unknown.any()
+    }
+    /// The unknown (X) bits of the value returned by `val()`
+    pub fn unknown_mask(&self) -> UnknownMask {
+        self.next_unknown.clone()
+    }
 }
 
 impl<T: Synth> Signal<InOut, T> {
     pub fn val(&self) -> T {
         self.val
     }
+    /// Returns `true` if any bit of the value returned by `val()` is unknown (X).  An
+    /// undriven signal reads as unknown in four-state mode.
+    pub fn is_unknown(&self) -> bool {
+        self.unknown.any() || (self.signal_is_undriven && is_four_state())
+    }
+    /// The unknown (X) bits of the value returned by `val()`
+    pub fn unknown_mask(&self) -> UnknownMask {
+        if self.signal_is_undriven && is_four_state() {
+            UnknownMask::all(T::BITS)
+        } else {
+            self.unknown.clone()
+        }
+    }
 }
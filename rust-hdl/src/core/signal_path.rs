@@ -0,0 +1,171 @@
+//! Access to the signals of a circuit by their hierarchical path.
+//!
+//! Normally, a testbench reads and writes signals through the fields of the circuit,
+//! e.g., `x.controller.state.q.val()`.  The functions in this module provide the
+//! same access at runtime, using a path string like `"controller.state.q"`.  This is
+//! useful for table driven tests, scripting a simulation from outside of Rust, and
+//! debugging.
+//!
+//! Paths are relative to the block passed in, and use the field names separated
+//! by `.`.  Elements of arrays and vectors of blocks are named `field$N`.  Use
+//! [signal_paths] to list the paths in a circuit.
+//!
+//! ```rust
+//! # use rust_hdl::prelude::*;
+//! let mut uut = Strobe::<32>::new(1_000_000, 1000.0);
+//! uut.connect_all();
+//! force_signal(&mut uut, "enable", true).unwrap();
+//! assert!(uut.enable.next);
+//! assert_eq!(read_signal_as::<Bits<32>>(&uut, "counter.q").unwrap(), 0);
+//! ```
+use crate::core::atom::{Atom, AtomKind, AtomMut};
+use crate::core::block::Block;
+use crate::core::probe::{Probe, ProbeMut};
+use crate::core::synth::{Synth, VCDValue};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignalPathError {
+    /// There is no signal with the given path
+    NotFound(String),
+    /// The signal is not an input, and cannot be forced
+    NotAnInput(String),
+    /// The value does not match the type of the signal
+    InvalidValue(String),
+}
+
+#[derive(Default)]
+struct PathTracker {
+    path: Vec<String>,
+}
+
+impl PathTracker {
+    fn push(&mut self, name: &str) {
+        self.path.push(name.into());
+    }
+    fn pop(&mut self) {
+        self.path.pop();
+    }
+    // The path of a signal, skipping the name of the top level block
+    fn signal(&self, name: &str) -> String {
+        self.path
+            .iter()
+            .skip(1)
+            .map(|x| x.as_str())
+            .chain(std::iter::once(name))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+#[derive(Default)]
+struct ReadProbe {
+    tracker: PathTracker,
+    target: String,
+    paths: Vec<String>,
+    value: Option<VCDValue>,
+}
+
+impl Probe for ReadProbe {
+    fn visit_start_scope(&mut self, name: &str, _node: &dyn Block) {
+        self.tracker.push(name);
+    }
+    fn visit_start_namespace(&mut self, name: &str, _node: &dyn Block) {
+        self.tracker.push(name);
+    }
+    fn visit_atom(&mut self, name: &str, signal: &dyn Atom) {
+        let path = self.tracker.signal(name);
+        if path == self.target {
+            self.value = Some(signal.vcd());
+        }
+        self.paths.push(path);
+    }
+    fn visit_end_namespace(&mut self, _name: &str, _node: &dyn Block) {
+        self.tracker.pop();
+    }
+    fn visit_end_scope(&mut self, _name: &str, _node: &dyn Block) {
+        self.tracker.pop();
+    }
+}
+
+struct ForceProbe<'a> {
+    tracker: PathTracker,
+    target: &'a str,
+    value: &'a VCDValue,
+    result: Result<(), SignalPathError>,
+}
+
+impl<'a> ProbeMut for ForceProbe<'a> {
+    fn visit_start_scope(&mut self, name: &str) {
+        self.tracker.push(name);
+    }
+    fn visit_start_namespace(&mut self, name: &str) {
+        self.tracker.push(name);
+    }
+    fn visit_atom(&mut self, name: &str, signal: &mut dyn AtomMut) {
+        if self.tracker.signal(name) != self.target {
+            return;
+        }
+        let path = self.target.to_string();
+        self.result = match signal.kind() {
+            AtomKind::InputParameter | AtomKind::InOutParameter => {
+                if signal.force(self.value) {
+                    Ok(())
+                } else {
+                    Err(SignalPathError::InvalidValue(path))
+                }
+            }
+            _ => Err(SignalPathError::NotAnInput(path)),
+        };
+    }
+    fn visit_end_namespace(&mut self, _name: &str) {
+        self.tracker.pop();
+    }
+    fn visit_end_scope(&mut self, _name: &str) {
+        self.tracker.pop();
+    }
+}
+
+/// List the paths of all of the signals in the circuit
+pub fn signal_paths(uut: &dyn Block) -> Vec<String> {
+    let mut probe = ReadProbe::default();
+    uut.accept("top", &mut probe);
+    probe.paths
+}
+
+/// Read the current value of the signal with the given path
+pub fn read_signal(uut: &dyn Block, path: &str) -> Result<VCDValue, SignalPathError> {
+    let mut probe = ReadProbe {
+        target: path.into(),
+        ..Default::default()
+    };
+    uut.accept("top", &mut probe);
+    probe
+        .value
+        .ok_or_else(|| SignalPathError::NotFound(path.into()))
+}
+
+/// Read the current value of the signal with the given path as type `T`.  For
+/// example, `read_signal_as::<Bits<8>>(&uut, "count")`.
+pub fn read_signal_as<T: Synth>(uut: &dyn Block, path: &str) -> Result<T, SignalPathError> {
+    T::from_vcd(&read_signal(uut, path)?).ok_or_else(|| SignalPathError::InvalidValue(path.into()))
+}
+
+/// Force the input signal with the given path to a value.  This is equivalent to
+/// assigning to the `next` field of the signal in a testbench.  Only inputs (and
+/// tristate signals) of the top level circuit should be forced, since other inputs are
+/// driven by the circuit itself.
+pub fn force_signal<V: Into<VCDValue>>(
+    uut: &mut dyn Block,
+    path: &str,
+    value: V,
+) -> Result<(), SignalPathError> {
+    let value = value.into();
+    let mut probe = ForceProbe {
+        tracker: Default::default(),
+        target: path,
+        value: &value,
+        result: Err(SignalPathError::NotFound(path.into())),
+    };
+    uut.accept_mut("top", &mut probe);
+    probe.result
+}
@@ -2,7 +2,7 @@ use super::bits::Bits;
 use crate::core::bits::{bit_cast, LiteralType, LITERAL_BITS};
 use num_bigint::{BigInt, Sign};
 use num_traits::cast::ToPrimitive;
-use std::fmt::{Debug, Formatter, LowerHex, UpperHex};
+use std::fmt::{Debug, Display, Formatter, LowerHex, UpperHex};
 use std::num::Wrapping;
 
 pub type SignedLiteralType = i64;
@@ -43,6 +43,18 @@ impl ToSignedBits for i64 {
     }
 }
 
+/// Allows you to format a [Signed] as a (signed) decimal number
+/// ```
+/// # use rust_hdl::core::prelude::*;
+/// let y: Signed<8> = signed(-42);
+/// assert_eq!(format!("y = {}", y), "y = -42");
+/// ```
+impl<const N: usize> Display for Signed<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.bigint(), f)
+    }
+}
+
 impl<const N: usize> LowerHex for Signed<N> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         LowerHex::fmt(&self.bigint(), f)
@@ -92,6 +104,28 @@ impl<const N: usize> Signed<N> {
     pub fn inner(&self) -> Bits<N> {
         self.0
     }
+    /// Arithmetic shift right, which fills the vacated bits with copies of the sign bit.
+    /// In HDL code, this is the Verilog `>>>` operator.
+    /// ```
+    /// # use rust_hdl::core::prelude::*;
+    /// let x: Signed<8> = signed(-100);
+    /// assert_eq!(x.ashr(2), signed(-25));
+    /// ```
+    pub fn ashr(&self, n: usize) -> Signed<N> {
+        if n >= N {
+            return if self.sign_bit() {
+                Signed(Bits::<N>::mask())
+            } else {
+                Signed(Bits::default())
+            };
+        }
+        let shifted = self.0 >> n as LiteralType;
+        if self.sign_bit() {
+            Signed(shifted | !(Bits::<N>::mask() >> n as LiteralType))
+        } else {
+            Signed(shifted)
+        }
+    }
 }
 
 impl<const N: usize> From<BigInt> for Signed<N> {
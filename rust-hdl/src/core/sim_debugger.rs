@@ -0,0 +1,431 @@
+//! An interactive debugger for a [Simulation](crate::core::simulate::Simulation).
+//!
+//! When a testbench fails deep into a run, it is often easier to stop the simulation
+//! just before the failure and look around than to dump a trace and open it in a
+//! waveform viewer.  Pass a [Debugger] to [Simulation::run_debug](crate::core::simulate::Simulation::run_debug)
+//! and the simulation will pause:
+//!  - at the start of the simulation (unless disabled with [Debugger::stop_at_start]),
+//!  - when the simulation time reaches a breakpoint set with [Debugger::break_at],
+//!  - when a condition set with [Debugger::break_when] becomes true,
+//!  - when a watched signal changes value (see [Debugger::watch]),
+//!  - when a testbench halts (e.g., because a `sim_assert!` failed).
+//!
+//! While paused, the debugger reads commands from the terminal (type `help` for a
+//! list).  Signals are named by their hierarchical path, as used by [read_signal].
+//!
+//! ```rust,no_run
+//! # use rust_hdl::prelude::*;
+//! let mut sim = Simulation::new();
+//! sim.add_clock(5, |x: &mut Box<Strobe<32>>| x.clock.next = !x.clock.val());
+//! sim.add_testbench(|mut sim: Sim<Strobe<32>>| {
+//!     let mut x = sim.init()?;
+//!     x.enable.next = true;
+//!     wait_clock_cycles!(sim, clock, x, 1000);
+//!     sim.done(x)
+//! });
+//! let mut debugger = Debugger::new()
+//!     .break_at(2_000)
+//!     .break_when("strobe", |x: &Strobe<32>| x.strobe.val())
+//!     .watch("counter.q");
+//! sim.run_debug(Box::new(Strobe::new(1_000_000, 1000.0)), 1_000_000, &mut debugger)
+//!     .unwrap();
+//! ```
+use crate::core::block::Block;
+use crate::core::signal_path::{read_signal, signal_paths};
+use crate::core::simulate::SimError;
+use crate::core::synth::VCDValue;
+use num_bigint::BigUint;
+use std::cell::RefCell;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+const HELP: &str = "\
+Commands:
+  c, continue          Continue until the next breakpoint
+  s, step [N]          Run N simulation events (default 1)
+  n, next PATH [N]     Run until N rising edges (default 1) of the signal at PATH
+  r, run DELTA         Run for DELTA picoseconds
+  p, print PATH        Show the value of the signal at PATH
+  ls, list [PREFIX]    List the signal paths starting with PREFIX
+  b, break TIME        Pause when the simulation reaches TIME
+  w, watch PATH        Pause when the signal at PATH changes
+  unwatch PATH         Remove a watchpoint
+  info                 Show the breakpoints and watchpoints
+  dump FILE            Write the trace so far to a VCD file
+  t, time              Show the simulation time
+  q, quit              Stop the simulation
+";
+
+/// A `Write` that appends to a buffer shared with the debugger, so that the trace
+/// recorded so far can be dumped while the simulation is running.
+#[derive(Clone, Default)]
+pub(crate) struct TraceBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for TraceBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum DebugAction {
+    Resume,
+    Quit,
+}
+
+enum Breakpoint<T> {
+    Time(u64),
+    When {
+        name: String,
+        condition: Box<dyn Fn(&T) -> bool>,
+        active: bool,
+    },
+}
+
+struct Watchpoint {
+    path: String,
+    value: Option<VCDValue>,
+}
+
+enum RunMode {
+    Continue,
+    Steps(u64),
+    Until(u64),
+    Edges {
+        path: String,
+        count: u64,
+        last: bool,
+    },
+}
+
+/// An interactive debugger for a simulation.  See the [module documentation](self)
+/// for details.
+pub struct Debugger<T> {
+    breakpoints: Vec<Breakpoint<T>>,
+    watchpoints: Vec<Watchpoint>,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    trace: TraceBuffer,
+    stop_at_start: bool,
+    break_on_halt: bool,
+    mode: RunMode,
+    detached: bool,
+}
+
+impl<T: Block> Default for Debugger<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Format a signal value for display in the debugger.  Vectors are shown in hex
+/// (or binary if they contain `x` or `z` bits).
+pub fn format_vcd_value(value: &VCDValue) -> String {
+    match value {
+        VCDValue::Single(x) => x.to_string(),
+        VCDValue::Vector(bits) => {
+            let binary = bits.iter().map(|x| x.to_string()).collect::<String>();
+            match BigUint::parse_bytes(binary.as_bytes(), 2) {
+                Some(x) => format!("0h{:x}", x),
+                None => format!("0b{}", binary),
+            }
+        }
+        VCDValue::String(x) => x.clone(),
+        VCDValue::Real(x) => x.to_string(),
+        VCDValue::Composite(x) => format!(
+            "{{{}}}",
+            x.iter()
+                .map(|x| format_vcd_value(x))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+impl<T: Block> Debugger<T> {
+    /// Create a debugger that reads commands from stdin and writes to stdout
+    pub fn new() -> Self {
+        Self::with_io(std::io::BufReader::new(std::io::stdin()), std::io::stdout())
+    }
+    /// Create a debugger that reads commands from `input` and writes to `output`.
+    /// This can be used to script a debugging session.
+    pub fn with_io<R: BufRead + 'static, W: Write + 'static>(input: R, output: W) -> Self {
+        Self {
+            breakpoints: vec![],
+            watchpoints: vec![],
+            input: Box::new(input),
+            output: Box::new(output),
+            trace: Default::default(),
+            stop_at_start: true,
+            break_on_halt: true,
+            mode: RunMode::Continue,
+            detached: false,
+        }
+    }
+    /// Pause when the simulation time reaches `time`
+    pub fn break_at(mut self, time: u64) -> Self {
+        self.breakpoints.push(Breakpoint::Time(time));
+        self
+    }
+    /// Pause when `condition` becomes true.  The `name` is shown when the
+    /// breakpoint is hit.
+    pub fn break_when<F: Fn(&T) -> bool + 'static>(mut self, name: &str, condition: F) -> Self {
+        self.breakpoints.push(Breakpoint::When {
+            name: name.into(),
+            condition: Box::new(condition),
+            active: false,
+        });
+        self
+    }
+    /// Pause when the signal with the given path changes value
+    pub fn watch(mut self, path: &str) -> Self {
+        self.watchpoints.push(Watchpoint {
+            path: path.into(),
+            value: None,
+        });
+        self
+    }
+    /// Set whether the debugger pauses before the simulation starts (the default is `true`)
+    pub fn stop_at_start(self, stop_at_start: bool) -> Self {
+        Self {
+            stop_at_start,
+            ..self
+        }
+    }
+    /// Set whether the debugger pauses when a testbench halts the simulation (the
+    /// default is `true`)
+    pub fn break_on_halt(self, break_on_halt: bool) -> Self {
+        Self {
+            break_on_halt,
+            ..self
+        }
+    }
+    /// The VCD trace of the simulation so far
+    pub fn trace(&self) -> Vec<u8> {
+        self.trace.0.borrow().clone()
+    }
+    pub(crate) fn trace_buffer(&self) -> TraceBuffer {
+        self.trace.clone()
+    }
+    pub(crate) fn start(&mut self, time: u64, x: &T) -> DebugAction {
+        for watch in &mut self.watchpoints {
+            watch.value = read_signal(x, &watch.path).ok();
+        }
+        if self.stop_at_start {
+            self.pause(time, x, &["start of simulation".to_string()])
+        } else {
+            DebugAction::Resume
+        }
+    }
+    pub(crate) fn halted(&mut self, time: u64, x: &T, err: &SimError) -> DebugAction {
+        if self.break_on_halt {
+            self.pause(time, x, &[format!("simulation halted: {:?}", err)])
+        } else {
+            DebugAction::Resume
+        }
+    }
+    pub(crate) fn step(&mut self, time: u64, x: &T) -> DebugAction {
+        let mut reasons = vec![];
+        let breakpoints = std::mem::take(&mut self.breakpoints);
+        for breakpoint in breakpoints {
+            match breakpoint {
+                Breakpoint::Time(t) if t <= time => {
+                    reasons.push(format!("breakpoint at time {}", t));
+                }
+                Breakpoint::When {
+                    name,
+                    condition,
+                    active,
+                } => {
+                    let now = condition(x);
+                    if now && !active {
+                        reasons.push(format!("breakpoint {}", name));
+                    }
+                    self.breakpoints.push(Breakpoint::When {
+                        name,
+                        condition,
+                        active: now,
+                    });
+                }
+                b => self.breakpoints.push(b),
+            }
+        }
+        for watch in &mut self.watchpoints {
+            let value = read_signal(x, &watch.path).ok();
+            if value != watch.value {
+                let show = |x: &Option<VCDValue>| {
+                    x.as_ref()
+                        .map(format_vcd_value)
+                        .unwrap_or_else(|| "?".into())
+                };
+                reasons.push(format!(
+                    "watch {}: {} -> {}",
+                    watch.path,
+                    show(&watch.value),
+                    show(&value)
+                ));
+                watch.value = value;
+            }
+        }
+        match &mut self.mode {
+            RunMode::Continue => {}
+            RunMode::Steps(count) => {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    reasons.push("step".into());
+                }
+            }
+            RunMode::Until(t) => {
+                if time >= *t {
+                    reasons.push("run complete".into());
+                }
+            }
+            RunMode::Edges { path, count, last } => {
+                let high = read_signal(x, path) == Ok(VCDValue::from(true));
+                if high && !*last {
+                    *count = count.saturating_sub(1);
+                    if *count == 0 {
+                        reasons.push(format!("rising edge of {}", path));
+                    }
+                }
+                *last = high;
+            }
+        }
+        if reasons.is_empty() {
+            DebugAction::Resume
+        } else {
+            self.pause(time, x, &reasons)
+        }
+    }
+    fn pause(&mut self, time: u64, x: &T, reasons: &[String]) -> DebugAction {
+        self.mode = RunMode::Continue;
+        if self.detached {
+            return DebugAction::Resume;
+        }
+        for reason in reasons {
+            let _ = writeln!(self.output, "[{}] paused: {}", time, reason);
+        }
+        loop {
+            let _ = write!(self.output, "(rhdl) ");
+            let _ = self.output.flush();
+            let mut line = String::new();
+            if self.input.read_line(&mut line).unwrap_or(0) == 0 {
+                // No more input, so let the simulation run to completion
+                self.detached = true;
+                return DebugAction::Resume;
+            }
+            let args = line.split_whitespace().collect::<Vec<_>>();
+            if args.is_empty() {
+                continue;
+            }
+            match self.command(time, x, &args) {
+                Ok(Some(action)) => return action,
+                Ok(None) => {}
+                Err(msg) => {
+                    let _ = writeln!(self.output, "error: {}", msg);
+                }
+            }
+        }
+    }
+    fn command(&mut self, time: u64, x: &T, args: &[&str]) -> Result<Option<DebugAction>, String> {
+        let number = |ndx: usize, default: Option<u64>| -> Result<u64, String> {
+            match args.get(ndx) {
+                Some(arg) => arg
+                    .parse::<u64>()
+                    .map_err(|_| format!("expected a number, got {}", arg)),
+                None => default.ok_or_else(|| format!("{} needs an argument", args[0])),
+            }
+        };
+        let path = |ndx: usize| -> Result<String, String> {
+            let path = args
+                .get(ndx)
+                .ok_or_else(|| format!("{} needs a signal path", args[0]))?;
+            read_signal(x, path).map_err(|_| format!("no signal named {}", path))?;
+            Ok(path.to_string())
+        };
+        match args[0] {
+            "c" | "continue" => {
+                self.mode = RunMode::Continue;
+                return Ok(Some(DebugAction::Resume));
+            }
+            "s" | "step" => {
+                self.mode = RunMode::Steps(number(1, Some(1))?.max(1));
+                return Ok(Some(DebugAction::Resume));
+            }
+            "n" | "next" => {
+                let path = path(1)?;
+                let last = read_signal(x, &path) == Ok(VCDValue::from(true));
+                self.mode = RunMode::Edges {
+                    path,
+                    count: number(2, Some(1))?.max(1),
+                    last,
+                };
+                return Ok(Some(DebugAction::Resume));
+            }
+            "r" | "run" => {
+                self.mode = RunMode::Until(time + number(1, None)?);
+                return Ok(Some(DebugAction::Resume));
+            }
+            "p" | "print" => {
+                let path = path(1)?;
+                let value = read_signal(x, &path).unwrap();
+                let _ = writeln!(self.output, "{} = {}", path, format_vcd_value(&value));
+            }
+            "ls" | "list" => {
+                let prefix = args.get(1).copied().unwrap_or("");
+                for path in signal_paths(x) {
+                    if path.starts_with(prefix) {
+                        let _ = writeln!(self.output, "{}", path);
+                    }
+                }
+            }
+            "b" | "break" => {
+                self.breakpoints.push(Breakpoint::Time(number(1, None)?));
+            }
+            "w" | "watch" => {
+                let path = path(1)?;
+                let value = read_signal(x, &path).ok();
+                self.watchpoints.push(Watchpoint { path, value });
+            }
+            "unwatch" => {
+                let len = self.watchpoints.len();
+                self.watchpoints
+                    .retain(|w| Some(&w.path.as_str()) != args.get(1));
+                if self.watchpoints.len() == len {
+                    return Err("no such watchpoint".into());
+                }
+            }
+            "info" => {
+                for breakpoint in &self.breakpoints {
+                    let _ = match breakpoint {
+                        Breakpoint::Time(t) => writeln!(self.output, "break at time {}", t),
+                        Breakpoint::When { name, .. } => {
+                            writeln!(self.output, "break when {}", name)
+                        }
+                    };
+                }
+                for watch in &self.watchpoints {
+                    let _ = writeln!(self.output, "watch {}", watch.path);
+                }
+            }
+            "dump" => {
+                let file = args.get(1).ok_or("dump needs a file name")?;
+                std::fs::write(file, self.trace()).map_err(|e| e.to_string())?;
+                let _ = writeln!(self.output, "wrote trace up to time {} to {}", time, file);
+            }
+            "t" | "time" => {
+                let _ = writeln!(self.output, "time = {}", time);
+            }
+            "q" | "quit" => return Ok(Some(DebugAction::Quit)),
+            "h" | "help" => {
+                let _ = write!(self.output, "{}", HELP);
+            }
+            x => return Err(format!("unknown command {} (try help)", x)),
+        }
+        Ok(None)
+    }
+}
@@ -3,10 +3,35 @@ use crossbeam::channel::{RecvError, SendError};
 
 use crate::core::block::Block;
 use crate::core::check_error::{check_all, CheckError};
+use crate::core::sim_debugger::{DebugAction, Debugger};
 use crate::core::vcd_probe::{write_vcd_change, write_vcd_dump, write_vcd_header};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::thread::JoinHandle;
 
+/// The environment variable used to override the random seed of a [Simulation].
+/// When a randomised simulation fails, the seed is printed so that the failure
+/// can be reproduced by running the test again with this variable set to the seed.
+pub const SEED_ENV_VAR: &str = "RUST_HDL_SEED";
+
+/// Returns the seed given by the [SEED_ENV_VAR] environment variable, if it is set.
+pub fn seed_from_env() -> Option<u64> {
+    std::env::var(SEED_ENV_VAR).ok().map(|x| {
+        x.trim()
+            .parse()
+            .expect("RUST_HDL_SEED must be an unsigned integer")
+    })
+}
+
+// Derive the seed for one of the random streams of a simulation, so that each
+// testbench gets its own (reproducible) sequence of random numbers.
+fn stream_seed(seed: u64, stream: u64) -> u64 {
+    seed ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
 /// Update changes to a circuit until it stabilizes
 ///
 /// # Arguments
@@ -32,15 +57,55 @@ pub enum SimError {
     /// The simulation terminated prematurely (i.e., something went wrong)
     SimTerminated,
     /// The simulation reached the maximum allowed time for the simulation
-    MaxTimeReached,
+    MaxTimeReached {
+        /// The simulation time when the run stopped
+        time: u64,
+    },
     /// The simulation halted - usually this means an assertion failed
-    SimHalted,
+    SimHalted {
+        /// The simulation time at which the testbench halted
+        time: u64,
+        /// The name of the testbench that halted the simulation
+        testbench: String,
+        /// The reason given for the halt (e.g., the failing assertion)
+        message: String,
+    },
     /// The circuit failed to converge.  This means the logic has some issue (like an oscillation).
-    FailedToConverge,
+    FailedToConverge {
+        /// The simulation time at which the circuit failed to converge
+        time: u64,
+    },
     /// Something went wrong with the circuit check (either a missing connection or other issue, like a latching write).
     Check(CheckError),
     /// The simulation panicked.  This usually means `.unwrap` was called on a result in the testbench.
-    SimPanic,
+    SimPanic {
+        /// The simulation time at which the testbench panicked
+        time: u64,
+        /// The name of the testbench that panicked
+        testbench: String,
+        /// The panic message
+        message: String,
+    },
+    /// A testbench waited for a condition that did not occur within its timeout
+    Timeout {
+        /// The simulation time at which the wait timed out
+        time: u64,
+        /// The name of the testbench that was waiting
+        testbench: String,
+        /// The timeout (in picoseconds) that expired
+        timeout: u64,
+    },
+}
+
+/// Extract the message from a panic payload (as returned by [std::panic::catch_unwind])
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
+    }
 }
 
 impl From<CheckError> for SimError {
@@ -68,8 +133,9 @@ enum TriggerType<T> {
     Never,
     Time(u64),
     Function(Box<dyn Fn(&T) -> bool + Send>),
+    FunctionOrTime(Box<dyn Fn(&T) -> bool + Send>, u64),
     Clock(u64),
-    Halt,
+    Halt(SimError),
 }
 
 struct Message<T> {
@@ -79,11 +145,12 @@ struct Message<T> {
 
 enum MessageOrPanic<T> {
     Message(Message<T>),
-    Panic,
+    Panic(String),
 }
 
 struct Worker<T> {
     id: usize,
+    name: String,
     channel_to_worker: Sender<Message<T>>,
     kind: TriggerType<T>,
 }
@@ -102,6 +169,7 @@ pub struct Simulation<T> {
     time: u64,
     testbenches: Vec<JoinHandle<Result<()>>>,
     custom_logic: Vec<CustomLogicFn<T>>,
+    seed: Arc<AtomicU64>,
 }
 
 /// The `Sim` struct is used to communicate with a simulation.  Every testbench
@@ -109,6 +177,10 @@ pub struct Simulation<T> {
 /// with the core simulation.
 pub struct Sim<T> {
     time: u64,
+    id: usize,
+    name: String,
+    seed: Arc<AtomicU64>,
+    rng: Option<StdRng>,
     to_sim: Sender<MessageOrPanic<T>>,
     from_sim: Receiver<Message<T>>,
 }
@@ -128,8 +200,12 @@ impl<T: Send + 'static + Block> Default for Simulation<T> {
 
 impl<T: Send + 'static + Block> Simulation<T> {
     /// Construct a simulation struct
+    ///
+    /// The simulation is given a random seed, unless one is provided by the
+    /// [SEED_ENV_VAR] environment variable.
     pub fn new() -> Simulation<T> {
         let (send, recv) = bounded(0);
+        let seed = seed_from_env().unwrap_or_else(|| rand::thread_rng().gen());
         Self {
             workers: vec![],
             recv,
@@ -137,8 +213,25 @@ impl<T: Send + 'static + Block> Simulation<T> {
             time: 0,
             testbenches: vec![],
             custom_logic: vec![],
+            seed: Arc::new(AtomicU64::new(seed)),
         }
     }
+    /// Set the seed used for the random numbers provided by [Sim::rng] and
+    /// [Simulation::rng].  This must be called before the simulation is run.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed.store(seed, Ordering::SeqCst);
+    }
+    /// The seed used for the random numbers in this simulation
+    pub fn seed(&self) -> u64 {
+        self.seed.load(Ordering::SeqCst)
+    }
+    /// Returns a random number generator seeded from the simulation seed.  Use
+    /// this to generate random test data (e.g., when building the circuit), so that
+    /// the data is reproducible from the seed.  Each call returns a generator that
+    /// produces the same sequence.
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(stream_seed(self.seed(), !0))
+    }
     /// Add a clock function to the simulation
     ///
     /// # Arguments
@@ -170,7 +263,8 @@ impl<T: Send + 'static + Block> Simulation<T> {
     where
         F: Fn(&mut Box<T>) -> () + Send + 'static + std::panic::RefUnwindSafe,
     {
-        self.add_testbench(move |mut ep: Sim<T>| {
+        let name = format!("clock_{}", self.workers.len());
+        self.add_named_testbench(&name, move |mut ep: Sim<T>| {
             let mut x = ep.init()?;
             loop {
                 x = ep.clock(interval, x)?;
@@ -215,7 +309,8 @@ impl<T: Send + 'static + Block> Simulation<T> {
     where
         F: Fn(&mut Box<T>) -> () + Send + 'static + std::panic::RefUnwindSafe,
     {
-        self.add_testbench(move |mut ep: Sim<T>| {
+        let name = format!("clock_{}", self.workers.len());
+        self.add_named_testbench(&name, move |mut ep: Sim<T>| {
             let mut x = ep.init()?;
             x = ep.wait(phase_delay, x)?;
             loop {
@@ -238,15 +333,34 @@ impl<T: Send + 'static + Block> Simulation<T> {
     where
         F: Fn(Sim<T>) -> Result<()> + Send + 'static + std::panic::RefUnwindSafe,
     {
-        let ep = self.endpoint();
+        let name = format!("testbench_{}", self.workers.len());
+        self.add_named_testbench(&name, testbench);
+    }
+    /// Add a testbench to the simulation with the given name
+    ///
+    /// The name is used to identify the testbench in the [SimError] returned
+    /// when the testbench halts, panics or times out.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the testbench
+    /// * `testbench` - a testbench function (see [Simulation::add_testbench]).
+    pub fn add_named_testbench<F>(&mut self, name: &str, testbench: F)
+    where
+        F: Fn(Sim<T>) -> Result<()> + Send + 'static + std::panic::RefUnwindSafe,
+    {
+        let ep = self.endpoint_named(name);
         self.testbenches.push(std::thread::spawn(move || {
             let ep_panic = ep.to_sim.clone();
             let result = std::panic::catch_unwind(|| testbench(ep));
             match result {
                 Ok(x) => x,
-                Err(_e) => {
-                    ep_panic.send(MessageOrPanic::Panic).unwrap();
-                    Err(SimError::SimPanic)
+                Err(e) => {
+                    // The simulation reports the panic (with the time and the name of
+                    // the testbench) when it receives the message
+                    let message = panic_message(e.as_ref());
+                    ep_panic.send(MessageOrPanic::Panic(message)).unwrap();
+                    Ok(())
                 }
             }
         }));
@@ -258,10 +372,15 @@ impl<T: Send + 'static + Block> Simulation<T> {
         self.custom_logic.push(Box::new(logic));
     }
     pub fn endpoint(&mut self) -> Sim<T> {
+        let name = format!("endpoint_{}", self.workers.len());
+        self.endpoint_named(&name)
+    }
+    fn endpoint_named(&mut self, name: &str) -> Sim<T> {
         let (send_to_worker, recv_from_sim_to_worker) = bounded(0);
         let id = self.workers.len();
         let worker = Worker {
             id,
+            name: name.into(),
             channel_to_worker: send_to_worker,
             kind: TriggerType::Never,
         };
@@ -270,6 +389,10 @@ impl<T: Send + 'static + Block> Simulation<T> {
             to_sim: self.channel_to_sim.clone(),
             from_sim: recv_from_sim_to_worker,
             time: 0,
+            id,
+            name: name.into(),
+            seed: self.seed.clone(),
+            rng: None,
         }
     }
     fn dispatch(&mut self, idx: usize, x: Box<T>) -> Result<Box<T>> {
@@ -281,8 +404,12 @@ impl<T: Send + 'static + Block> Simulation<T> {
         let x = self.recv.recv()?;
         let mut x = match x {
             MessageOrPanic::Message(x) => x,
-            MessageOrPanic::Panic => {
-                return Err(SimError::SimPanic);
+            MessageOrPanic::Panic(message) => {
+                return Err(SimError::SimPanic {
+                    time: self.time,
+                    testbench: worker.name.clone(),
+                    message,
+                });
             }
         };
         worker.kind = x.kind;
@@ -299,7 +426,7 @@ impl<T: Send + 'static + Block> Simulation<T> {
             }
         }
         if !converged {
-            Err(SimError::FailedToConverge)
+            Err(SimError::FailedToConverge { time: self.time })
         } else {
             Ok(x.circuit)
         }
@@ -310,11 +437,11 @@ impl<T: Send + 'static + Block> Simulation<T> {
         let mut only_clock_waiters = true;
         for worker in self.workers.iter() {
             match &worker.kind {
-                TriggerType::Halt => {
+                TriggerType::Halt(_) => {
                     return NextTime {
                         halted: true,
                         time: !0,
-                        idx: !0,
+                        idx: worker.id,
                         clocks_only: false,
                     }
                 }
@@ -334,6 +461,18 @@ impl<T: Send + 'static + Block> Simulation<T> {
                         break;
                     }
                 }
+                TriggerType::FunctionOrTime(watch, t) => {
+                    only_clock_waiters = false;
+                    if watch(&x) {
+                        min_idx = worker.id;
+                        min_time = self.time;
+                        break;
+                    }
+                    if *t < min_time {
+                        min_time = *t;
+                        min_idx = worker.id;
+                    }
+                }
                 TriggerType::Clock(t) => {
                     if *t < min_time {
                         min_time = *t;
@@ -349,13 +488,35 @@ impl<T: Send + 'static + Block> Simulation<T> {
             halted: false,
         }
     }
+    fn halt_error(&mut self, idx: usize) -> SimError {
+        match std::mem::replace(&mut self.workers[idx].kind, TriggerType::Never) {
+            TriggerType::Halt(err) => err,
+            _ => SimError::SimTerminated,
+        }
+    }
     fn terminate(&mut self) {
         self.workers.clear();
         for handle in std::mem::take(&mut self.testbenches) {
             let _ = handle.join().unwrap();
         }
     }
-    pub fn run(&mut self, mut x: Box<T>, max_time: u64) -> Result<()> {
+    fn report_seed(&self, result: Result<()>) -> Result<()> {
+        if let Err(err) = &result {
+            eprintln!(
+                "Simulation failed ({:?}) with seed {}.  Set {}={} to reproduce.",
+                err,
+                self.seed(),
+                SEED_ENV_VAR,
+                self.seed()
+            );
+        }
+        result
+    }
+    pub fn run(&mut self, x: Box<T>, max_time: u64) -> Result<()> {
+        let result = self.run_untraced(x, max_time);
+        self.report_seed(result)
+    }
+    fn run_untraced(&mut self, mut x: Box<T>, max_time: u64) -> Result<()> {
         x.as_mut().connect_all();
         check_all(x.as_mut())?;
         // First initialize the workers.
@@ -363,11 +524,13 @@ impl<T: Send + 'static + Block> Simulation<T> {
             x = self.dispatch(id, x)?;
         }
         // Next run until we have no one else waiting
-        let mut halted = false;
+        let mut halted = None;
         while self.time < max_time {
             let next = self.scan_workers(&x);
             if next.time == !0 || next.clocks_only || next.halted {
-                halted = next.halted;
+                if next.halted {
+                    halted = Some(self.halt_error(next.idx));
+                }
                 break;
             }
             self.time = next.time;
@@ -375,10 +538,10 @@ impl<T: Send + 'static + Block> Simulation<T> {
         }
         self.terminate();
         if self.time >= max_time {
-            return Err(SimError::MaxTimeReached);
+            return Err(SimError::MaxTimeReached { time: self.time });
         }
-        if halted {
-            return Err(SimError::SimHalted);
+        if let Some(err) = halted {
+            return Err(err);
         }
         Ok(())
     }
@@ -388,7 +551,32 @@ impl<T: Send + 'static + Block> Simulation<T> {
         std::fs::write(name, vcd).unwrap();
         result
     }
-    pub fn run_traced<W: Write>(&mut self, mut x: Box<T>, max_time: u64, trace: W) -> Result<()> {
+    pub fn run_traced<W: Write>(&mut self, x: Box<T>, max_time: u64, trace: W) -> Result<()> {
+        let result = self.run_with_trace(x, max_time, trace, None);
+        self.report_seed(result)
+    }
+    /// Run the simulation under the control of an interactive [Debugger], which
+    /// can pause the simulation at breakpoints to inspect the circuit.  The trace
+    /// of the simulation is recorded, so that it can be dumped from the debugger.
+    pub fn run_debug(
+        &mut self,
+        x: Box<T>,
+        max_time: u64,
+        debugger: &mut Debugger<T>,
+    ) -> Result<()> {
+        let trace = debugger.trace_buffer();
+        let result = self.run_with_trace(x, max_time, trace, Some(debugger));
+        self.report_seed(result)
+    }
+    // The debugger (if any) is told about every step of the simulation, and may
+    // stop it early
+    fn run_with_trace<W: Write>(
+        &mut self,
+        mut x: Box<T>,
+        max_time: u64,
+        trace: W,
+        mut debugger: Option<&mut Debugger<T>>,
+    ) -> Result<()> {
         x.as_mut().connect_all();
         check_all(x.as_mut())?;
         let mut vcd = write_vcd_header(trace, x.as_ref());
@@ -397,25 +585,45 @@ impl<T: Send + 'static + Block> Simulation<T> {
             x = self.dispatch(id, x)?;
         }
         vcd = write_vcd_dump(vcd, x.as_ref());
-        let mut halted = false;
+        let mut halted = None;
+        let mut action = match debugger.as_mut() {
+            Some(debugger) => debugger.start(self.time, x.as_ref()),
+            None => DebugAction::Resume,
+        };
         // Next run until we have no one else waiting
-        while self.time < max_time {
+        while action == DebugAction::Resume && self.time < max_time {
             let next = self.scan_workers(x.as_ref());
             if next.time == !0 || next.clocks_only || next.halted {
-                halted = next.halted;
+                if next.halted {
+                    let err = self.halt_error(next.idx);
+                    if let Some(debugger) = debugger.as_mut() {
+                        debugger.halted(self.time, x.as_ref(), &err);
+                    }
+                    halted = Some(err);
+                }
                 break;
             }
             self.time = next.time;
             x = self.dispatch(next.idx, x)?;
             vcd.timestamp(next.time).unwrap();
             vcd = write_vcd_change(vcd, x.as_ref());
+            if let Some(debugger) = debugger.as_mut() {
+                action = debugger.step(self.time, x.as_ref());
+            }
         }
         self.terminate();
+        if action == DebugAction::Quit {
+            return Err(SimError::SimHalted {
+                time: self.time,
+                testbench: "debugger".into(),
+                message: "quit from debugger".into(),
+            });
+        }
         if self.time >= max_time {
-            return Err(SimError::MaxTimeReached);
+            return Err(SimError::MaxTimeReached { time: self.time });
         }
-        if halted {
-            return Err(SimError::SimHalted);
+        if let Some(err) = halted {
+            return Err(err);
         }
         Ok(())
     }
@@ -447,6 +655,38 @@ impl<T> Sim<T> {
         }
         Ok(t.circuit)
     }
+    /// Wait for the `check` condition to become true, but for at most `timeout`
+    /// picoseconds.  If the condition does not occur in time, the simulation is
+    /// halted and a [SimError::Timeout] is returned.
+    pub fn watch_with_timeout<S>(&mut self, check: S, timeout: u64, x: Box<T>) -> Result<Box<T>>
+    where
+        S: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        let check = std::sync::Arc::new(check);
+        let watch = check.clone();
+        let deadline = self.time + timeout;
+        self.to_sim.send(MessageOrPanic::Message(Message {
+            kind: TriggerType::FunctionOrTime(Box::new(move |x| watch(x)), deadline),
+            circuit: x,
+        }))?;
+        let t = self.from_sim.recv()?;
+        if let TriggerType::Time(t0) = t.kind {
+            self.time = t0;
+        }
+        if check(&t.circuit) {
+            return Ok(t.circuit);
+        }
+        let err = SimError::Timeout {
+            time: self.time,
+            testbench: self.name.clone(),
+            timeout,
+        };
+        self.to_sim.send(MessageOrPanic::Message(Message {
+            kind: TriggerType::Halt(err.clone()),
+            circuit: t.circuit,
+        }))?;
+        Err(err)
+    }
     pub fn clock(&mut self, delta: u64, x: Box<T>) -> Result<Box<T>> {
         self.to_sim.send(MessageOrPanic::Message(Message {
             kind: TriggerType::Clock(delta + self.time),
@@ -477,15 +717,74 @@ impl<T> Sim<T> {
         Ok(())
     }
     pub fn halt(&self, x: Box<T>) -> Result<()> {
+        self.halt_with_message("", x)
+    }
+    /// Halt the simulation, recording the reason (e.g., the failed assertion)
+    /// in the [SimError::SimHalted] returned by the simulation.
+    pub fn halt_with_message(&self, message: &str, x: Box<T>) -> Result<()> {
+        let err = SimError::SimHalted {
+            time: self.time,
+            testbench: self.name.clone(),
+            message: message.into(),
+        };
         self.to_sim.send(MessageOrPanic::Message(Message {
-            kind: TriggerType::Halt,
+            kind: TriggerType::Halt(err.clone()),
             circuit: x,
         }))?;
-        Err(SimError::SimHalted)
+        Err(err)
     }
     pub fn time(&self) -> u64 {
         self.time
     }
+    /// The name of the testbench that owns this endpoint
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// A random number generator for this testbench, seeded from the seed of the
+    /// [Simulation].  Each testbench receives its own sequence of random numbers,
+    /// which is the same every time the simulation is run with the same seed.
+    pub fn rng(&mut self) -> &mut StdRng {
+        let seed = stream_seed(self.seed.load(Ordering::SeqCst), self.id as u64);
+        self.rng.get_or_insert_with(|| StdRng::seed_from_u64(seed))
+    }
+}
+
+/// Run a randomised test once for each of `count` seeds.  The `test` closure is
+/// called with the seed, and is expected to pass it to [Simulation::set_seed].  The
+/// seeds are `0..count`, unless the [SEED_ENV_VAR] environment variable is set, in
+/// which case the test is run only for that seed.  The first failure is returned,
+/// and its seed is printed.
+///
+/// ```rust
+/// # use rust_hdl::prelude::*;
+/// use rand::Rng;
+/// let result = run_with_seeds(4, |seed| {
+///     let mut sim = Simulation::new();
+///     sim.set_seed(seed);
+///     sim.add_testbench(|mut sim: Sim<Strobe<32>>| {
+///         let x = sim.init()?;
+///         let _delay = sim.rng().gen_range(0..10);
+///         sim.done(x)
+///     });
+///     sim.run(Box::new(Strobe::new(1_000_000, 1000.0)), 1_000)
+/// });
+/// assert!(result.is_ok());
+/// ```
+pub fn run_with_seeds<F>(count: u64, mut test: F) -> Result<()>
+where
+    F: FnMut(u64) -> Result<()>,
+{
+    let seeds = match seed_from_env() {
+        Some(seed) => seed..seed + 1,
+        None => 0..count,
+    };
+    for seed in seeds {
+        if let Err(err) = test(seed) {
+            eprintln!("Test failed with seed {}", seed);
+            return Err(err);
+        }
+    }
+    Ok(())
 }
 
 #[macro_export]
@@ -534,7 +833,7 @@ macro_rules! sim_assert {
     ($sim: ident, $test: expr, $circuit: ident) => {
         if !($test) {
             println!("HALT {}", stringify!($test));
-            return $sim.halt($circuit);
+            return $sim.halt_with_message(stringify!($test), $circuit);
         }
     };
 }
@@ -543,14 +842,15 @@ macro_rules! sim_assert {
 macro_rules! sim_assert_eq {
     ($sim: ident, $lhs: expr, $rhs: expr, $circuit: ident) => {
         if !($lhs == $rhs) {
-            println!(
-                "HALT {} != {},  {:?} != {:?}",
+            let msg = format!(
+                "{} != {},  {:?} != {:?}",
                 stringify!($lhs),
                 stringify!($rhs),
                 $lhs,
                 $rhs
             );
-            return $sim.halt($circuit);
+            println!("HALT {}", msg);
+            return $sim.halt_with_message(&msg, $circuit);
         }
     };
 }
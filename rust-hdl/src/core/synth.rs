@@ -3,7 +3,9 @@ use std::fmt::Debug;
 use crate::core::ast::VerilogLiteral;
 use crate::core::bits::{Bit, Bits};
 use crate::core::clock::Clock;
-use crate::core::signed::Signed;
+use crate::core::fixed::{Fixed, UFixed};
+use crate::core::four_state::UnknownMask;
+use crate::core::signed::{signed_cast, Signed};
 use crate::core::type_descriptor::{TypeDescriptor, TypeKind};
 
 #[derive(Clone, PartialEq, Debug)]
@@ -12,6 +14,7 @@ pub enum VCDValue {
     Vector(Vec<vcd::Value>),
     String(String),
     Composite(Vec<Box<VCDValue>>),
+    Real(f64),
 }
 
 impl From<bool> for VCDValue {
@@ -24,11 +27,79 @@ impl From<bool> for VCDValue {
     }
 }
 
+/// Build a [VCDValue] for the given type in which every bit has the value `fill`.
+/// Used to represent unknown (X) and undriven (Z) values.
+pub fn vcd_fill(descriptor: &TypeDescriptor, fill: vcd::Value) -> VCDValue {
+    match &descriptor.kind {
+        TypeKind::Bits(1) | TypeKind::Signed(1) => VCDValue::Single(fill),
+        TypeKind::Bits(width) | TypeKind::Signed(width) => VCDValue::Vector(vec![fill; *width]),
+        TypeKind::Enum(_) => VCDValue::String(fill.to_string()),
+        // Real valued traces have no unknown or undriven values
+        TypeKind::Fixed { .. } => VCDValue::Real(f64::NAN),
+        TypeKind::Composite(fields) => VCDValue::Composite(
+            fields
+                .iter()
+                .map(|field| Box::new(vcd_fill(&field.kind, fill)))
+                .collect(),
+        ),
+    }
+}
+
+/// Mark the unknown bits of a [VCDValue] of the given type as `x`.  Bit vectors are
+/// marked bit by bit, other values are unknown as a whole if any of their bits are.
+pub fn vcd_mark_unknown(
+    value: VCDValue,
+    descriptor: &TypeDescriptor,
+    unknown: &UnknownMask,
+) -> VCDValue {
+    match value {
+        VCDValue::Single(_) if unknown.get_bit(0) => VCDValue::Single(vcd::Value::X),
+        VCDValue::Single(x) => VCDValue::Single(x),
+        VCDValue::Vector(mut x) => {
+            // Vectors are stored most significant bit first
+            let width = x.len();
+            for (ndx, bit) in x.iter_mut().enumerate() {
+                if unknown.get_bit(width - 1 - ndx) {
+                    *bit = vcd::Value::X;
+                }
+            }
+            VCDValue::Vector(x)
+        }
+        _ => vcd_fill(descriptor, vcd::Value::X),
+    }
+}
+
+/// Build a [VCDValue] for the given type in which every bit is unknown (X).  Used for
+/// the fields of the inactive variants of an enum with fields.
+pub fn vcd_unknown(descriptor: &TypeDescriptor) -> VCDValue {
+    vcd_fill(descriptor, vcd::Value::X)
+}
+
+/// Extract the bits (least significant first) from a [VCDValue].  Returns `None` if
+/// the value is not a vector of known bits.
+pub fn vcd_bits(value: &VCDValue) -> Option<Vec<bool>> {
+    let bit = |x: &vcd::Value| match x {
+        vcd::Value::V0 => Some(false),
+        vcd::Value::V1 => Some(true),
+        _ => None,
+    };
+    match value {
+        VCDValue::Single(x) => Some(vec![bit(x)?]),
+        VCDValue::Vector(x) => x.iter().rev().map(bit).collect(),
+        _ => None,
+    }
+}
+
 pub trait Synth: Default + Copy + PartialEq + Debug {
     const BITS: usize;
     fn descriptor() -> TypeDescriptor;
     fn vcd(self) -> VCDValue;
     fn verilog(self) -> VerilogLiteral;
+    /// Construct a value from the representation produced by [Synth::vcd].  Returns
+    /// `None` if the value is not valid for this type (or the type does not support it).
+    fn from_vcd(_value: &VCDValue) -> Option<Self> {
+        None
+    }
 }
 
 impl<const N: usize> Synth for Bits<N> {
@@ -48,6 +119,20 @@ impl<const N: usize> Synth for Bits<N> {
     fn verilog(self) -> VerilogLiteral {
         self.into()
     }
+
+    fn from_vcd(value: &VCDValue) -> Option<Self> {
+        let bits = vcd_bits(value)?;
+        if bits.len() > N {
+            return None;
+        }
+        Some(
+            bits.iter()
+                .enumerate()
+                .fold(Bits::default(), |acc, (ndx, bit)| {
+                    acc.replace_bit(ndx, *bit)
+                }),
+        )
+    }
 }
 
 impl Synth for Bit {
@@ -71,6 +156,13 @@ impl Synth for Bit {
     fn verilog(self) -> VerilogLiteral {
         self.into()
     }
+
+    fn from_vcd(value: &VCDValue) -> Option<Self> {
+        match vcd_bits(value)?[..] {
+            [x] => Some(x),
+            _ => None,
+        }
+    }
 }
 
 impl Synth for Clock {
@@ -90,6 +182,12 @@ impl Synth for Clock {
     fn verilog(self) -> VerilogLiteral {
         self.clk.into()
     }
+
+    fn from_vcd(value: &VCDValue) -> Option<Self> {
+        Some(Clock {
+            clk: bool::from_vcd(value)?,
+        })
+    }
 }
 
 impl<const N: usize> Synth for Signed<N> {
@@ -106,4 +204,59 @@ impl<const N: usize> Synth for Signed<N> {
     fn verilog(self) -> VerilogLiteral {
         self.inner().into()
     }
+    fn from_vcd(value: &VCDValue) -> Option<Self> {
+        Some(signed_cast(Bits::from_vcd(value)?))
+    }
+}
+
+impl<const I: usize, const F: usize> Synth for Fixed<I, F> {
+    const BITS: usize = I + F;
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor {
+            name: format!("Fixed::<{}, {}>", I, F),
+            kind: TypeKind::Fixed {
+                width: Self::BITS,
+                fraction: F,
+                signed: true,
+            },
+        }
+    }
+    fn vcd(self) -> VCDValue {
+        VCDValue::Real(self.to_f64())
+    }
+    fn verilog(self) -> VerilogLiteral {
+        self.into()
+    }
+    fn from_vcd(value: &VCDValue) -> Option<Self> {
+        match value {
+            VCDValue::Real(x) if x.is_finite() => Some(Self::from_f64(*x)),
+            _ => None,
+        }
+    }
+}
+
+impl<const I: usize, const F: usize> Synth for UFixed<I, F> {
+    const BITS: usize = I + F;
+    fn descriptor() -> TypeDescriptor {
+        TypeDescriptor {
+            name: format!("UFixed::<{}, {}>", I, F),
+            kind: TypeKind::Fixed {
+                width: Self::BITS,
+                fraction: F,
+                signed: false,
+            },
+        }
+    }
+    fn vcd(self) -> VCDValue {
+        VCDValue::Real(self.to_f64())
+    }
+    fn verilog(self) -> VerilogLiteral {
+        self.into()
+    }
+    fn from_vcd(value: &VCDValue) -> Option<Self> {
+        match value {
+            VCDValue::Real(x) if x.is_finite() => Some(Self::from_f64(*x)),
+            _ => None,
+        }
+    }
 }
@@ -10,10 +10,31 @@ pub struct TypeField {
     pub kind: TypeDescriptor,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    pub value: usize,
+}
+
 #[derive(Clone, Debug)]
 pub enum TypeKind {
     Bits(usize),
     Signed(usize),
-    Enum(Vec<String>),
+    Enum(Vec<EnumVariant>),
     Composite(Vec<Box<TypeField>>),
+    Fixed {
+        width: usize,
+        fraction: usize,
+        signed: bool,
+    },
+}
+
+impl TypeKind {
+    /// True for types that are declared `signed` in Verilog
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            TypeKind::Signed(_) | TypeKind::Fixed { signed: true, .. }
+        )
+    }
 }
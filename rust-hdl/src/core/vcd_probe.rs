@@ -45,6 +45,9 @@ fn register_signal<W: Write>(
             VCDIDCode::Singleton(vcd.add_wire(*width as u32, name).unwrap())
         }
         TypeKind::Enum(_) => VCDIDCode::Singleton(vcd.add_wire(0, name).unwrap()),
+        TypeKind::Fixed { .. } => {
+            VCDIDCode::Singleton(vcd.add_var(vcd::VarType::Real, 64, name, None).unwrap())
+        }
         TypeKind::Composite(k) => {
             let mut ret = vec![];
             for field in k {
@@ -159,6 +162,9 @@ fn do_vcd_change<W: Write>(
                 VCDValue::String(t) => {
                     vcd.change_string(*idc, &t).unwrap();
                 }
+                VCDValue::Real(x) => {
+                    vcd.change_real(*idc, *x).unwrap();
+                }
                 VCDValue::Composite(_) => {
                     panic!("Composite data received for singleton type");
                 }
@@ -3,12 +3,13 @@ use num_bigint::BigUint;
 use regex::Regex;
 
 use crate::core::ast::{
-    VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional, VerilogExpression,
-    VerilogLink, VerilogLinkDetails, VerilogLiteral, VerilogLoop, VerilogMatch, VerilogOp,
-    VerilogOpUnary,
+    SourceLocation, VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional,
+    VerilogDisplay, VerilogExpression, VerilogFunction, VerilogFunctionCall,
+    VerilogFunctionVariable, VerilogLink, VerilogLinkDetails, VerilogLiteral, VerilogLoop,
+    VerilogMatch, VerilogOp, VerilogOpUnary, VerilogSystemTask,
 };
 use crate::core::code_writer::CodeWriter;
-use crate::core::verilog_visitor::{walk_block, VerilogVisitor};
+use crate::core::verilog_visitor::{walk_block, walk_function_call, VerilogVisitor};
 
 struct LoopVariable {
     variable: String,
@@ -19,6 +20,7 @@ pub struct VerilogCodeGenerator {
     io: CodeWriter,
     loops: Vec<LoopVariable>,
     links: Vec<VerilogLink>,
+    source_locations: bool,
 }
 
 impl VerilogCodeGenerator {
@@ -27,6 +29,7 @@ impl VerilogCodeGenerator {
             io: CodeWriter::new(),
             loops: vec![],
             links: vec![],
+            source_locations: false,
         }
     }
 
@@ -83,6 +86,20 @@ impl ToString for VerilogCodeGenerator {
     }
 }
 
+fn verilog_string_escape(x: &str) -> String {
+    let mut ret = String::new();
+    for c in x.chars() {
+        match c {
+            '\\' => ret += "\\\\",
+            '"' => ret += "\\\"",
+            '\n' => ret += "\\n",
+            '\t' => ret += "\\t",
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
+
 pub fn verilog_link_extraction(code: &VerilogBlock) -> Vec<VerilogLink> {
     let mut gen = VerilogCodeGenerator::new();
     gen.visit_block(code);
@@ -90,9 +107,104 @@ pub fn verilog_link_extraction(code: &VerilogBlock) -> Vec<VerilogLink> {
 }
 
 pub fn verilog_combinatorial(code: &VerilogBlock) -> String {
+    verilog_combinatorial_with_options(code, false)
+}
+
+/// Like [verilog_combinatorial], but with `// file:line` annotations of the Rust source
+/// if `source_locations` is set.
+pub fn verilog_combinatorial_with_options(code: &VerilogBlock, source_locations: bool) -> String {
     let mut gen = VerilogCodeGenerator::new();
+    gen.source_locations = source_locations;
     gen.visit_block(code);
-    format!("always @(*) {}\n", gen.to_string())
+    let functions = verilog_functions(code)
+        .iter()
+        .map(|x| verilog_function(x, source_locations))
+        .collect::<String>();
+    format!("{}always @(*) {}\n", functions, gen.to_string())
+}
+
+/// Find the Rust source of a line (numbered from 1) of the generated Verilog, by looking
+/// for the closest `// file:line` annotation above it.  Returns `None` if the line is
+/// not part of the update code of a module (e.g., a declaration, or custom Verilog).
+/// ```rust
+/// # use rust_hdl::core::prelude::*;
+/// let vlog = "always @(*) begin\n    // src/blinky.rs:12\n    if (x) begin\n        y = 1;\n";
+/// let loc = source_location(vlog, 4).unwrap();
+/// assert_eq!(loc.to_string(), "src/blinky.rs:12");
+/// assert!(source_location(vlog, 1).is_none());
+/// ```
+pub fn source_location(verilog: &str, line: usize) -> Option<SourceLocation> {
+    let annotation = Regex::new(r"^\s*// (\S+):(\d+)$").unwrap();
+    let lines = verilog.lines().collect::<Vec<_>>();
+    let last = line.checked_sub(1)?;
+    for text in lines.get(..=last)?.iter().rev() {
+        if let Some(x) = annotation.captures(text) {
+            return Some(SourceLocation {
+                file: x[1].to_string(),
+                line: x[2].parse().ok()?,
+            });
+        }
+        let text = text.trim_start();
+        if ["module", "endmodule", "always", "function", "endfunction"]
+            .iter()
+            .any(|x| text.starts_with(x))
+        {
+            return None;
+        }
+    }
+    None
+}
+
+// Collects the (distinct) functions called by a block of code, including those called
+// by the functions themselves.
+#[derive(Default)]
+struct FunctionCollector {
+    functions: Vec<VerilogFunction>,
+}
+
+impl VerilogVisitor for FunctionCollector {
+    fn visit_function_call(&mut self, c: &VerilogFunctionCall) {
+        if !self.functions.iter().any(|x| x.name == c.function.name) {
+            self.functions.push(c.function.clone());
+            self.visit_block(&c.function.block);
+        }
+        walk_function_call(self, c);
+    }
+}
+
+fn verilog_functions(code: &VerilogBlock) -> Vec<VerilogFunction> {
+    let mut collector = FunctionCollector::default();
+    collector.visit_block(code);
+    collector.functions
+}
+
+fn function_variable(x: &VerilogFunctionVariable) -> String {
+    let signed = if x.signed { "signed " } else { "" };
+    if x.width == 1 {
+        format!("{}{}", signed, x.name)
+    } else {
+        format!("{}[{}:0] {}", signed, x.width - 1, x.name)
+    }
+}
+
+fn verilog_function(f: &VerilogFunction, source_locations: bool) -> String {
+    let mut io = CodeWriter::new();
+    io.add(format!("function {};", function_variable(&f.result)));
+    io.push();
+    for arg in &f.args {
+        io.add(format!("input {};", function_variable(arg)));
+    }
+    for local in &f.locals {
+        io.add(format!("reg {};", function_variable(local)));
+    }
+    let mut gen = VerilogCodeGenerator::new();
+    gen.source_locations = source_locations;
+    gen.visit_block(&f.block);
+    io.add(gen.to_string());
+    io.pop();
+    io.add("endfunction");
+    io.next();
+    io.to_string()
 }
 
 impl VerilogVisitor for VerilogCodeGenerator {
@@ -168,6 +280,34 @@ impl VerilogVisitor for VerilogCodeGenerator {
         self.io.add(format!("// {}", x));
     }
 
+    fn visit_location(&mut self, l: &SourceLocation) {
+        if self.source_locations {
+            self.io.add(format!("// {}", l));
+        }
+    }
+
+    fn visit_display(&mut self, d: &VerilogDisplay) {
+        // Diagnostics are for simulators only - synthesis tools define SYNTHESIS
+        self.io.add("`ifndef SYNTHESIS");
+        if let Some(assertion) = &d.assertion {
+            self.io.write("if (!(");
+            self.visit_expression(assertion);
+            self.io.write(")) ");
+        }
+        self.io.write(match d.task {
+            VerilogSystemTask::Display => "$display(\"",
+            VerilogSystemTask::Error => "$error(\"",
+        });
+        self.io.write(verilog_string_escape(&d.format));
+        self.io.write("\"");
+        for arg in &d.args {
+            self.io.write(", ");
+            self.visit_expression(arg);
+        }
+        self.io.writeln(");");
+        self.io.add("`endif");
+    }
+
     fn visit_signal(&mut self, sig: &str) {
         self.io.write(self.ident_fixup(sig));
     }
@@ -208,6 +348,7 @@ impl VerilogVisitor for VerilogCodeGenerator {
             VerilogOp::BitOr => "|",
             VerilogOp::Shl => "<<",
             VerilogOp::Shr => ">>",
+            VerilogOp::AShr => ">>>",
             VerilogOp::Eq => "==",
             VerilogOp::Lt => "<",
             VerilogOp::Le => "<=",
@@ -264,6 +405,34 @@ impl VerilogVisitor for VerilogCodeGenerator {
         self.io.write(format!(")+:({})]", width));
     }
 
+    fn visit_function_call(&mut self, c: &VerilogFunctionCall) {
+        self.io.write(format!("{}(", c.function.name));
+        for (ndx, arg) in c.args.iter().enumerate() {
+            if ndx > 0 {
+                self.io.write(", ");
+            }
+            self.visit_expression(arg);
+        }
+        self.io.write(")");
+    }
+
+    fn visit_concat(&mut self, a: &[VerilogExpression]) {
+        self.io.write("{");
+        for (ndx, x) in a.iter().enumerate() {
+            if ndx > 0 {
+                self.io.write(", ");
+            }
+            self.visit_expression(x);
+        }
+        self.io.write("}");
+    }
+
+    fn visit_replicate(&mut self, count: &usize, e: &VerilogExpression) {
+        self.io.write(format!("{{{}{{", count));
+        self.visit_expression(e);
+        self.io.write("}}");
+    }
+
     fn visit_index_replace(
         &mut self,
         sig: &VerilogExpression,
@@ -0,0 +1,361 @@
+//! Golden snapshots of generated Verilog.
+//!
+//! Changes to the code generator or to widget internals can silently change the RTL
+//! emitted for a design that has already been qualified on hardware.  A snapshot test
+//! stores the (normalised) output of [generate_verilog] for a design in a file, and
+//! on later runs compares the fresh output against it.  A mismatch fails the test
+//! with a unified diff, and the new output is left next to the snapshot as
+//! `<name>.v.new`.
+//!
+//! To accept a change, rerun the tests with the environment variable
+//! `RUST_HDL_UPDATE_SNAPSHOTS=1`.  The snapshot files are then rewritten (or created,
+//! for a new snapshot), and the change to the RTL shows up in code review.
+//!
+//! ```rust,no_run
+//! # use rust_hdl::prelude::*;
+//! let mut uut = Strobe::<32>::new(1_000_000, 1000.0);
+//! uut.connect_all();
+//! // Compares against tests/snapshots/strobe.v in the crate directory
+//! verilog_snapshot!(uut, "strobe");
+//! ```
+use crate::core::block::Block;
+use crate::core::module_defines::generate_verilog;
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// The environment variable that, when set to anything other than `0` or an empty
+/// string, causes snapshot mismatches to be accepted and written out.
+pub const SNAPSHOT_UPDATE_VAR: &str = "RUST_HDL_UPDATE_SNAPSHOTS";
+
+/// The outcome of a successful snapshot check.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotStatus {
+    /// The generated Verilog matched the snapshot
+    Matched,
+    /// There was no snapshot, and one was written
+    Created,
+    /// The snapshot differed, and was replaced
+    Updated,
+}
+
+/// Reasons a snapshot check fails.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    /// There is no snapshot file at the given path
+    Missing(PathBuf),
+    /// The generated Verilog does not match the snapshot
+    Mismatch {
+        /// The snapshot file
+        path: PathBuf,
+        /// A unified diff from the snapshot to the generated Verilog
+        diff: String,
+    },
+    /// The snapshot could not be read or written
+    IO(String),
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(x: std::io::Error) -> Self {
+        SnapshotError::IO(x.to_string())
+    }
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Missing(path) => write!(
+                f,
+                "no Verilog snapshot at {} (rerun with {}=1 to create it)",
+                path.display(),
+                SNAPSHOT_UPDATE_VAR
+            ),
+            SnapshotError::Mismatch { path, diff } => write!(
+                f,
+                "generated Verilog differs from the snapshot {} (rerun with {}=1 to accept)\n{}",
+                path.display(),
+                SNAPSHOT_UPDATE_VAR,
+                diff
+            ),
+            SnapshotError::IO(e) => write!(f, "unable to access Verilog snapshot: {}", e),
+        }
+    }
+}
+
+/// Returns true if the [SNAPSHOT_UPDATE_VAR] environment variable requests that
+/// snapshots be updated.
+pub fn snapshot_updates_requested() -> bool {
+    match std::env::var(SNAPSHOT_UPDATE_VAR) {
+        Ok(x) => !x.is_empty() && x != "0",
+        Err(_) => false,
+    }
+}
+
+/// Normalise Verilog text so that snapshots do not depend on incidental whitespace.
+/// Line endings become `\n`, trailing whitespace is removed, runs of blank lines are
+/// collapsed into one, and leading and trailing blank lines are dropped.  Rust source
+/// location comments (`// file.rs:line`) are removed, since they change whenever the
+/// Rust source moves.
+pub fn normalize_verilog(verilog: &str) -> String {
+    let location = Regex::new(r"^\s*// \S+\.rs:\d+$").unwrap();
+    let mut ret = String::new();
+    let mut blank = false;
+    for line in verilog.lines() {
+        let line = line.trim_end();
+        if location.is_match(line) {
+            continue;
+        }
+        if line.is_empty() {
+            blank = !ret.is_empty();
+            continue;
+        }
+        if blank {
+            ret.push('\n');
+            blank = false;
+        }
+        ret.push_str(line);
+        ret.push('\n');
+    }
+    ret
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum DiffOp {
+    Same(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+// Line diff based on the longest common subsequence.  The common prefix and suffix
+// are trimmed first, which keeps the table small for the usual case of a few
+// localised changes.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffOp> {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let mut ops: Vec<DiffOp> = (0..prefix).map(|i| DiffOp::Same(i, i)).collect();
+    if a.len().saturating_mul(b.len()) > 16_000_000 {
+        // Too big to align - show it as a wholesale replacement
+        ops.extend((0..a.len()).map(|i| DiffOp::Delete(prefix + i)));
+        ops.extend((0..b.len()).map(|j| DiffOp::Insert(prefix + j)));
+    } else {
+        let width = b.len() + 1;
+        let mut lcs = vec![0_u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                ops.push(DiffOp::Same(prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if i < a.len()
+                && (j == b.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(DiffOp::Delete(prefix + i));
+                i += 1;
+            } else {
+                ops.push(DiffOp::Insert(prefix + j));
+                j += 1;
+            }
+        }
+    }
+    let old_tail = old.len() - suffix;
+    let new_tail = new.len() - suffix;
+    ops.extend((0..suffix).map(|k| DiffOp::Same(old_tail + k, new_tail + k)));
+    ops
+}
+
+/// Produce a unified diff (with `context` lines of context around each change) that
+/// turns `old` into `new`.  The result is empty if the two are identical.
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+    context: usize,
+) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Same(_, _)))
+        .map(|(ndx, _)| ndx)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+    // Group the changes into hunks, merging those whose context overlaps
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for ndx in changes {
+        let start = ndx.saturating_sub(context);
+        let end = (ndx + context + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let mut ret = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        let hunk = &ops[start..end];
+        // Line numbers at the start of the hunk, for each side
+        let (mut old_start, mut new_start) = (old_lines.len(), new_lines.len());
+        for op in &ops[start..] {
+            match *op {
+                DiffOp::Same(i, j) => {
+                    old_start = old_start.min(i);
+                    new_start = new_start.min(j);
+                    break;
+                }
+                DiffOp::Delete(i) => old_start = old_start.min(i),
+                DiffOp::Insert(j) => new_start = new_start.min(j),
+            }
+        }
+        let old_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Insert(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Delete(_)))
+            .count();
+        let range = |start: usize, count: usize| {
+            if count == 0 {
+                format!("{},0", start)
+            } else {
+                format!("{},{}", start + 1, count)
+            }
+        };
+        ret += &format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_count),
+            range(new_start, new_count)
+        );
+        for op in hunk {
+            match *op {
+                DiffOp::Same(i, _) => ret += &format!(" {}\n", old_lines[i]),
+                DiffOp::Delete(i) => ret += &format!("-{}\n", old_lines[i]),
+                DiffOp::Insert(j) => ret += &format!("+{}\n", new_lines[j]),
+            }
+        }
+    }
+    ret
+}
+
+/// Compare `verilog` against the snapshot `<dir>/<name>.v`.  If `update` is true,
+/// a missing or different snapshot is (re)written instead of reported as an error.
+/// On a mismatch the generated Verilog is also saved as `<dir>/<name>.v.new`.
+pub fn check_verilog_snapshot(
+    dir: &Path,
+    name: &str,
+    verilog: &str,
+    update: bool,
+) -> Result<SnapshotStatus, SnapshotError> {
+    let path = dir.join(format!("{}.v", name));
+    let new_path = dir.join(format!("{}.v.new", name));
+    let actual = normalize_verilog(verilog);
+    let expected = match std::fs::read_to_string(&path) {
+        Ok(x) => Some(normalize_verilog(&x)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let status = match &expected {
+        Some(expected) if expected == &actual => {
+            let _ = std::fs::remove_file(&new_path);
+            return Ok(SnapshotStatus::Matched);
+        }
+        Some(_) => SnapshotStatus::Updated,
+        None => SnapshotStatus::Created,
+    };
+    if update {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&path, &actual)?;
+        let _ = std::fs::remove_file(&new_path);
+        return Ok(status);
+    }
+    match expected {
+        None => Err(SnapshotError::Missing(path)),
+        Some(expected) => {
+            std::fs::write(&new_path, &actual)?;
+            let diff = unified_diff(
+                &expected,
+                &actual,
+                &path.to_string_lossy(),
+                &new_path.to_string_lossy(),
+                3,
+            );
+            Err(SnapshotError::Mismatch { path, diff })
+        }
+    }
+}
+
+/// Generate the Verilog for `uut` and compare it against the snapshot `<dir>/<name>.v`,
+/// panicking with a diff if they differ.  Set [SNAPSHOT_UPDATE_VAR] to accept changes.
+/// The circuit must already be connected (with `connect_all`).
+pub fn assert_verilog_snapshot<U: Block>(dir: &Path, name: &str, uut: &U) {
+    let verilog = generate_verilog(uut);
+    match check_verilog_snapshot(dir, name, &verilog, snapshot_updates_requested()) {
+        Ok(SnapshotStatus::Matched) => {}
+        Ok(status) => println!(
+            "Verilog snapshot {} {:?}",
+            dir.join(format!("{}.v", name)).display(),
+            status
+        ),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Compare the Verilog generated for a (connected) circuit against the golden snapshot
+/// `tests/snapshots/<name>.v` in the calling crate.  See [assert_verilog_snapshot].
+#[macro_export]
+macro_rules! verilog_snapshot {
+    ($uut: expr, $name: expr) => {
+        $crate::core::verilog_snapshot::assert_verilog_snapshot(
+            &std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("snapshots"),
+            $name,
+            &$uut,
+        )
+    };
+}
+
+#[test]
+fn test_normalize_verilog() {
+    let x = "\n\nmodule top(a);  \r\n\r\n\n   input a;\t\n    // src/top.rs:12\nendmodule\n\n";
+    assert_eq!(
+        normalize_verilog(x),
+        "module top(a);\n\n   input a;\nendmodule\n"
+    );
+}
+
+#[test]
+fn test_unified_diff() {
+    let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+    let new = "a\nb\nc\nD\ne\nf\ng\nh\ni\nj\nk\n";
+    let diff = unified_diff(old, new, "old", "new", 1);
+    assert_eq!(
+        diff,
+        "--- old\n+++ new\n@@ -3,3 +3,3 @@\n c\n-d\n+D\n e\n@@ -10,1 +10,2 @@\n j\n+k\n"
+    );
+    assert!(unified_diff(old, old, "old", "new", 3).is_empty());
+}
@@ -1,7 +1,7 @@
 use crate::core::ast::{
-    VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional, VerilogExpression,
-    VerilogIndexAssignment, VerilogLink, VerilogLiteral, VerilogLoop, VerilogMatch, VerilogOp,
-    VerilogOpUnary, VerilogStatement,
+    SourceLocation, VerilogBlock, VerilogBlockOrConditional, VerilogCase, VerilogConditional,
+    VerilogDisplay, VerilogExpression, VerilogFunctionCall, VerilogIndexAssignment, VerilogLink,
+    VerilogLiteral, VerilogLoop, VerilogMatch, VerilogOp, VerilogOpUnary, VerilogStatement,
 };
 
 pub trait VerilogVisitor {
@@ -47,6 +47,14 @@ pub trait VerilogVisitor {
         // Terminal
     }
 
+    fn visit_location(&mut self, _l: &SourceLocation) {
+        // Terminal
+    }
+
+    fn visit_display(&mut self, _d: &VerilogDisplay) {
+        // Terminal - simulation diagnostics do not take part in the analysis
+    }
+
     fn visit_signal(&mut self, _c: &str) {
         // Terminal
     }
@@ -109,6 +117,10 @@ pub trait VerilogVisitor {
         walk_slice_replace(self, a, b, c, d);
     }
 
+    fn visit_function_call(&mut self, c: &VerilogFunctionCall) {
+        walk_function_call(self, c);
+    }
+
     fn visit_index_replace(
         &mut self,
         a: &VerilogExpression,
@@ -117,6 +129,34 @@ pub trait VerilogVisitor {
     ) {
         walk_index_replacement(self, a, b, c);
     }
+
+    fn visit_concat(&mut self, a: &[VerilogExpression]) {
+        walk_concat(self, a);
+    }
+
+    fn visit_replicate(&mut self, a: &usize, b: &VerilogExpression) {
+        walk_replicate(self, a, b);
+    }
+}
+
+pub fn walk_function_call<V: VerilogVisitor + ?Sized>(visitor: &mut V, c: &VerilogFunctionCall) {
+    for arg in &c.args {
+        visitor.visit_expression(arg);
+    }
+}
+
+pub fn walk_concat<V: VerilogVisitor + ?Sized>(visitor: &mut V, a: &[VerilogExpression]) {
+    for x in a {
+        visitor.visit_expression(x);
+    }
+}
+
+pub fn walk_replicate<V: VerilogVisitor + ?Sized>(
+    visitor: &mut V,
+    _a: &usize,
+    b: &VerilogExpression,
+) {
+    visitor.visit_expression(b);
 }
 
 pub fn walk_index_replacement<V: VerilogVisitor + ?Sized>(
@@ -235,6 +275,12 @@ pub fn walk_statement<V: VerilogVisitor + ?Sized>(visitor: &mut V, s: &VerilogSt
                 visitor.visit_statement(statement);
             }
         }
+        VerilogStatement::Display(d) => {
+            visitor.visit_display(d);
+        }
+        VerilogStatement::Location(l) => {
+            visitor.visit_location(l);
+        }
     }
 }
 
@@ -332,5 +378,14 @@ pub fn walk_expression<V: VerilogVisitor + ?Sized>(visitor: &mut V, e: &VerilogE
         VerilogExpression::IndexReplace(a, b, c) => {
             visitor.visit_index_replace(a, b, c);
         }
+        VerilogExpression::Call(c) => {
+            visitor.visit_function_call(c);
+        }
+        VerilogExpression::Concat(a) => {
+            visitor.visit_concat(a);
+        }
+        VerilogExpression::Replicate(a, b) => {
+            visitor.visit_replicate(a, b);
+        }
     }
 }
@@ -13,6 +13,8 @@ pub enum SynthError {
     IOError(std::io::Error),
     WireHasNoDriver(Vec<String>),
     MissingModule(Vec<String>),
+    PortMismatch(Vec<String>),
+    NotEquivalent(Vec<String>),
 }
 
 impl From<std::io::Error> for SynthError {
@@ -84,11 +86,201 @@ pub fn yosys_validate(prefix: &str, translation: &str) -> Result<(), SynthError>
         )));
     }
     if !stdout.contains("End of script.") {
-        return Err(SynthError::SynthesisFailed { stdout, stderr });
+        return Err(SynthError::SynthesisFailed {
+            stdout: annotate_source_locations(translation, &stdout),
+            stderr: annotate_source_locations(translation, &stderr),
+        });
     }
     Ok(())
 }
 
+// Point the errors that yosys reports at `top.v:<line>` back to the Rust source, if the
+// Verilog was generated with source location annotations
+fn annotate_source_locations(translation: &str, output: &str) -> String {
+    let regex = regex::Regex::new(r"top\.v:(\d+)").unwrap();
+    regex
+        .replace_all(output, |x: &regex::Captures| {
+            match x[1]
+                .parse()
+                .ok()
+                .and_then(|line| source_location(translation, line))
+            {
+                Some(location) => format!("{} ({})", &x[0], location),
+                None => x[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+// Rename the modules defined in `verilog` by prefixing them with `prefix`, so that
+// two designs (which both have a `top`) can be loaded into the same yosys session.
+fn prefix_verilog_modules(verilog: &str, prefix: &str) -> String {
+    let module_decl = regex::Regex::new(r"^\s*module\s+([^\s(]+)").unwrap();
+    let mut modules: Vec<String> = verilog
+        .lines()
+        .filter_map(|line| module_decl.captures(line))
+        .map(|x| x[1].to_string())
+        .collect();
+    modules.sort();
+    modules.dedup();
+    let rename = |name: &str| -> Option<String> {
+        if modules.iter().any(|x| x == name) {
+            Some(format!("{}{}", prefix, name))
+        } else {
+            None
+        }
+    };
+    let mut ret = vec![];
+    for line in verilog.lines() {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let body = line.trim_start();
+        let mut tokens = body.splitn(2, |c: char| c.is_whitespace() || c == '(');
+        let first = tokens.next().unwrap_or_default();
+        let new_line = if first == "module" || first == "endmodule" {
+            // module name(...); or endmodule // name
+            let rest = &body[first.len()..];
+            let name_start = rest.len()
+                - rest
+                    .trim_start_matches(|c: char| c.is_whitespace() || c == '/')
+                    .len();
+            let tail = &rest[name_start..];
+            let name_end = tail
+                .find(|c: char| c.is_whitespace() || c == '(')
+                .unwrap_or(tail.len());
+            match rename(&tail[..name_end]) {
+                Some(name) => format!(
+                    "{}{}{}{}{}",
+                    indent,
+                    first,
+                    &rest[..name_start],
+                    name,
+                    &tail[name_end..]
+                ),
+                None => line.to_string(),
+            }
+        } else {
+            // An instantiation of a submodule:  name instance(
+            match rename(first) {
+                Some(name) if body[first.len()..].starts_with(' ') => {
+                    format!("{}{}{}", indent, name, &body[first.len()..])
+                }
+                _ => line.to_string(),
+            }
+        };
+        ret.push(new_line);
+    }
+    ret.join("\n")
+}
+
+fn port_signature(uut: &dyn Block) -> Vec<String> {
+    design_hierarchy(uut)
+        .ports
+        .iter()
+        .map(|x| format!("{} {:?} [{}]", x.name, x.direction, x.width))
+        .collect()
+}
+
+/// Use yosys to check that two designs are functionally equivalent.  The designs must
+/// have identical port lists (names, directions and widths).  Both are converted to
+/// Verilog, flattened, and compared with `equiv_make`, `equiv_simple` and `equiv_induct`,
+/// with `depth` steps of sequential induction.  Registers are matched by name, so
+/// designs whose state is held in differently named registers may not be provably
+/// equivalent, even if they are.
+///
+/// On failure, [SynthError::PortMismatch] lists the ports that differ, and
+/// [SynthError::NotEquivalent] lists the signals that could not be proven equal.
+pub fn yosys_equivalence<A: Block, B: Block>(
+    prefix: &str,
+    gold: &A,
+    gate: &B,
+    depth: usize,
+) -> Result<(), SynthError> {
+    let gold_ports = port_signature(gold);
+    let gate_ports = port_signature(gate);
+    if gold_ports != gate_ports {
+        let mut mismatch: Vec<String> = gold_ports
+            .iter()
+            .filter(|x| !gate_ports.contains(x))
+            .map(|x| format!("gold: {}", x))
+            .collect();
+        mismatch.extend(
+            gate_ports
+                .iter()
+                .filter(|x| !gold_ports.contains(x))
+                .map(|x| format!("gate: {}", x)),
+        );
+        return Err(SynthError::PortMismatch(mismatch));
+    }
+    let dir = temp_dir().as_path().join(prefix);
+    let _ = remove_dir_all(&dir);
+    let _ = create_dir_all(&dir);
+    std::fs::write(
+        dir.join("gold.v"),
+        prefix_verilog_modules(&generate_verilog(gold), "gold_"),
+    )?;
+    std::fs::write(
+        dir.join("gate.v"),
+        prefix_verilog_modules(&generate_verilog(gate), "gate_"),
+    )?;
+    let output = Command::new("yosys")
+        .current_dir(dir.clone())
+        .arg(format!(
+            "-p read -vlog95 gold.v gate.v; hierarchy -check; proc; flatten; opt_clean; \
+             equiv_make gold_top gate_top equiv; hierarchy -top equiv; async2sync; \
+             equiv_simple -seq {depth}; equiv_induct -seq {depth}; equiv_status",
+            depth = depth
+        ))
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    {
+        let mut debug = File::create(dir.join("yosys.stdout"))?;
+        write!(debug, "{}", stdout)?;
+        write!(debug, "{}", stderr)?;
+    }
+    if stdout.contains("Equivalence successfully proven!") {
+        return Ok(());
+    }
+    if stdout.contains("Unproven $equiv") {
+        let unproven = regex::Regex::new(r"Unproven \$equiv [^:]*: \\(\S+)").unwrap();
+        let mut signals: Vec<String> = unproven
+            .captures_iter(&stdout)
+            .map(|x| x[1].trim_end_matches("_gold").to_string())
+            .collect();
+        signals.sort();
+        signals.dedup();
+        return Err(SynthError::NotEquivalent(signals));
+    }
+    Err(SynthError::SynthesisFailed { stdout, stderr })
+}
+
+#[test]
+fn test_prefix_verilog_modules() {
+    let verilog = "
+module top(clock,q);
+    input wire clock;
+    output wire q;
+    top$flop flop(
+        .clock(clock),
+        .q(q)
+    );
+endmodule // top
+
+module top$flop(clock,q);
+    input wire clock;
+    output reg q;
+    always @(posedge clock) q <= ~q;
+endmodule // top$flop
+";
+    let renamed = prefix_verilog_modules(verilog, "gold_");
+    assert!(renamed.contains("module gold_top(clock,q);"));
+    assert!(renamed.contains("    gold_top$flop flop("));
+    assert!(renamed.contains("module gold_top$flop(clock,q);"));
+    assert!(renamed.contains("endmodule // gold_top$flop"));
+    assert!(renamed.contains("input wire clock;"));
+    assert!(!renamed.contains(" top"));
+}
+
 #[derive(LogicBlock)]
 pub struct TopWrap<U: Block> {
     pub uut: U,
@@ -1,4 +1,5 @@
 use crate::docs::vcd2svg::renderable::Renderable;
+use crate::docs::vcd2svg::signal_display::{TimeHighlight, TimeMarker};
 use crate::docs::vcd2svg::time_view::TimeView;
 use crate::docs::vcd2svg::timed_value::{changes, SignalType, TimedValue};
 use crate::docs::vcd2svg::utils::{line, rect, time_label};
@@ -19,6 +20,8 @@ pub struct DisplayMetrics {
     pub min_time: u64,
     pub max_time: u64,
     pub style: VCDStyle,
+    pub markers: Vec<TimeMarker>,
+    pub highlights: Vec<TimeHighlight>,
 }
 
 impl Default for DisplayMetrics {
@@ -36,6 +39,8 @@ impl Default for DisplayMetrics {
             min_time: 40,
             max_time: 102,
             style: VCDStyle::scansion(),
+            markers: vec![],
+            highlights: vec![],
         }
     }
 }
@@ -267,6 +272,61 @@ impl DisplayMetrics {
         document
     }
 
+    fn annotation_label(&self, x: u32, label: &str, color: &str) -> Text {
+        Text::new()
+            .add(svg::node::Text::new(label))
+            .set("x", x + self.shim / 2)
+            .set("y", self.timescale_height + self.shim / 2)
+            .set("text-anchor", "start")
+            .set("font-family", "sans-serif")
+            .set("alignment-baseline", "hanging")
+            .set("fill", color)
+            .set("font-size", self.label_size - 2)
+    }
+
+    pub(crate) fn markers(&self, mut doc: SVG) -> SVG {
+        for marker in &self.markers {
+            if let Some(x) = self.time_to_pixel(marker.time) {
+                let color = marker.color.as_ref().unwrap_or(&self.style.marker_color);
+                doc = doc
+                    .add(line(
+                        x,
+                        self.timescale_midline,
+                        x,
+                        self.canvas_height,
+                        color,
+                    ))
+                    .add(self.annotation_label(x, &marker.label, color));
+            }
+        }
+        doc
+    }
+
+    pub(crate) fn highlights(&self, mut doc: SVG) -> SVG {
+        for highlight in &self.highlights {
+            if highlight.end_time < self.min_time || highlight.start_time > self.max_time {
+                continue;
+            }
+            let x0 = self
+                .time_to_pixel(highlight.start_time.max(self.min_time))
+                .unwrap();
+            let x1 = self
+                .time_to_pixel(highlight.end_time.min(self.max_time))
+                .unwrap();
+            let color = highlight
+                .color
+                .as_ref()
+                .unwrap_or(&self.style.highlight_color);
+            doc = doc
+                .add(
+                    rect(x0, self.timescale_height, x1, self.canvas_height, color)
+                        .set("fill-opacity", 0.35),
+                )
+                .add(self.annotation_label(x0, &highlight.label, color));
+        }
+        doc
+    }
+
     pub(crate) fn horiz_grid_line(&self, index: usize, doc: SVG) -> SVG {
         if self.style.grid_lines.is_none() {
             return doc;
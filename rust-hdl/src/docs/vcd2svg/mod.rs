@@ -1,10 +1,12 @@
 use crate::docs::vcd2svg::display_metrics::DisplayMetrics;
+use crate::docs::vcd2svg::signal_display::SignalDisplay;
 use crate::docs::vcd2svg::trace_collection::TraceCollection;
 use crate::docs::vcd2svg::vcd_style::VCDStyle;
 
 pub mod display_metrics;
 mod interval;
 mod renderable;
+pub mod signal_display;
 pub mod symbols;
 pub mod text_frame;
 mod time_view;
@@ -12,6 +14,7 @@ mod timed_value;
 pub mod trace_collection;
 mod utils;
 pub mod vcd_style;
+pub mod wave_tool;
 
 pub fn vcd_to_svg(
     vcd_filename: &str,
@@ -20,13 +23,49 @@ pub fn vcd_to_svg(
     min_time_in_ps: u64,
     max_time_in_ps: u64,
 ) -> anyhow::Result<()> {
-    let vcd = std::fs::File::open(vcd_filename)?;
-    let traces = TraceCollection::parse(signal_names, vcd)?;
+    let signals = signal_names
+        .iter()
+        .map(|x| SignalDisplay::new(x))
+        .collect::<Vec<_>>();
     let mut metrics = DisplayMetrics::default();
     metrics.style = VCDStyle::gtkwave();
     metrics.min_time = min_time_in_ps;
     metrics.max_time = max_time_in_ps;
-    let document = traces.as_svg(&metrics)?;
+    vcd_to_svg_with_options(vcd_filename, svg_filename, &signals, &metrics)
+}
+
+/// Render a VCD file to an SVG, with control over how each signal is shown, and
+/// the time markers and highlighted intervals given in the [DisplayMetrics].
+///
+/// ```rust,no_run
+/// # use rust_hdl::docs::vcd2svg::*;
+/// # use rust_hdl::docs::vcd2svg::display_metrics::DisplayMetrics;
+/// # use rust_hdl::docs::vcd2svg::signal_display::*;
+/// let mut metrics = DisplayMetrics::default();
+/// metrics.min_time = 0;
+/// metrics.max_time = 1_000_000;
+/// metrics.markers.push(TimeMarker::new(250_000, "start"));
+/// metrics.highlights.push(TimeHighlight::new(400_000, 600_000, "burst"));
+/// vcd_to_svg_with_options(
+///     "adc.vcd",
+///     "adc.svg",
+///     &[
+///         SignalDisplay::new("uut.clock").alias("clk"),
+///         SignalDisplay::new("uut.adc.sample").format(ValueFormat::Signed),
+///     ],
+///     &metrics,
+/// )
+/// .unwrap();
+/// ```
+pub fn vcd_to_svg_with_options(
+    vcd_filename: &str,
+    svg_filename: &str,
+    signals: &[SignalDisplay],
+    metrics: &DisplayMetrics,
+) -> anyhow::Result<()> {
+    let vcd = std::fs::File::open(vcd_filename)?;
+    let traces = TraceCollection::parse_with_options(signals, vcd)?;
+    let document = traces.as_svg(metrics)?;
     svg::save(svg_filename, &document)?;
     Ok(())
 }
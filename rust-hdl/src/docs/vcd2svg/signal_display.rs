@@ -0,0 +1,178 @@
+use crate::core::synth::Synth;
+use crate::core::type_descriptor::TypeKind;
+use num_bigint::BigInt;
+use num_traits::{One, ToPrimitive, Zero};
+
+/// How the value of a multi-bit signal is shown in the trace
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueFormat {
+    /// Hexadecimal, e.g., `0h2a` (the default)
+    Hex,
+    /// Unsigned decimal
+    Decimal,
+    /// Signed (twos complement) decimal
+    Signed,
+    /// Binary, padded to the width of the signal
+    Binary,
+    /// The name of the enum variant with the given discriminant
+    Enum(Vec<String>),
+    /// The name of the enum variant with the given value, for enums that are not
+    /// binary encoded from zero (e.g., one-hot encoded states)
+    EncodedEnum(Vec<(usize, String)>),
+}
+
+impl Default for ValueFormat {
+    fn default() -> Self {
+        ValueFormat::Hex
+    }
+}
+
+impl ValueFormat {
+    /// Show the value using the variant names of a `LogicState` enum.  Use this
+    /// for signals that carry the discriminant of an enum (e.g., a `Bits` signal
+    /// or a trace from an external Verilog simulator).
+    pub fn enum_names<T: Synth>() -> ValueFormat {
+        match T::descriptor().kind {
+            TypeKind::Enum(variants) => {
+                let short = |x: &str| x.rsplit("::").next().unwrap_or(x).to_string();
+                if variants.iter().enumerate().all(|(ndx, x)| x.value == ndx) {
+                    ValueFormat::Enum(variants.iter().map(|x| short(&x.name)).collect())
+                } else {
+                    ValueFormat::EncodedEnum(
+                        variants.iter().map(|x| (x.value, short(&x.name))).collect(),
+                    )
+                }
+            }
+            _ => ValueFormat::Hex,
+        }
+    }
+
+    /// Format a value of a signal that is `width` bits wide
+    pub fn format(&self, value: &BigInt, width: usize) -> String {
+        match self {
+            ValueFormat::Hex => format!("0h{:x}", value),
+            ValueFormat::Decimal => format!("{}", value),
+            ValueFormat::Signed => {
+                let sign_bit = BigInt::one() << width.saturating_sub(1);
+                if width > 0 && (value & &sign_bit) != BigInt::zero() {
+                    format!("{}", value - (sign_bit << 1))
+                } else {
+                    format!("{}", value)
+                }
+            }
+            ValueFormat::Binary => format!("0b{:0width$b}", value, width = width),
+            ValueFormat::Enum(names) => {
+                let name = value.to_usize().and_then(|ndx| names.get(ndx).cloned());
+                name.unwrap_or_else(|| ValueFormat::Hex.format(value, width))
+            }
+            ValueFormat::EncodedEnum(names) => {
+                let name = value.to_usize().and_then(|value| {
+                    names
+                        .iter()
+                        .find(|(x, _)| *x == value)
+                        .map(|(_, name)| name.clone())
+                });
+                name.unwrap_or_else(|| ValueFormat::Hex.format(value, width))
+            }
+        }
+    }
+}
+
+/// Options for showing a signal in the trace.
+///
+/// ```rust
+/// # use rust_hdl::docs::vcd2svg::signal_display::{SignalDisplay, ValueFormat};
+/// let sig = SignalDisplay::new("uut.adc.count")
+///     .alias("count")
+///     .format(ValueFormat::Decimal);
+/// assert_eq!(sig.label(), "count");
+/// ```
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SignalDisplay {
+    /// The path of the signal in the VCD file, e.g., `uut.clock`
+    pub path: String,
+    /// The label to show for the signal (the path is used if this is not set)
+    pub alias: Option<String>,
+    /// The format used for the values of multi-bit signals
+    pub format: ValueFormat,
+}
+
+impl SignalDisplay {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+            ..Default::default()
+        }
+    }
+    pub fn alias(self, alias: &str) -> Self {
+        Self {
+            alias: Some(alias.into()),
+            ..self
+        }
+    }
+    pub fn format(self, format: ValueFormat) -> Self {
+        Self { format, ..self }
+    }
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.path)
+    }
+}
+
+impl From<&str> for SignalDisplay {
+    fn from(path: &str) -> Self {
+        SignalDisplay::new(path)
+    }
+}
+
+/// A vertical line drawn across the trace at the given time
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeMarker {
+    pub time: u64,
+    pub label: String,
+    /// The color of the marker (the style marker color is used if this is not set)
+    pub color: Option<String>,
+}
+
+impl TimeMarker {
+    pub fn new(time: u64, label: &str) -> Self {
+        Self {
+            time,
+            label: label.into(),
+            color: None,
+        }
+    }
+}
+
+/// A shaded interval of time, drawn behind the traces
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeHighlight {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub label: String,
+    /// The color of the highlight (the style highlight color is used if this is not set)
+    pub color: Option<String>,
+}
+
+impl TimeHighlight {
+    pub fn new(start_time: u64, end_time: u64, label: &str) -> Self {
+        Self {
+            start_time,
+            end_time,
+            label: label.into(),
+            color: None,
+        }
+    }
+}
+
+#[test]
+fn test_value_formats() {
+    let x = BigInt::from(0xfe);
+    assert_eq!(ValueFormat::Hex.format(&x, 8), "0hfe");
+    assert_eq!(ValueFormat::Decimal.format(&x, 8), "254");
+    assert_eq!(ValueFormat::Signed.format(&x, 8), "-2");
+    assert_eq!(ValueFormat::Signed.format(&x, 9), "254");
+    assert_eq!(ValueFormat::Binary.format(&BigInt::from(5), 4), "0b0101");
+    let names = ValueFormat::Enum(vec!["Idle".into(), "Run".into()]);
+    assert_eq!(names.format(&BigInt::from(1), 1), "Run");
+    assert_eq!(names.format(&BigInt::from(3), 2), "0h3");
+}
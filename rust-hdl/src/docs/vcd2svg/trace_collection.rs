@@ -1,4 +1,5 @@
 use crate::docs::vcd2svg::display_metrics::DisplayMetrics;
+use crate::docs::vcd2svg::signal_display::SignalDisplay;
 use crate::docs::vcd2svg::symbols;
 use crate::docs::vcd2svg::text_frame::TextFrame;
 use crate::docs::vcd2svg::timed_value::{changes, SignalType, TimedValue};
@@ -114,24 +115,35 @@ fn bin_trace<T: SignalType>(trace: &Vec<TimedValue<T>>, timing: &BinTimes) -> Ve
 
 pub struct TraceCollection {
     pub signal_names: Vec<(IdCode, String)>,
+    pub displays: Vec<SignalDisplay>,
+    pub widths: HashMap<IdCode, usize>,
     pub string_valued: HashMap<IdCode, StringTrace>,
     pub vector_valued: HashMap<IdCode, VectorTrace>,
     pub scalar_valued: HashMap<IdCode, BinaryTrace>,
 }
 
 impl TraceCollection {
-    pub fn parse(signals: &[&str], mut file: File) -> anyhow::Result<Self> {
+    pub fn parse(signals: &[&str], file: File) -> anyhow::Result<Self> {
+        let displays = signals
+            .iter()
+            .map(|x| SignalDisplay::new(x))
+            .collect::<Vec<_>>();
+        Self::parse_with_options(&displays, file)
+    }
+
+    pub fn parse_with_options(signals: &[SignalDisplay], mut file: File) -> anyhow::Result<Self> {
         let mut parser = vcd::Parser::new(&mut file);
         let header = parser.parse_header()?;
         let mut string_valued = HashMap::new();
         let mut vector_valued = HashMap::new();
         let mut scalar_valued = HashMap::new();
+        let mut widths = HashMap::new();
         let mut signal_names = Vec::new();
         for signal in signals {
-            let path = signal.split(".").collect::<Vec<_>>();
-            let sig = header
-                .find_var(&path)
-                .ok_or_else(|| anyhow::Error::msg(format!("cannot resolve signal {}", signal)))?;
+            let path = signal.path.split(".").collect::<Vec<_>>();
+            let sig = header.find_var(&path).ok_or_else(|| {
+                anyhow::Error::msg(format!("cannot resolve signal {}", signal.path))
+            })?;
             if sig.size == 0 {
                 string_valued.insert(sig.code, StringTrace::new());
             } else if sig.size == 1 {
@@ -139,7 +151,8 @@ impl TraceCollection {
             } else {
                 vector_valued.insert(sig.code, VectorTrace::new());
             }
-            signal_names.push((sig.code, signal.to_string()));
+            widths.insert(sig.code, sig.size as usize);
+            signal_names.push((sig.code, signal.path.clone()));
         }
         let mut timestamp = 0_u64;
         for command_result in parser {
@@ -177,12 +190,31 @@ impl TraceCollection {
         }
         Ok(Self {
             signal_names,
+            displays: signals.to_vec(),
+            widths,
             string_valued,
             vector_valued,
             scalar_valued,
         })
     }
 
+    // The values of a vector signal, in the format of its display
+    fn formatted(
+        &self,
+        code: &IdCode,
+        display: &SignalDisplay,
+        trace: &VectorTrace,
+    ) -> StringTrace {
+        let width = self.widths.get(code).copied().unwrap_or(0);
+        trace
+            .iter()
+            .map(|x| TimedValue {
+                time: x.time,
+                value: display.format.format(&x.value, width),
+            })
+            .collect()
+    }
+
     pub fn as_svg(&self, metrics: &DisplayMetrics) -> anyhow::Result<Document> {
         let document = Document::new()
             .set(
@@ -198,22 +230,30 @@ impl TraceCollection {
             .add(metrics.timescale_midline());
 
         document = metrics.timescale(document);
+        document = metrics.highlights(document);
 
-        for (index, details) in self.signal_names.iter().enumerate() {
+        for (index, (details, display)) in self
+            .signal_names
+            .iter()
+            .zip(self.displays.iter())
+            .enumerate()
+        {
             document = document
-                .add(metrics.signal_label(index, &details.1))
+                .add(metrics.signal_label(index, display.label()))
                 .add(metrics.signal_line(index));
             document = metrics.horiz_grid_line(index, document);
             if let Some(s) = self.scalar_valued.get(&details.0) {
                 document = document.add(metrics.bit_signal_plot(index, s));
             } else if let Some(s) = self.vector_valued.get(&details.0) {
-                document = metrics.vector_signal_plot(index, s, document);
+                let s = self.formatted(&details.0, display, s);
+                document = metrics.vector_signal_plot(index, &s, document);
             } else if let Some(s) = self.string_valued.get(&details.0) {
                 document = metrics.vector_signal_plot(index, s, document);
             } else {
                 anyhow::bail!("Unable to find signal {} in the trace...", details.1)
             }
         }
+        document = metrics.markers(document);
         Ok(document)
     }
     pub fn as_string(
@@ -239,14 +279,20 @@ impl TraceCollection {
             sig_columns + 1,
         );
         frame.write(1, sig_columns - 4, "time");
-        for (index, details) in self.signal_names.iter().enumerate() {
+        for (index, (details, display)) in self
+            .signal_names
+            .iter()
+            .zip(self.displays.iter())
+            .enumerate()
+        {
             let index = index + 1;
             frame.write(index * 3 + 1, sig_columns - details.1.len(), &details.1);
             if let Some(s) = self.scalar_valued.get(&details.0) {
                 let bins = bin_trace(&changes(s), &timing);
                 draw_symbols(&render_bool(&bins), &mut frame, index * 3, sig_columns + 1);
             } else if let Some(s) = self.vector_valued.get(&details.0) {
-                let bins = bin_trace(&changes(s), &timing);
+                let s = self.formatted(&details.0, display, s);
+                let bins = bin_trace(&changes(&s), &timing);
                 draw_symbols(
                     &render_multibit(&bins),
                     &mut frame,
@@ -6,6 +6,8 @@ pub struct VCDStyle {
     pub timeline_tick_color: String,
     pub signal_label_background_color: String,
     pub grid_lines: Option<String>,
+    pub marker_color: String,
+    pub highlight_color: String,
 }
 
 impl VCDStyle {
@@ -18,6 +20,8 @@ impl VCDStyle {
             timeline_tick_color: "#000000".into(),
             trace_color: "#87ecd1".into(),
             grid_lines: None,
+            marker_color: "#ff6f59".into(),
+            highlight_color: "#fce38a".into(),
         }
     }
     pub fn gtkwave() -> VCDStyle {
@@ -29,6 +33,8 @@ impl VCDStyle {
             timeline_tick_color: "#FFFFFF".into(),
             trace_color: "#00ff00".into(),
             grid_lines: Some("#202070".into()),
+            marker_color: "#ff4040".into(),
+            highlight_color: "#4040a0".into(),
         }
     }
 }
@@ -0,0 +1,419 @@
+//! The implementation of the `rhdl-wave` command line tool, which renders the
+//! signals of a VCD file as an SVG, a text diagram, or an interactive HTML page.
+//! See [USAGE] for the options.
+use crate::docs::vcd2svg::display_metrics::DisplayMetrics;
+use crate::docs::vcd2svg::signal_display::{SignalDisplay, TimeMarker, ValueFormat};
+use crate::docs::vcd2svg::trace_collection::TraceCollection;
+use crate::docs::vcd2svg::vcd_style::VCDStyle;
+use regex::Regex;
+use std::fs::File;
+use vcd::ScopeItem;
+
+/// The command line help for `rhdl-wave`
+pub const USAGE: &str = r#"rhdl-wave <file.vcd> [options]
+
+  -s, --signal PATTERN[=FORMAT]  Signals to show (may be repeated).  The pattern may
+                                 use `*` (any characters except `.`), `**` (any
+                                 characters) and `?`.  FORMAT is one of hex, dec,
+                                 signed or bin.  Defaults to all signals.
+  --from TIME                    Start of the time window (e.g., 100ns).  Default 0.
+                                 Times without a unit are in the units of the
+                                 file's $timescale.
+  --to TIME                      End of the time window.  Default is the end of the file.
+  --style gtkwave|scansion       The color scheme for SVG and HTML output
+  --format svg|txt|html          The output format (default is from the output file
+                                 extension, or txt)
+  -o, --output FILE              Where to write the output (default is stdout)
+  --columns N                    The width of text output (default 140)
+  --marker TIME[=LABEL]          Draw a marker at the given time (may be repeated)
+  --list                         List the signals in the file and exit
+"#;
+
+/// The kind of output to render
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaveFormat {
+    /// An SVG image
+    Svg,
+    /// A text diagram
+    Text,
+    /// A self-contained HTML page with zoom and pan
+    Html,
+}
+
+/// The options for `rhdl-wave`, as parsed from the command line
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaveOptions {
+    pub vcd_filename: String,
+    /// The signal patterns to show, and the format for each
+    pub signals: Vec<(String, ValueFormat)>,
+    /// The start of the time window (see [parse_time])
+    pub from: String,
+    /// The end of the time window (the end of the file if not set)
+    pub to: Option<String>,
+    pub style: String,
+    /// The output format (inferred from the output file name if not set)
+    pub format: Option<WaveFormat>,
+    /// The output file (stdout if not set)
+    pub output: Option<String>,
+    /// The width of text output
+    pub columns: usize,
+    /// The times and labels of the markers
+    pub markers: Vec<(String, String)>,
+    /// Only list the signals in the file
+    pub list: bool,
+}
+
+impl Default for WaveOptions {
+    fn default() -> Self {
+        Self {
+            vcd_filename: Default::default(),
+            signals: vec![],
+            from: "0".into(),
+            to: None,
+            style: "gtkwave".into(),
+            format: None,
+            output: None,
+            columns: 140,
+            markers: vec![],
+            list: false,
+        }
+    }
+}
+
+/// Parse a time with an optional unit (`ps`, `ns`, `us`, `ms` or `s`) into the units of
+/// a VCD file, whose `$timescale` is `timescale` seconds.  A time without a unit is
+/// already in the units of the file.
+pub fn parse_time(time: &str, timescale: f64) -> anyhow::Result<u64> {
+    let time = time.trim();
+    let split = time
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(time.len());
+    let (value, unit) = time.split_at(split);
+    let scale = match unit {
+        "" => 1.0,
+        "ps" => 1.0e-12 / timescale,
+        "ns" => 1.0e-9 / timescale,
+        "us" => 1.0e-6 / timescale,
+        "ms" => 1.0e-3 / timescale,
+        "s" => 1.0 / timescale,
+        _ => anyhow::bail!("unknown time unit in {}", time),
+    };
+    let value: f64 = value
+        .parse()
+        .map_err(|_| anyhow::Error::msg(format!("invalid time {}", time)))?;
+    Ok((value * scale).round() as u64)
+}
+
+fn parse_format(format: &str) -> anyhow::Result<ValueFormat> {
+    Ok(match format {
+        "hex" => ValueFormat::Hex,
+        "dec" => ValueFormat::Decimal,
+        "signed" => ValueFormat::Signed,
+        "bin" => ValueFormat::Binary,
+        _ => anyhow::bail!("unknown value format {}", format),
+    })
+}
+
+/// Parse the command line arguments (not including the program name)
+pub fn parse_args(args: &[String]) -> anyhow::Result<WaveOptions> {
+    let mut options = WaveOptions::default();
+    let mut args = args.iter();
+    let mut vcd_filename = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::Error::msg(format!("missing value for {}", arg)))
+        };
+        match arg.as_str() {
+            "-s" | "--signal" => {
+                let signal = value()?;
+                options.signals.push(match signal.split_once('=') {
+                    Some((pattern, format)) => (pattern.to_string(), parse_format(format)?),
+                    None => (signal.to_string(), ValueFormat::Hex),
+                });
+            }
+            // Times are checked here, but converted once the timescale is known
+            "--from" => {
+                let time = value()?;
+                parse_time(time, 1.0)?;
+                options.from = time.to_string();
+            }
+            "--to" => {
+                let time = value()?;
+                parse_time(time, 1.0)?;
+                options.to = Some(time.to_string());
+            }
+            "--style" => options.style = value()?.to_string(),
+            "--format" => {
+                options.format = Some(match value()?.as_str() {
+                    "svg" => WaveFormat::Svg,
+                    "txt" => WaveFormat::Text,
+                    "html" => WaveFormat::Html,
+                    x => anyhow::bail!("unknown output format {}", x),
+                })
+            }
+            "-o" | "--output" => options.output = Some(value()?.to_string()),
+            "--columns" => options.columns = value()?.parse()?,
+            "--marker" => {
+                let marker = value()?;
+                let (time, label) = marker.split_once('=').unwrap_or((marker, ""));
+                parse_time(time, 1.0)?;
+                options.markers.push((time.to_string(), label.to_string()));
+            }
+            "--list" => options.list = true,
+            x if x.starts_with('-') => anyhow::bail!("unknown option {}", x),
+            x => {
+                if vcd_filename.replace(x.to_string()).is_some() {
+                    anyhow::bail!("only one VCD file may be given")
+                }
+            }
+        }
+    }
+    options.vcd_filename = vcd_filename.ok_or_else(|| anyhow::Error::msg("no VCD file given"))?;
+    Ok(options)
+}
+
+fn collect_names(prefix: &str, items: &[ScopeItem], names: &mut Vec<String>) {
+    for item in items {
+        match item {
+            ScopeItem::Scope(scope) => {
+                let prefix = format!("{}{}.", prefix, scope.identifier);
+                collect_names(&prefix, &scope.children, names);
+            }
+            ScopeItem::Var(var) => names.push(format!("{}{}", prefix, var.reference)),
+        }
+    }
+}
+
+/// List the full paths of the signals in a VCD file
+pub fn vcd_signal_names(vcd_filename: &str) -> anyhow::Result<Vec<String>> {
+    let mut file = File::open(vcd_filename)?;
+    let header = vcd::Parser::new(&mut file).parse_header()?;
+    let mut names = vec![];
+    collect_names("", &header.items, &mut names);
+    Ok(names)
+}
+
+/// The `$timescale` of a VCD file in seconds (1ps if the file does not give one)
+pub fn vcd_timescale(vcd_filename: &str) -> anyhow::Result<f64> {
+    let mut file = File::open(vcd_filename)?;
+    let header = vcd::Parser::new(&mut file).parse_header()?;
+    Ok(match header.timescale {
+        Some((count, unit)) => count as f64 * unit.fraction(),
+        None => 1.0e-12,
+    })
+}
+
+/// The time of the last change in a VCD file
+pub fn vcd_end_time(vcd_filename: &str) -> anyhow::Result<u64> {
+    let mut file = File::open(vcd_filename)?;
+    let mut parser = vcd::Parser::new(&mut file);
+    parser.parse_header()?;
+    let mut end_time = 0;
+    for command in parser {
+        if let vcd::Command::Timestamp(x) = command? {
+            end_time = end_time.max(x);
+        }
+    }
+    Ok(end_time)
+}
+
+fn glob_to_regex(pattern: &str) -> Regex {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex += ".*";
+            }
+            '*' => regex += "[^.]*",
+            '?' => regex += "[^.]",
+            c => regex += &regex::escape(&c.to_string()),
+        }
+    }
+    regex += "$";
+    Regex::new(&regex).unwrap()
+}
+
+/// Select the signals that match any of the glob patterns, in the order of the
+/// patterns.  A `*` matches any characters except `.`, `**` matches any characters,
+/// and `?` matches a single character.
+pub fn match_signals(names: &[String], patterns: &[&str]) -> Vec<String> {
+    let mut ret: Vec<String> = vec![];
+    for pattern in patterns {
+        let regex = glob_to_regex(pattern);
+        for name in names {
+            if regex.is_match(name) && !ret.contains(name) {
+                ret.push(name.clone());
+            }
+        }
+    }
+    ret
+}
+
+/// Wrap an SVG in a self-contained HTML page, which can be zoomed with the mouse
+/// wheel and panned by dragging.
+pub fn svg_to_html(title: &str, svg: &str) -> String {
+    format!(
+        r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ margin: 0; background: #202020; overflow: hidden; }}
+#wave {{ width: 100vw; height: 100vh; cursor: grab; }}
+#wave svg {{ width: 100%; height: 100%; }}
+</style>
+</head>
+<body>
+<div id="wave">
+{svg}
+</div>
+<script>
+const svg = document.querySelector("#wave svg");
+const box = svg.getAttribute("viewBox").split(" ").map(Number);
+let view = box.slice();
+const apply = () => svg.setAttribute("viewBox", view.join(" "));
+svg.addEventListener("wheel", (e) => {{
+  e.preventDefault();
+  const rect = svg.getBoundingClientRect();
+  const fx = (e.clientX - rect.left) / rect.width;
+  const scale = e.deltaY < 0 ? 0.8 : 1.25;
+  const width = Math.min(box[2], view[2] * scale);
+  view[0] = Math.max(box[0], Math.min(box[0] + box[2] - width, view[0] + fx * (view[2] - width)));
+  view[2] = width;
+  apply();
+}});
+let drag = null;
+svg.addEventListener("mousedown", (e) => {{ drag = {{ x: e.clientX, x0: view[0] }}; }});
+window.addEventListener("mouseup", () => {{ drag = null; }});
+window.addEventListener("mousemove", (e) => {{
+  if (!drag) return;
+  const rect = svg.getBoundingClientRect();
+  const dx = (e.clientX - drag.x) * view[2] / rect.width;
+  view[0] = Math.max(box[0], Math.min(box[0] + box[2] - view[2], drag.x0 - dx));
+  apply();
+}});
+svg.addEventListener("dblclick", () => {{ view = box.slice(); apply(); }});
+</script>
+</body>
+</html>
+"##,
+        title = title,
+        svg = svg
+    )
+}
+
+/// Render the waveforms as described by the options, and return the output
+pub fn render(options: &WaveOptions) -> anyhow::Result<String> {
+    let names = vcd_signal_names(&options.vcd_filename)?;
+    let mut signals = vec![];
+    if options.signals.is_empty() {
+        signals.extend(names.iter().map(|x| SignalDisplay::new(x)));
+    }
+    for (pattern, format) in &options.signals {
+        let matches = match_signals(&names, &[pattern]);
+        if matches.is_empty() {
+            anyhow::bail!("no signals match {}", pattern);
+        }
+        signals.extend(
+            matches
+                .iter()
+                .map(|x| SignalDisplay::new(x).format(format.clone())),
+        );
+    }
+    let timescale = vcd_timescale(&options.vcd_filename)?;
+    let from = parse_time(&options.from, timescale)?;
+    let to = match &options.to {
+        Some(to) => parse_time(to, timescale)?,
+        None => vcd_end_time(&options.vcd_filename)?,
+    };
+    if to <= from {
+        anyhow::bail!("the time window is empty");
+    }
+    let format = options.format.unwrap_or_else(|| {
+        match options.output.as_ref().and_then(|x| x.rsplit('.').next()) {
+            Some("svg") => WaveFormat::Svg,
+            Some("html") | Some("htm") => WaveFormat::Html,
+            _ => WaveFormat::Text,
+        }
+    });
+    let traces = TraceCollection::parse_with_options(&signals, File::open(&options.vcd_filename)?)?;
+    if format == WaveFormat::Text {
+        return traces.as_string(from, to, options.columns);
+    }
+    let mut metrics = DisplayMetrics::default();
+    metrics.style = match options.style.as_str() {
+        "gtkwave" => VCDStyle::gtkwave(),
+        "scansion" => VCDStyle::scansion(),
+        x => anyhow::bail!("unknown style {}", x),
+    };
+    metrics.min_time = from;
+    metrics.max_time = to;
+    for (time, label) in &options.markers {
+        metrics
+            .markers
+            .push(TimeMarker::new(parse_time(time, timescale)?, label));
+    }
+    metrics.canvas_height =
+        metrics.timescale_height + (signals.len() as u32 + 1) * metrics.signal_height;
+    let svg = traces.as_svg(&metrics)?.to_string();
+    Ok(match format {
+        WaveFormat::Html => svg_to_html(&options.vcd_filename, &svg),
+        _ => svg,
+    })
+}
+
+/// Run the tool with the given command line arguments (not including the program name)
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let options = parse_args(args)?;
+    let output = if options.list {
+        vcd_signal_names(&options.vcd_filename)?.join("\n") + "\n"
+    } else {
+        render(&options)?
+    };
+    match &options.output {
+        Some(filename) => std::fs::write(filename, output)?,
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_time() {
+    assert_eq!(parse_time("150", 1.0e-12).unwrap(), 150);
+    assert_eq!(parse_time("2ns", 1.0e-12).unwrap(), 2_000);
+    assert_eq!(parse_time("1.5us", 1.0e-12).unwrap(), 1_500_000);
+    assert!(parse_time("3 furlongs", 1.0e-12).is_err());
+    // With a 1ns timescale, a bare number is a number of nanoseconds
+    assert_eq!(parse_time("150", 1.0e-9).unwrap(), 150);
+    assert_eq!(parse_time("2us", 1.0e-9).unwrap(), 2_000);
+    assert_eq!(parse_time("500ps", 1.0e-9).unwrap(), 1);
+}
+
+#[test]
+fn test_match_signals() {
+    let names = [
+        "uut.clock",
+        "uut.adc.count",
+        "uut.adc.state.q",
+        "uut.dac.count",
+    ]
+    .iter()
+    .map(|x| x.to_string())
+    .collect::<Vec<_>>();
+    assert_eq!(
+        match_signals(&names, &["uut.*.count"]),
+        vec!["uut.adc.count", "uut.dac.count"]
+    );
+    assert_eq!(
+        match_signals(&names, &["uut.adc.**", "uut.clock"]),
+        vec!["uut.adc.count", "uut.adc.state.q", "uut.clock"]
+    );
+    assert_eq!(
+        match_signals(&names, &["uut.?ac.count"]),
+        vec!["uut.dac.count"]
+    );
+}
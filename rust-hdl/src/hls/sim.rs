@@ -10,8 +10,8 @@ macro_rules! hls_fifo_write_lazy {
             $uut.$($fifo).+.write.next = true;
             wait_clock_cycle!($sim, $($clock).+, $uut);
             $uut.$($fifo).+.write.next = false;
-            if rand::thread_rng().gen::<f64>() < 0.2 {
-                for _ in 0..(rand::thread_rng().gen::<u8>() % 40) {
+            if rand::Rng::gen::<f64>($sim.rng()) < 0.2 {
+                for _ in 0..(rand::Rng::gen::<u8>($sim.rng()) % 40) {
                     wait_clock_cycle!($sim, $($clock).+, $uut);
                 }
             }
@@ -59,8 +59,8 @@ macro_rules! hls_fifo_read_lazy {
             $uut.$($fifo).+.read.next = true;
             wait_clock_cycle!($sim, $($clock).+, $uut);
             $uut.$($fifo).+.read.next = false;
-            if rand::thread_rng().gen::<f64>() < 0.2 {
-                for _ in 0..(rand::thread_rng().gen::<u8>() % 40) {
+            if rand::Rng::gen::<f64>($sim.rng()) < 0.2 {
+                for _ in 0..(rand::Rng::gen::<u8>($sim.rng()) % 40) {
                     wait_clock_cycle!($sim, $($clock).+, $uut);
                 }
             }
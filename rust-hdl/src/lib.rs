@@ -165,19 +165,27 @@
 //! However, in most cases, you can leave literals suffix-free, and Rust will automatically
 //! determine the type from the context.
 //!
-//! You can construct a larger constant using the [bits] function.  If you have a literal of up to
-//! 128 bits, it provides a functional form
+//! You can construct a larger vector using the [bits] function, which provides a functional form
+//! for literals of up to 64 bits
 //! ```
 //! # use rust_hdl::core::prelude::*;
-//! let x: Bits<200> = bits(0xDEAD_BEEE); // Works for up to 128 bit constants.
+//! let x: Bits<200> = bits(0xDEAD_BEEE); // Works for up to 64 bit constants.
 //! ```
 //!
 //! There is also the [ToBits] trait, which is implemented on the basic unsigned integer types.
-//! This trait allows you to handily convert from different integer values
+//! This trait allows you to handily convert from different integer values, including `u128`
 //!
 //! ```
 //! # use rust_hdl::core::prelude::*;
 //! let x: Bits<10> = 32_u8.to_bits();
+//! let y: Bits<128> = 0xFFFF_0000_FFFF_0000_FFFF_0000_FFFF_0000_u128.to_bits();
+//! ```
+//!
+//! Constants wider than that can be written as hex strings with [hex_bits](core::bits::hex_bits).
+//! Both forms can be used in HDL kernels, and become Verilog literals of the full width.
+//! ```
+//! # use rust_hdl::core::prelude::*;
+//! let x: Bits<160> = hex_bits("DEAD_BEEF_0000_0000_0000_0000_0000_0000_CAFE_BABE");
 //! ```
 //!
 //! ### Operations
@@ -194,7 +202,8 @@
 //! * Unsigned comparisons (e.g., `>,>=,<,<=`) between `Bits` of the same size - these are
 //! always treated as unsigned values for comparison purposes.
 //! * Shift left using the `<<` operator
-//! * Shift right (no sign extension!) using the '>>' operator
+//! * Shift right (no sign extension!) using the '>>' operator.  For `Signed` values, `ashr`
+//!   shifts in copies of the sign bit
 //! * Bitwise logical `NOT` using the `!` prefix operator
 //!
 //! These should feel natural when using RustHDL, as expressions follow Rust's rules (and not Verilog's).
@@ -210,6 +219,19 @@
 //! You can, of course, construct expressions of arbitrary complexity using parenthesis, etc.
 //! The only real surprise may be at synthesis time, when you try to fit the expression onto hardware.
 //!
+//! ### Fixed point numbers
+//!
+//! For DSP, the [Fixed](core::fixed::Fixed) and [UFixed](core::fixed::UFixed) types hold
+//! signed and unsigned values with a given number of integer and fraction bits.  They show up
+//! as real numbers in traces.  Rounding and saturation are chosen at each conversion or multiply:
+//! ```rust
+//! # use rust_hdl::core::prelude::*;
+//! let x = Fixed::<4, 12>::from_f64(1.5);
+//! let y: Fixed<4, 4> = fixed_cast::<4, 4, 4, 12>(x, Rounding::Nearest, Overflow::Saturate);
+//! assert_eq!(y.to_f64(), 1.5);
+//! ```
+//! See the [fixed](core::fixed) module for details.
+//!
 //! ### Signal Type
 //!
 //! *Signals are software abstractions to represent physical wires*.  The [Signal](core::signal::Signal)
@@ -629,6 +651,13 @@
 //!     - `bits`
 //!     - `Bits`
 //!     - `Type::join` and `Type::link` used to link and join logical interfaces...
+//! - Bit manipulation intrinsics, which need their widths spelled out, and become Verilog
+//!   concatenation and replication, or small Verilog functions
+//!     - `concat_bits!(a, b, c)` - concatenate bit vectors (the first argument is most significant)
+//!     - `replicate::<M, N>` - repeat a bit vector to fill `M` bits
+//!     - literal arguments of these need a width, e.g., `concat_bits!(bits::<4>(0xA), x)`
+//!     - `reverse_bits::<N>`, `rotate_left::<N>`, `rotate_right::<N>`
+//!     - `count_ones::<M, N>`, `leading_zeros::<M, N>` - counts returned as a `Bits<M>`
 //! - Method calls - Kernels support the following limited set of method calls
 //!     - `get_bits` - extract a (fixed width) set of bits from a bit vector
 //!     - `get_bit` - extract a single bit from a bit vector
@@ -636,6 +665,7 @@
 //!     - `all` - true if all the bits in the bit vector are true
 //!     - `any` - true if any of the bits in the bit vector are true
 //!     - `xor` - true if the number of ones in the bit vector is odd
+//!     - `ashr` - arithmetic (sign extending) shift right of a `Signed` value
 //!     - `val`, `into`, `index`, `to_bits` - ignored in HDL kernels
 //! ```rust
 //! # use rust_hdl::core::prelude::*;
@@ -709,9 +739,12 @@
 //! }
 //! ```
 //! - Macros - some macros are supported in kernels
-//!     - `println` - this is converted into a comment in the generated HDL
-//!     - `comment` - also a comment
-//!     - `assert` - converted to a comment
+//!     - `println` - this is converted into a `$display` in the generated HDL, with the format
+//!        arguments mapped to signal values (`{}` becomes `%0d`, `{:x}` becomes `%h` and `{:b}`
+//!        becomes `%b`, which print a [Bits] value the same way in both simulators)
+//!     - `comment` - this is converted into a comment in the generated HDL
+//!     - `assert` - this is converted into a check that calls `$error` if the condition is false
+//!     - Both `$display` and `$error` are wrapped in `` `ifndef SYNTHESIS `` so they only appear in simulation
 //!     - `dff_setup` - setup a DFF - this macro is converted into the appropriate HDL
 //!     - `clock` - clock a set of components - this macro is also converted into the appropriate HDL
 //! - Loops - `for` loops are supported for code generation
@@ -921,7 +954,7 @@
 //!
 //! ## Enums
 //!
-//! In keeping with Rust's strongly typed model, you can use enums (not sum types) in your HDL,
+//! In keeping with Rust's strongly typed model, you can use enums in your HDL,
 //! provided you derive the `LogicState` trait for them.  This makes your code much easier to
 //! read and debug, and `rustc` will make sure you don't do anything illegal with your
 //! enums.
@@ -1050,6 +1083,82 @@
 //! }
 //! ```
 //!
+//! - You can control how the `enum` is encoded in hardware
+//!
+//! By default, the variants are numbered from zero in binary.  If the values matter (e.g.,
+//! for opcodes that must match a spec), you can give explicit discriminants, and RustHDL
+//! will size the `enum` to hold the largest one.  Alternately, you can select a Gray or
+//! one-hot encoding with the `logic_state` attribute.  The encoding is used both in
+//! simulation (e.g., for `BITS` and conversion to `Bits`) and in the generated Verilog.
+//!
+//! ```rust
+//! # use rust_hdl::core::prelude::*;
+//!
+//! #[derive(Copy, Clone, PartialEq, Debug, LogicState)]
+//! enum Opcode {
+//!     Load = 0x03,
+//!     Store = 0x23,
+//!     Branch = 0x63,
+//! }
+//!
+//! #[derive(Copy, Clone, PartialEq, Debug, LogicState)]
+//! #[logic_state(encoding = "one_hot")]
+//! enum Phase {
+//!     Fetch,   // <-- 3'b001
+//!     Decode,  // <-- 3'b010
+//!     Execute, // <-- 3'b100
+//! }
+//!
+//! assert_eq!(Opcode::BITS, 7);
+//! assert_eq!(Phase::BITS, 3);
+//! ```
+//!
+//! - Enums can carry data
+//!
+//! Variants can have fields, in which case the `enum` is packed as a tag (in the least
+//! significant bits) followed by the fields of the active variant, and is as wide as the
+//! tag plus the largest variant.  In HDL, you can build a variant by assigning it to a
+//! signal, and take it apart with a `match`, where the bound fields are slices of the
+//! matched signal.  In a trace, the tag is shown by name, and the fields of each
+//! variant are shown separately (and unknown when that variant is not active).
+//!
+//! ```rust
+//! # use rust_hdl::core::prelude::*;
+//! #[derive(Copy, Clone, PartialEq, Debug, LogicState)]
+//! enum Cmd {
+//!     Nop,
+//!     Read { addr: Bits<24> },
+//!     Write { addr: Bits<24>, data: Bits<16> },
+//! }
+//!
+//! #[derive(LogicBlock, Default)]
+//! struct Decoder {
+//!     pub cmd: Signal<In, Cmd>,
+//!     pub addr: Signal<Out, Bits<24>>,
+//!     pub write: Signal<Out, Bit>,
+//! }
+//!
+//! impl Logic for Decoder {
+//!     #[hdl_gen]
+//!     fn update(&mut self) {
+//!         self.addr.next = 0.into();
+//!         self.write.next = false;
+//!         match self.cmd.val() {
+//!             Cmd::Read { addr } => {
+//!                 self.addr.next = addr;
+//!             }
+//!             Cmd::Write { addr, data: _ } => {
+//!                 self.addr.next = addr;
+//!                 self.write.next = true;
+//!             }
+//!             Cmd::Nop => {}
+//!         }
+//!     }
+//! }
+//!
+//! assert_eq!(Cmd::BITS, 2 + 40);
+//! ```
+//!
 //! ## Interfaces
 //!
 //! One area you will encouter as your circuits become more complex is that the interfaces
@@ -1723,6 +1832,13 @@
 //! thorough in it's checking than RustHDL, so I highly recommend you install it and use the
 //! [yosys_validate] function on your generated Verilog.
 //!
+//! The registers of the design start from their simulation values only if the FPGA gives them
+//! those values at power on.  Use [generate_verilog_with_options] with
+//! [VerilogOptions::initial_values] set to emit `initial` values for all of the registers
+//! (the [DFF](crate::widgets::dff::DFF) otherwise starts from `T::default()`).  On FPGAs
+//! that support this, you do not need an [AutoReset](crate::widgets::auto_reset::AutoReset)
+//! to put the design into its starting state.
+//!
 //! ## Struct valued signals
 //!
 //! We have seen how Enums and Interfaces can help make your code more compact and readable.  There
@@ -1846,6 +1962,55 @@
 //! don't get carried away.  Those expressions are evaluated by the HDL kernel generator and
 //! it has a limited vocab.
 //!
+//! ## Helper Functions
+//!
+//! Expressions that show up in many kernels (a CRC step, a saturating add, an address decode)
+//! can be pulled out into free functions marked with `#[hdl_fn]`.  These are plain Rust functions
+//! that take and return `Synth` types.  In simulation they are simply called.  In the generated
+//! Verilog, each module gets a `function` for every helper it uses.
+//! ```rust
+//! # use rust_hdl::core::prelude::*;
+//! #[hdl_fn]
+//! fn saturating_add(a: Bits<8>, b: Bits<8>) -> Bits<8> {
+//!     let sum: Bits<9> = bit_cast::<9, 8>(a) + bit_cast::<9, 8>(b);
+//!     if sum.get_bit(8) {
+//!         0xFF.into()
+//!     } else {
+//!         bit_cast::<8, 9>(sum)
+//!     }
+//! }
+//!
+//! #[hdl_fn]
+//! fn gray_code<const N: usize>(x: Bits<N>) -> Bits<N> {
+//!     x ^ (x >> 1)
+//! }
+//!
+//! #[derive(LogicBlock, Default)]
+//! struct Foo {
+//!     pub a: Signal<In, Bits<8>>,
+//!     pub b: Signal<In, Bits<8>>,
+//!     pub sum: Signal<Out, Bits<8>>,
+//!     pub gray: Signal<Out, Bits<8>>,
+//! }
+//!
+//! impl Logic for Foo {
+//!     #[hdl_gen]
+//!     fn update(&mut self) {
+//!         self.sum.next = saturating_add(self.a.val(), self.b.val());
+//!         self.gray.next = gray_code::<8>(self.a.val());
+//!     }
+//! }
+//! ```
+//!
+//! The body of a helper follows the same rules as an HDL kernel, with a few additions.  Locals
+//! can be declared with `let`, but they need a type, and the function must end with the value
+//! it returns (which can be an `if` or `match`).  A few other things to keep in mind:
+//!  - The attribute generates a companion function (`saturating_add_hdl` above) that must be in
+//!    scope wherever the helper is called from a kernel.  Import both, or call with a path.
+//!  - Generic helpers must be called with a turbofish (`gray_code::<8>`) from a kernel.  Each
+//!    instantiation becomes a separate Verilog function (`gray_code$8`).
+//!  - Only const generic parameters are supported.
+//!
 //! ## High Level Synthesis
 //!
 //! RustHDL supports it's own version of High Level Synthesis (HLS).  Normally, this is some kind
@@ -1979,6 +2144,7 @@
 
 #![warn(missing_docs)]
 
+pub mod cli;
 ///! The core RustHDL module.  Defines variable width bits, signals, logical blocks, etc.
 pub mod core;
 ///! Tools for documenting RustHDL designs, including the generation of SVGs from simulation waveforms.
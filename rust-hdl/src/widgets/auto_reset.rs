@@ -1,6 +1,11 @@
 use crate::core::prelude::*;
 use crate::widgets::dff::DFF;
 
+/// [AutoReset] holds `reset` high for the first 255 clock cycles after power on, by
+/// counting up from the power-on value of its register.  On FPGAs that support power-on
+/// values for their registers, it is not needed to bring the design into its initial state:
+/// generate the Verilog with [VerilogOptions::initial_values] set instead, and the registers
+/// start with the same values as they do in simulation.
 #[derive(Clone, Debug, LogicBlock, Default)]
 pub struct AutoReset {
     pub reset: Signal<Out, Bit>,
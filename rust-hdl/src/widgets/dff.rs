@@ -12,7 +12,7 @@ impl<T: Synth> Default for DFF<T> {
     fn default() -> DFF<T> {
         Self {
             d: Signal::default(),
-            q: Signal::default(),
+            q: Signal::new_uninitialized(),
             clock: Signal::default(),
         }
     }
@@ -21,7 +21,8 @@ impl<T: Synth> Default for DFF<T> {
 impl<T: Synth> Logic for DFF<T> {
     fn update(&mut self) {
         if self.clock.pos_edge() {
-            self.q.next = self.d.val()
+            self.q.next = self.d.val();
+            self.q.set_next_unknown_mask(self.d.unknown_mask());
         }
     }
     fn connect(&mut self) {
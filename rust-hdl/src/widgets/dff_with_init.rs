@@ -18,7 +18,11 @@ impl<T: Synth + BitXor<Output = T>> DFFWithInit<T> {
             q: Default::default(),
             clock: Default::default(),
             init: Constant::new(init),
-            dff: Default::default(),
+            // The inner register powers up to zero, so it is never unknown
+            dff: DFF {
+                q: Signal::default(),
+                ..Default::default()
+            },
         }
     }
 }
@@ -34,9 +34,12 @@ pub struct AsynchronousFIFO<D: Synth, const N: usize, const NP1: usize, const BL
     read_logic: FIFOReadLogic<D, N, NP1, BLOCK_SIZE>,
     // write logic
     write_logic: FIFOWriteLogic<D, N, NP1, BLOCK_SIZE>,
-    // Synchronize the write pointer to the read side
+    // Synchronize the write pointer to the read side.  The synchronizers are kept as
+    // separate modules so that clock domain crossing constraints can find them.
+    #[verilog_attribute(keep_hierarchy = "yes")]
     write_to_read: VectorSynchronizer<Bits<NP1>>,
     // Synchronize the read pointer to the write side
+    #[verilog_attribute(keep_hierarchy = "yes")]
     read_to_write: VectorSynchronizer<Bits<NP1>>,
 }
 
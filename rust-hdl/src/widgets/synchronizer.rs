@@ -4,8 +4,10 @@ use crate::widgets::dff::DFF;
 
 /// A [BitSynchronizer] is used to move signals that are asynchronous to a clock into that
 /// clock domain using a pair of back-to-back flip-flops.  While the first flip flop may
-/// become metastable, the second one is likely to be stable.
-#[derive(LogicBlock, Default)]
+/// become metastable, the second one is likely to be stable.  Both flip-flops are marked
+/// with the `ASYNC_REG` attribute in the generated HDL, so that the synthesis tools place
+/// them close together and do not optimize them away.
+#[derive(LogicBlock)]
 pub struct BitSynchronizer {
     /// The input signal, which is asynchronous to the clock
     pub sig_in: Signal<In, Bit>,
@@ -17,6 +19,22 @@ pub struct BitSynchronizer {
     dff1: DFF<Bit>,
 }
 
+impl Default for BitSynchronizer {
+    fn default() -> Self {
+        let mut dff0 = DFF::default();
+        let mut dff1 = DFF::default();
+        dff0.q.add_attribute(Attribute::async_reg());
+        dff1.q.add_attribute(Attribute::async_reg());
+        Self {
+            sig_in: Default::default(),
+            sig_out: Default::default(),
+            clock: Default::default(),
+            dff0,
+            dff1,
+        }
+    }
+}
+
 impl Logic for BitSynchronizer {
     #[hdl_gen]
     fn update(&mut self) {
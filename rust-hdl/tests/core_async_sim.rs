@@ -0,0 +1,111 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[test]
+fn test_async_rising_edge_detector_works() {
+    let uut = EdgeDetector::new(true);
+    let mut sim = AsyncSimulation::new();
+    sim.add_clock(5, |x: &mut EdgeDetector| x.clock.next = !x.clock.val());
+    sim.add_testbench(|sim: AsyncSim<EdgeDetector>| async move {
+        sim.circuit().input_signal.next = true;
+        for _ in 0..8 {
+            sim.clock_edge(|x| &x.clock).await?;
+        }
+        assert!(!sim.circuit().edge_signal.val());
+        sim.circuit().input_signal.next = false;
+        sim.clock_edge(|x| &x.clock).await?;
+        assert!(!sim.circuit().edge_signal.val());
+        sim.clock_edge(|x| &x.clock).await?;
+        assert!(!sim.circuit().edge_signal.val());
+        sim.circuit().input_signal.next = true;
+        sim.clock_edge(|x| &x.clock).await?;
+        assert!(sim.circuit().edge_signal.val());
+        sim.clock_edge(|x| &x.clock).await?;
+        assert!(!sim.circuit().edge_signal.val());
+        Ok(())
+    });
+    sim.run_traced(
+        Box::new(uut),
+        1000,
+        std::fs::File::create(vcd_path!("edge_det_async.vcd")).unwrap(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_async_wait_and_until() {
+    let uut = Strobe::<32>::new(1_000_000_000, 100_000_000.0);
+    let mut sim = AsyncSimulation::new();
+    sim.add_clock(500, |x: &mut Strobe<32>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|sim: AsyncSim<Strobe<32>>| async move {
+        sim.circuit().enable.next = true;
+        sim.wait(2_000).await?;
+        assert_eq!(sim.time(), 2_000);
+        sim.until(|x| x.strobe.val()).await?;
+        let first = sim.time();
+        sim.until(|x| !x.strobe.val()).await?;
+        sim.until(|x| x.strobe.val()).await?;
+        assert_eq!(sim.time() - first, 10_000);
+        Ok(())
+    });
+    sim.run(Box::new(uut), 100_000).unwrap();
+}
+
+#[test]
+fn test_async_halt_is_reported() {
+    let uut = Strobe::<32>::new(1_000_000_000, 100_000_000.0);
+    let mut sim = AsyncSimulation::new();
+    sim.add_clock(500, |x: &mut Strobe<32>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|sim: AsyncSim<Strobe<32>>| async move {
+        sim.wait(2_000).await?;
+        if !sim.circuit().strobe.val() {
+            return sim.halt();
+        }
+        Ok(())
+    });
+    match sim.run(Box::new(uut), 100_000) {
+        Err(SimError::SimHalted {
+            time, testbench, ..
+        }) => {
+            assert_eq!(time, 2_000);
+            assert_eq!(testbench, "testbench_1");
+        }
+        x => panic!("Unexpected result {:?}", x),
+    }
+}
+
+#[test]
+fn test_async_borrow_across_await_is_an_error() {
+    let uut = Strobe::<32>::new(1_000_000_000, 100_000_000.0);
+    let mut sim = AsyncSimulation::new();
+    sim.add_clock(500, |x: &mut Strobe<32>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|sim: AsyncSim<Strobe<32>>| async move {
+        let x = sim.circuit();
+        sim.wait(2_000).await?;
+        drop(x);
+        Ok(())
+    });
+    assert_eq!(
+        sim.run(Box::new(uut), 100_000),
+        Err(SimError::SimTerminated)
+    );
+}
+
+#[test]
+fn test_async_until_with_timeout() {
+    let uut = Strobe::<32>::new(1_000_000_000, 100_000_000.0);
+    let mut sim = AsyncSimulation::new();
+    sim.add_clock(500, |x: &mut Strobe<32>| x.clock.next = !x.clock.val());
+    sim.add_named_testbench("strobe_watch", |sim: AsyncSim<Strobe<32>>| async move {
+        sim.until_with_timeout(|x| x.strobe.val(), 5_000).await?;
+        Ok(())
+    });
+    assert_eq!(
+        sim.run(Box::new(uut), 100_000),
+        Err(SimError::Timeout {
+            time: 5_000,
+            testbench: "strobe_watch".into(),
+            timeout: 5_000
+        })
+    );
+}
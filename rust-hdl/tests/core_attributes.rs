@@ -0,0 +1,88 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock)]
+#[verilog_attribute(keep_hierarchy = "yes", DONT_TOUCH = true)]
+struct Memory {
+    pub clock: Signal<In, Clock>,
+    pub address: Signal<In, Bits<4>>,
+    pub data: Signal<Out, Bits<8>>,
+    #[verilog_attribute(ram_style = "block")]
+    store: Signal<Local, Bits<8>>,
+    probe: Signal<Local, Bits<8>>,
+    #[verilog_attribute(keep)]
+    delays: [DFF<Bits<8>>; 2],
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        let mut probe = Signal::default();
+        probe.add_attribute(Attribute::mark_debug());
+        probe.add_attribute(Attribute::keep());
+        Self {
+            clock: Default::default(),
+            address: Default::default(),
+            data: Default::default(),
+            store: Default::default(),
+            probe,
+            delays: Default::default(),
+        }
+    }
+}
+
+impl Logic for Memory {
+    #[hdl_gen]
+    fn update(&mut self) {
+        for i in 0..2 {
+            self.delays[i].clock.next = self.clock.val();
+        }
+        self.store.next = bit_cast::<8, 4>(self.address.val());
+        self.delays[0].d.next = self.store.val();
+        self.delays[1].d.next = self.delays[0].q.val();
+        self.probe.next = self.delays[1].q.val();
+        self.data.next = self.probe.val();
+    }
+}
+
+#[test]
+fn test_attributes_are_emitted() {
+    let mut uut = Memory::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    println!("{}", vlog);
+    assert!(vlog.contains("(* keep_hierarchy = \"yes\", DONT_TOUCH = \"true\" *)\nmodule top("));
+    assert!(vlog.contains("(* ram_style = \"block\" *) reg  [7:0] store;"));
+    assert!(vlog.contains("(* mark_debug = \"true\", keep *) reg  [7:0] probe;"));
+    assert!(vlog.contains("(* keep *) top$delays$0 delays$0("));
+    assert!(vlog.contains("(* keep *) top$delays$1 delays$1("));
+    // Attributes on the sub-module instances do not leak onto their stub signals
+    assert!(vlog.contains("\n    reg  [7:0] delays$0$d;"));
+    assert!(vlog.contains("\n\nmodule top$delays$0("));
+}
+
+#[test]
+fn test_synchronizer_flops_are_marked() {
+    let mut uut = BitSynchronizer::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert_eq!(
+        vlog.matches("(* ASYNC_REG = \"TRUE\" *) output reg  q;")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_async_fifo_synchronizers_are_marked() {
+    declare_async_fifo!(TFifo, Bits<8>, 16, 1);
+    let mut uut = TFifo::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("(* keep_hierarchy = \"yes\" *) top$write_to_read write_to_read("));
+    assert!(vlog.contains("(* keep_hierarchy = \"yes\" *) top$read_to_write read_to_write("));
+    assert_eq!(
+        vlog.matches("(* ASYNC_REG = \"TRUE\" *) output reg  q;")
+            .count(),
+        8
+    );
+}
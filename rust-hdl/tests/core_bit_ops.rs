@@ -0,0 +1,117 @@
+use rust_hdl::core::prelude::*;
+
+#[test]
+fn test_bit_ops_long_vectors() {
+    let x: Bits<40> = bits(0x00_DEAD_BEEF);
+    assert_eq!(reverse_bits::<40>(x), bits(0xF7_7DB5_7B00));
+    assert_eq!(rotate_left::<40>(x, 12), bits(0xEA_DBEE_F00D));
+    assert_eq!(rotate_right::<40>(x, 12), bits(0xEE_F00D_EADB));
+    assert_eq!(count_ones::<6, 40>(x), bits(24));
+    assert_eq!(leading_zeros::<6, 40>(x), bits(8));
+    assert_eq!(leading_zeros::<6, 40>(bits(0)), bits(40));
+    let y: Bits<80> = replicate::<80, 40>(x);
+    assert_eq!(y.get_bits::<40>(40), x);
+    assert_eq!(y.get_bits::<40>(0), x);
+    let z: Bits<48> = concat_bits!(bits::<8>(0x12), x);
+    assert_eq!(z, bits(0x12_00_DEAD_BEEF));
+}
+
+#[test]
+fn test_signed_ashr() {
+    let x: Signed<40> = signed(-1_000_000);
+    assert_eq!(x.ashr(4), signed(-62_500));
+    assert_eq!(x.ashr(40), signed(-1));
+    let y: Signed<8> = signed(100);
+    assert_eq!(y.ashr(3), signed(12));
+}
+
+#[test]
+#[should_panic]
+fn test_concat_width_mismatch() {
+    let x: Bits<8> = bits(0xFF);
+    let _y: Bits<12> = concat_bits!(x, true);
+}
+
+#[hdl_fn]
+fn swap_nibbles(x: Bits<8>) -> Bits<8> {
+    concat_bits!(x.get_bits::<4>(0), x.get_bits::<4>(4))
+}
+
+#[derive(LogicBlock, Default)]
+struct Packer {
+    pub data: Signal<In, Bits<8>>,
+    pub parity: Signal<In, Bit>,
+    pub amount: Signal<In, Bits<3>>,
+    pub sample: Signal<In, Signed<8>>,
+    pub frame: Signal<Out, Bits<11>>,
+    pub mask: Signal<Out, Bits<16>>,
+    pub reversed: Signal<Out, Bits<8>>,
+    pub rotated: Signal<Out, Bits<8>>,
+    pub ones: Signal<Out, Bits<4>>,
+    pub zeros: Signal<Out, Bits<4>>,
+    pub swapped: Signal<Out, Bits<8>>,
+    pub scaled: Signal<Out, Signed<8>>,
+    pub tagged: Signal<Out, Bits<16>>,
+    pub pattern: Signal<Out, Bits<16>>,
+}
+
+impl Logic for Packer {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.frame.next = concat_bits!(true, self.data.val(), self.parity.val(), false);
+        self.mask.next = replicate::<16, 1>(self.parity.val().into());
+        self.reversed.next = reverse_bits::<8>(self.data.val());
+        self.rotated.next = rotate_left::<8>(self.data.val(), self.amount.val().index());
+        self.ones.next = count_ones::<4, 8>(self.data.val());
+        self.zeros.next = leading_zeros::<4, 8>(self.data.val());
+        self.swapped.next = swap_nibbles(self.data.val());
+        self.scaled.next = self.sample.val().ashr(self.amount.val().index());
+        self.tagged.next = concat_bits!(bits::<4>(0xA), self.amount.val(), true, self.data.val());
+        self.pattern.next = replicate::<16, 4>(bits::<4>(0x5));
+    }
+}
+
+#[test]
+fn test_bit_ops_verilog() {
+    let mut uut = Packer::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("frame = {1'b1, data, parity, 1'b0};"));
+    assert!(vlog.contains("mask = {16{parity}};"));
+    assert!(vlog.contains("function [7:0] reverse_bits$8;"));
+    assert!(vlog.contains("rotated = rotate_left$8(data, amount);"));
+    assert!(vlog.contains("function [3:0] count_ones$4$8;"));
+    assert!(vlog.contains("function [3:0] leading_zeros$4$8;"));
+    assert!(vlog.contains("scaled = sample >>> amount;"));
+    // Literals keep their width
+    assert!(vlog.contains("tagged = {4'ha, amount, 1'b1, data};"));
+    assert!(vlog.contains("pattern = {4{4'h5}};"));
+    yosys_validate("bit_ops", &vlog).unwrap();
+}
+
+#[test]
+fn test_bit_ops_simulation() {
+    let mut uut = Packer::default();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_testbench(move |mut sim: Sim<Packer>| {
+        let mut x = sim.init()?;
+        x.data.next = 0b0011_0100.into();
+        x.parity.next = true;
+        x.amount.next = 3.into();
+        x.sample.next = signed(-100);
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(sim, x.frame.val(), 0b1_0011_0100_1_0, x);
+        sim_assert_eq!(sim, x.mask.val(), 0xFFFF, x);
+        sim_assert_eq!(sim, x.reversed.val(), 0b0010_1100, x);
+        sim_assert_eq!(sim, x.rotated.val(), 0b1010_0001, x);
+        sim_assert_eq!(sim, x.ones.val(), 3, x);
+        sim_assert_eq!(sim, x.zeros.val(), 2, x);
+        sim_assert_eq!(sim, x.swapped.val(), 0b0100_0011, x);
+        sim_assert_eq!(sim, x.scaled.val(), signed::<8>(-13), x);
+        sim_assert_eq!(sim, x.tagged.val(), 0b1010_011_1_0011_0100, x);
+        sim_assert_eq!(sim, x.pattern.val(), 0x5555, x);
+        sim.done(x)
+    });
+    sim.run(Box::new(uut), 100).unwrap();
+}
@@ -0,0 +1,96 @@
+use rust_hdl::cli::{run, CliError};
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock, Default)]
+struct Blinker {
+    pub clock: Signal<In, Clock>,
+    pub led: Signal<Out, Bit>,
+    counter: DFF<Bits<4>>,
+}
+
+impl Logic for Blinker {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, counter);
+        self.counter.d.next = self.counter.q.val() + 1;
+        self.led.next = self.counter.q.val().get_bit(3);
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Unconnected {
+    pub led: Signal<Out, Bit>,
+    inner: Blinker,
+}
+
+impl Logic for Unconnected {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.led.next = self.inner.led.val();
+    }
+}
+
+fn simulate_blinker(vcd: &str) -> Result<(), SimError> {
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<Blinker>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|mut sim: Sim<Blinker>| {
+        let mut x = sim.init()?;
+        wait_clock_cycles!(sim, clock, x, 20);
+        sim.done(x)
+    });
+    sim.run_to_file(Box::new(Blinker::default()), 10_000, vcd)
+}
+
+rust_hdl::register_design! {
+    name: "blinker",
+    description: "Blink an LED",
+    build: || Box::new(Blinker::default()),
+    simulate: simulate_blinker,
+}
+
+rust_hdl::register_design! {
+    name: "unconnected",
+    description: "A design with an undriven clock",
+    build: || Box::new(Unconnected::default()),
+}
+
+fn cli(args: &[&str]) -> Result<String, CliError> {
+    let mut args = args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    args.push("--out-dir".into());
+    args.push(target_path!("cli").to_string());
+    run(&args)
+}
+
+#[test]
+fn test_cli_lists_and_writes_verilog() {
+    let list = cli(&["list"]).unwrap();
+    assert!(list.contains("blinker"));
+    assert!(list.contains("Blink an LED"));
+    cli(&["verilog", "blinker"]).unwrap();
+    let verilog =
+        std::fs::read_to_string(format!("{}/blinker/top.v", target_path!("cli"))).unwrap();
+    assert!(verilog.contains("module top"));
+}
+
+#[test]
+fn test_cli_exit_codes() {
+    let err = cli(&["verilog", "nonesuch"]).unwrap_err();
+    assert_eq!(err.exit_code(), 2);
+    let err = cli(&["frobnicate"]).unwrap_err();
+    assert_eq!(err.exit_code(), 2);
+    let err = cli(&["build", "blinker"]).unwrap_err();
+    assert_eq!(err, CliError::Usage("build needs --board".into()));
+    let err = cli(&["verilog", "unconnected"]).unwrap_err();
+    assert_eq!(err.exit_code(), 1);
+    let err = cli(&["sim", "unconnected"]).unwrap_err();
+    assert_eq!(err.exit_code(), 2);
+}
+
+#[test]
+fn test_cli_simulates_and_renders_waves() {
+    cli(&["sim", "blinker"]).unwrap();
+    cli(&["wave", "blinker", "-s", "uut.led", "--to", "100"]).unwrap();
+    let svg = std::fs::read_to_string(format!("{}/blinker/wave.svg", target_path!("cli"))).unwrap();
+    assert!(svg.contains("uut.led"));
+}
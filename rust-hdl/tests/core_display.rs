@@ -0,0 +1,85 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock, Default)]
+struct Chatty {
+    pub clock: Signal<In, Clock>,
+    pub enable: Signal<In, Bit>,
+    pub count: Signal<Out, Bits<8>>,
+    counter: DFF<Bits<8>>,
+}
+
+impl Logic for Chatty {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, counter);
+        if self.enable.val() {
+            self.counter.d.next = self.counter.q.val() + 1;
+            println!(
+                "count {} (0x{:x}) is 100%",
+                self.counter.q.val(),
+                self.counter.q.val()
+            );
+        }
+        assert!(self.counter.q.val() != 200);
+        assert!(
+            self.counter.q.val() < 250,
+            "counter {:b} overflowed",
+            self.counter.q.val()
+        );
+        self.count.next = self.counter.q.val();
+    }
+}
+
+#[test]
+fn test_println_and_assert_become_system_tasks() {
+    let mut uut = Chatty::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    println!("{}", vlog);
+    assert!(vlog.contains(
+        "        `ifndef SYNTHESIS\n            $display(\"count %0d (0x%h) is 100%%\", counter$q, counter$q);\n            `endif"
+    ));
+    assert!(vlog.contains(
+        "if (!(counter$q != 32'hc8)) $error(\"assertion failed: counter.q.val()!=200\");"
+    ));
+    assert!(
+        vlog.contains("if (!(counter$q < 32'hfa)) $error(\"counter %b overflowed\", counter$q);")
+    );
+    assert!(!vlog.contains("// count"));
+}
+
+#[test]
+fn test_display_verilog_is_valid() {
+    let mut uut = Chatty::default();
+    uut.connect_all();
+    yosys_validate("display", &generate_verilog(&uut)).unwrap();
+}
+
+// Format a value of the given width the way a Verilog simulator does for `%0d`, `%h`
+// and `%b`.
+fn verilog_display(format: &str, width: usize, value: u64) -> String {
+    format
+        .replace("%0d", &format!("{}", value))
+        .replace("%h", &format!("{:0w$x}", value, w = width.div_ceil(4)))
+        .replace("%b", &format!("{:0w$b}", value, w = width))
+}
+
+#[test]
+fn test_display_matches_verilog_formatting() {
+    let mut uut = Chatty::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    let format = regex::Regex::new(r#"\$display\("([^"]*)""#)
+        .unwrap()
+        .captures(&vlog)
+        .unwrap()[1]
+        .replace("%%", "%");
+    // A value with leading zeros prints the same in both simulators
+    let value: Bits<8> = 0x0a.into();
+    assert_eq!(
+        format!("count {} (0x{:x}) is 100%", value, value),
+        verilog_display(&format, 8, 10)
+    );
+    assert_eq!(format!("{:b}", value), verilog_display("%b", 8, 10));
+}
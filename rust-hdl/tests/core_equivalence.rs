@@ -0,0 +1,101 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock, Default)]
+struct AdderAB {
+    a: Signal<In, Bits<8>>,
+    b: Signal<In, Bits<8>>,
+    sum: Signal<Out, Bits<8>>,
+}
+
+impl Logic for AdderAB {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.sum.next = self.a.val() + self.b.val();
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct AdderBA {
+    a: Signal<In, Bits<8>>,
+    b: Signal<In, Bits<8>>,
+    sum: Signal<Out, Bits<8>>,
+}
+
+impl Logic for AdderBA {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.sum.next = self.b.val() + self.a.val();
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Subtractor {
+    a: Signal<In, Bits<8>>,
+    b: Signal<In, Bits<8>>,
+    sum: Signal<Out, Bits<8>>,
+}
+
+impl Logic for Subtractor {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.sum.next = self.a.val() - self.b.val();
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct NarrowAdder {
+    a: Signal<In, Bits<4>>,
+    b: Signal<In, Bits<8>>,
+    sum: Signal<Out, Bits<8>>,
+}
+
+impl Logic for NarrowAdder {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.sum.next = bit_cast::<8, 4>(self.a.val()) + self.b.val();
+    }
+}
+
+fn connected<T: Block>(mut uut: T) -> T {
+    uut.connect_all();
+    uut
+}
+
+#[test]
+fn test_adders_are_equivalent() {
+    let gold = connected(AdderAB::default());
+    let gate = connected(AdderBA::default());
+    yosys_equivalence("equiv_adders", &gold, &gate, 4).unwrap();
+}
+
+#[test]
+fn test_strobes_are_equivalent() {
+    let gold = connected(Strobe::<32>::new(1_000_000, 1000.0));
+    let gate = connected(Strobe::<32>::new(1_000_000, 1000.0));
+    yosys_equivalence("equiv_strobes", &gold, &gate, 10).unwrap();
+}
+
+#[test]
+fn test_subtractor_is_not_an_adder() {
+    let gold = connected(AdderAB::default());
+    let gate = connected(Subtractor::default());
+    match yosys_equivalence("equiv_subtractor", &gold, &gate, 4) {
+        Err(SynthError::NotEquivalent(signals)) => {
+            assert_eq!(signals, vec!["sum".to_string()])
+        }
+        x => panic!("Expected non-equivalence, got {:?}", x),
+    }
+}
+
+#[test]
+fn test_port_mismatch_is_reported() {
+    let gold = connected(AdderAB::default());
+    let gate = connected(NarrowAdder::default());
+    match yosys_equivalence("equiv_ports", &gold, &gate, 4) {
+        Err(SynthError::PortMismatch(ports)) => {
+            assert_eq!(ports, vec!["gold: a Input [8]", "gate: a Input [4]"])
+        }
+        x => panic!("Expected a port mismatch, got {:?}", x),
+    }
+}
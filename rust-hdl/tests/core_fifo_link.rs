@@ -28,17 +28,17 @@ impl Logic for FIFOBridgeTest {
     }
 }
 
-impl Default for FIFOBridgeTest {
-    fn default() -> Self {
+impl FIFOBridgeTest {
+    fn new<R: Rng>(rng: &mut R) -> Self {
         let data1 = (0..256)
-            .map(|_| rand::thread_rng().gen::<u8>().to_bits())
+            .map(|_| rng.gen::<u8>().to_bits())
             .collect::<Vec<_>>();
         let data2 = data1.clone();
         Self {
-            feeder: LazyFIFOFeeder::new(&data2, &bursty_vec(256)),
+            feeder: LazyFIFOFeeder::new(&data2, &bursty_vec(rng, 256)),
             fp: Default::default(),
             bp: Default::default(),
-            reader: LazyFIFOReader::new(&data1, &bursty_vec(256)),
+            reader: LazyFIFOReader::new(&data1, &bursty_vec(rng, 256)),
             lnk: Default::default(),
             clock: Default::default(),
         }
@@ -47,12 +47,12 @@ impl Default for FIFOBridgeTest {
 
 #[test]
 fn test_fifo_linker() {
-    let mut uut = FIFOBridgeTest::default();
+    let mut sim = Simulation::new();
+    let mut uut = FIFOBridgeTest::new(&mut sim.rng());
     uut.clock.connect();
     uut.feeder.start.connect();
     uut.reader.start.connect();
     uut.connect_all();
-    let mut sim = Simulation::new();
     sim.add_clock(5, |x: &mut Box<FIFOBridgeTest>| {
         x.clock.next = !x.clock.val()
     });
@@ -0,0 +1,162 @@
+use rust_hdl::core::prelude::*;
+
+#[test]
+fn test_fixed_conversions() {
+    let x = Fixed::<4, 4>::from_f64(2.3);
+    assert_eq!(x.raw(), 37); // 2.3125 is the nearest value
+    assert_eq!(x.to_f64(), 2.3125);
+    assert_eq!(Fixed::<4, 4>::from_f64(100.0), Fixed::<4, 4>::max_value());
+    assert_eq!(Fixed::<4, 4>::from_f64(-100.0).to_f64(), -8.0);
+    assert_eq!(UFixed::<4, 4>::from_f64(-1.0), UFixed::<4, 4>::min_value());
+    // Addition and subtraction wrap around, as they do in hardware
+    let max = Fixed::<4, 4>::max_value();
+    assert_eq!(max + Fixed::from_raw(1), Fixed::<4, 4>::min_value());
+    assert_eq!((-x).to_f64(), -2.3125);
+    assert_eq!((x - Fixed::from_f64(0.5)).to_f64(), 1.8125);
+    assert!(-x < x);
+    assert_eq!(Fixed::<4, 4>::BITS, 8);
+    assert_eq!(Fixed::<4, 4>::from_f64(-1.0).verilog().to_string(), "8'hf0");
+}
+
+#[test]
+fn test_fixed_rounding_and_overflow() {
+    let x = Fixed::<4, 4>::from_f64(-2.6875);
+    let t = fixed_cast::<4, 2, 4, 4>(x, Rounding::Truncate, Overflow::Wrap);
+    assert_eq!(t.to_f64(), -2.75);
+    let r = fixed_cast::<4, 2, 4, 4>(x, Rounding::Nearest, Overflow::Wrap);
+    assert_eq!(r.to_f64(), -2.75);
+    let r = fixed_cast::<4, 2, 4, 4>(Fixed::from_f64(2.625), Rounding::Nearest, Overflow::Wrap);
+    assert_eq!(r.to_f64(), 2.75);
+    let big = Fixed::<8, 0>::from_f64(100.0);
+    let w = fixed_cast::<4, 4, 8, 0>(big, Rounding::Truncate, Overflow::Wrap);
+    assert_eq!(w.to_f64(), 4.0); // 100 = 0b110_0100, so 4 survives the wrap
+    let s = fixed_cast::<4, 4, 8, 0>(big, Rounding::Truncate, Overflow::Saturate);
+    assert_eq!(s, Fixed::<4, 4>::max_value());
+    let p = fixed_mul::<4, 4, 4, 4, 4, 4>(
+        Fixed::from_f64(-3.0),
+        Fixed::from_f64(3.0),
+        Rounding::Truncate,
+        Overflow::Saturate,
+    );
+    assert_eq!(p, Fixed::<4, 4>::min_value());
+    let u = ufixed_mul::<2, 6, 1, 7, 1, 7>(
+        UFixed::from_f64(1.5),
+        UFixed::from_f64(1.25),
+        Rounding::Nearest,
+        Overflow::Saturate,
+    );
+    assert_eq!(u.to_f64(), 1.875);
+}
+
+#[test]
+fn test_fixed_mul_at_64_bits() {
+    // The products of the widest values need all 128 bits
+    let max = UFixed::<64, 0>::max_value();
+    let s = ufixed_mul::<64, 0, 64, 0, 64, 0>(max, max, Rounding::Truncate, Overflow::Saturate);
+    assert_eq!(s, max);
+    let w = ufixed_mul::<64, 0, 64, 0, 64, 0>(max, max, Rounding::Truncate, Overflow::Wrap);
+    assert_eq!(w.raw(), 1);
+    let max = UFixed::<0, 64>::max_value();
+    let r = ufixed_mul::<0, 64, 0, 64, 0, 64>(max, max, Rounding::Nearest, Overflow::Saturate);
+    assert_eq!(r.raw(), u64::MAX - 1);
+    let one = ufixed_mul::<1, 0, 0, 64, 0, 64>(max, max, Rounding::Nearest, Overflow::Saturate);
+    assert_eq!(one.raw(), 1);
+    let min = Fixed::<64, 0>::min_value();
+    let s = fixed_mul::<64, 0, 64, 0, 64, 0>(min, min, Rounding::Nearest, Overflow::Saturate);
+    assert_eq!(s, Fixed::<64, 0>::max_value());
+    let half = Fixed::<1, 63>::from_f64(-0.5);
+    let p = fixed_mul::<1, 63, 1, 63, 1, 63>(half, half, Rounding::Nearest, Overflow::Saturate);
+    assert_eq!(p.to_f64(), 0.25);
+}
+
+#[derive(LogicBlock)]
+struct Scaler {
+    pub x: Signal<In, Fixed<4, 12>>,
+    pub gain: Signal<In, Fixed<2, 14>>,
+    pub volume: Signal<In, UFixed<1, 7>>,
+    pub y: Signal<Out, Fixed<4, 12>>,
+    pub level: Signal<Out, UFixed<1, 7>>,
+    offset: Constant<Fixed<4, 12>>,
+}
+
+impl Default for Scaler {
+    fn default() -> Self {
+        Self {
+            x: Default::default(),
+            gain: Default::default(),
+            volume: Default::default(),
+            y: Default::default(),
+            level: Default::default(),
+            offset: Constant::new(Fixed::from_f64(0.125)),
+        }
+    }
+}
+
+impl Logic for Scaler {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.y.next = fixed_mul::<4, 12, 2, 14, 4, 12>(
+            self.gain.val(),
+            self.x.val(),
+            Rounding::Nearest,
+            Overflow::Saturate,
+        ) + self.offset.val();
+        self.level.next = ufixed_mul::<1, 7, 1, 7, 1, 7>(
+            self.volume.val(),
+            self.volume.val(),
+            Rounding::Truncate,
+            Overflow::Saturate,
+        );
+    }
+}
+
+#[test]
+fn test_fixed_verilog() {
+    let mut uut = Scaler::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("input wire signed [15:0] x;"));
+    assert!(vlog.contains("localparam signed offset = 16'h200;"));
+    assert!(vlog.contains("function signed [15:0] fixed_mul$4$12$2$14$4$12$round$sat;"));
+    assert!(vlog.contains("ext = a * b;"));
+    assert!(vlog.contains("ext = ext >>> 64'he;"));
+    assert!(vlog.contains("function [7:0] ufixed_mul$1$7$1$7$1$7$trunc$sat;"));
+    yosys_validate("fixed", &vlog).unwrap();
+}
+
+#[test]
+fn test_fixed_simulation() {
+    let mut uut = Scaler::default();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_testbench(move |mut sim: Sim<Scaler>| {
+        let mut x = sim.init()?;
+        x.x.next = Fixed::from_f64(-2.5);
+        x.gain.next = Fixed::from_f64(0.75);
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(sim, x.y.val().to_f64(), -1.75, x);
+        x.x.next = Fixed::from_f64(7.5);
+        x.gain.next = Fixed::from_f64(1.5);
+        x = sim.wait(10, x)?;
+        // The product saturates before the offset is added (and wraps)
+        sim_assert_eq!(
+            sim,
+            x.y.val(),
+            Fixed::<4, 12>::max_value() + Fixed::from_f64(0.125),
+            x
+        );
+        x.volume.next = UFixed::from_f64(0.75);
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(sim, x.level.val().to_f64(), 0.5625, x);
+        x.volume.next = UFixed::from_f64(1.5);
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(sim, x.level.val(), UFixed::<1, 7>::max_value(), x);
+        sim.done(x)
+    });
+    let mut vcd = vec![];
+    sim.run_traced(Box::new(uut), 100, &mut vcd).unwrap();
+    // Fixed point signals are traced as real numbers
+    let vcd = String::from_utf8(vcd).unwrap();
+    assert!(vcd.contains("$var real 64"));
+    assert!(vcd.contains("r-1.75"));
+}
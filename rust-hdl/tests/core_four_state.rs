@@ -0,0 +1,243 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, LogicState)]
+enum Mode {
+    Idle,
+    Count,
+}
+
+#[derive(LogicBlock)]
+struct ResetCounter {
+    pub clock: Signal<In, Clock>,
+    pub reset: Signal<In, Bit>,
+    pub count: Signal<Out, Bits<8>>,
+    pub running: Signal<Out, Bit>,
+    counter: DFF<Bits<8>>,
+    mode: DFF<Mode>,
+}
+
+impl Default for ResetCounter {
+    fn default() -> Self {
+        Self {
+            clock: Default::default(),
+            reset: Default::default(),
+            count: Default::default(),
+            running: Default::default(),
+            counter: Default::default(),
+            mode: Default::default(),
+        }
+    }
+}
+
+impl Logic for ResetCounter {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, counter, mode);
+        self.count.next = self.counter.q.val();
+        self.running.next = false;
+        if self.reset.val() {
+            self.counter.d.next = 0.into();
+            self.mode.d.next = Mode::Idle;
+        } else {
+            self.counter.d.next = self.counter.q.val() + 1;
+            match self.mode.q.val() {
+                Mode::Idle => {
+                    self.mode.d.next = Mode::Count;
+                }
+                Mode::Count => {
+                    self.running.next = true;
+                }
+            }
+        }
+    }
+}
+
+fn run_counter(reset_cycles: u64, expect_unknown: bool) -> String {
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<ResetCounter>| x.clock.next = !x.clock.val());
+    sim.add_testbench(move |mut sim: Sim<ResetCounter>| {
+        let mut x = sim.init()?;
+        x.reset.next = reset_cycles > 0;
+        wait_clock_cycles!(sim, clock, x, reset_cycles);
+        x.reset.next = false;
+        for _ in 0..4 {
+            wait_clock_cycle!(sim, clock, x);
+            sim_assert_eq!(sim, x.count.is_unknown(), expect_unknown, x);
+        }
+        sim.done(x)
+    });
+    let mut vcd = vec![];
+    let uut = Box::new(ResetCounter::default());
+    sim.run_traced(uut, 1_000, &mut vcd).unwrap();
+    String::from_utf8(vcd).unwrap()
+}
+
+// Needs the propagation of unknown values through `#[hdl_gen]` code
+#[cfg(feature = "four-state")]
+#[test]
+fn test_four_state_uninitialized_register_is_unknown() {
+    four_state::set_four_state(true);
+    let _ = four_state::take_unknown_conditions();
+    let vcd = run_counter(0, true);
+    four_state::set_four_state(false);
+    assert!(vcd.contains("bxxxxxxxx"));
+    let reports = four_state::take_unknown_conditions();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].condition, "self.mode.q.val()");
+    assert!(reports[0].location.contains("core_four_state.rs"));
+}
+
+#[test]
+fn test_four_state_reset_clears_unknown() {
+    four_state::set_four_state(true);
+    run_counter(2, false);
+    let mut dff: DFF<Bits<8>> = DFF::default();
+    four_state::set_four_state(false);
+    let _ = four_state::take_unknown_conditions();
+    assert!(dff.q.is_unknown());
+    dff.d.next = 42.into();
+    dff.clock.next.clk = true;
+    dff.update_all();
+    dff.update_all();
+    assert!(!dff.q.is_unknown());
+    assert_eq!(dff.q.val(), 42);
+}
+
+#[test]
+fn test_two_state_has_no_unknowns() {
+    let vcd = run_counter(0, false);
+    assert!(!vcd.contains("bxxxxxxxx"));
+    assert!(four_state::take_unknown_conditions().is_empty());
+}
+
+#[test]
+fn test_four_state_dff_with_init_is_known() {
+    four_state::set_four_state(true);
+    let dff = DFFWithInit::<Bits<8>>::new(42.into());
+    let plain: DFF<Bits<8>> = DFF::default();
+    four_state::set_four_state(false);
+    assert!(!dff.q.is_unknown());
+    assert!(!dff.dff.q.is_unknown());
+    assert!(plain.q.is_unknown());
+}
+
+#[test]
+fn test_four_state_undriven_tristate_is_unknown() {
+    four_state::set_four_state(true);
+    let mut a: Signal<InOut, Bits<4>> = Default::default();
+    let mut b: Signal<InOut, Bits<4>> = Default::default();
+    a.simulate_connected_tristate(&mut b);
+    assert!(a.is_unknown());
+    a.set_tristate_is_output(true);
+    a.simulate_connected_tristate(&mut b);
+    assert!(!a.is_unknown());
+    four_state::set_four_state(false);
+}
+
+#[cfg(feature = "four-state")]
+#[derive(LogicBlock, Default)]
+struct BitLoader {
+    pub clock: Signal<In, Clock>,
+    pub load: Signal<In, Bit>,
+    pub low: Signal<Out, Bit>,
+    pub high: Signal<Out, Bits<4>>,
+    pub word: Signal<Out, Bits<8>>,
+    store: DFF<Bits<8>>,
+}
+
+#[cfg(feature = "four-state")]
+impl Logic for BitLoader {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, store);
+        if self.load.val() {
+            self.store.d.next = self.store.q.val().replace_bit(0, true);
+        }
+        self.low.next = self.store.q.val().get_bit(0);
+        self.high.next = self.store.q.val().get_bits::<4>(4);
+        self.word.next = self.store.q.val();
+    }
+}
+
+#[cfg(feature = "four-state")]
+#[test]
+fn test_four_state_tracks_bits() {
+    four_state::set_four_state(true);
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<BitLoader>| x.clock.next = !x.clock.val());
+    sim.add_testbench(move |mut sim: Sim<BitLoader>| {
+        let mut x = sim.init()?;
+        x.load.next = true;
+        wait_clock_cycles!(sim, clock, x, 2);
+        // Only the bit that was written is known
+        sim_assert!(sim, !x.low.is_unknown(), x);
+        sim_assert!(sim, x.low.val(), x);
+        sim_assert!(sim, x.high.is_unknown(), x);
+        sim_assert!(sim, x.word.is_unknown(), x);
+        sim.done(x)
+    });
+    let mut vcd = vec![];
+    let mut uut = BitLoader::default();
+    uut.connect_all();
+    let result = sim.run_traced(Box::new(uut), 100, &mut vcd);
+    four_state::set_four_state(false);
+    result.unwrap();
+    let _ = four_state::take_unknown_conditions();
+    assert!(String::from_utf8(vcd).unwrap().contains("bxxxxxxx1"));
+}
+
+#[derive(LogicBlock, Default)]
+struct Pins {
+    pub clock: Signal<In, Clock>,
+    pub bus: Signal<InOut, Bits<4>>,
+    pub value: Signal<Out, Bits<4>>,
+    store: DFF<Bits<4>>,
+}
+
+impl Logic for Pins {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, store);
+        self.value.next = self.store.q.val();
+    }
+}
+
+#[test]
+fn test_four_state_vcd_has_unknown_and_undriven_values() {
+    four_state::set_four_state(true);
+    let mut uut = Pins::default();
+    four_state::set_four_state(false);
+    uut.bus.connect();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<Pins>| x.clock.next = !x.clock.val());
+    sim.add_testbench(move |mut sim: Sim<Pins>| {
+        let mut x = sim.init()?;
+        x.bus.set_tristate_is_output(false);
+        wait_clock_cycle!(sim, clock, x);
+        sim.done(x)
+    });
+    let mut vcd = vec![];
+    sim.run_traced(Box::new(uut), 100, &mut vcd).unwrap();
+    let vcd = String::from_utf8(vcd).unwrap();
+    assert!(vcd.contains("bxxxx"));
+    assert!(vcd.contains("bzzzz"));
+}
+
+#[test]
+fn test_four_state_unknown_conditions_are_reported_once() {
+    let _ = four_state::take_unknown_conditions();
+    four_state::report_unknown_condition("top.rs:1", "self.a.val()");
+    assert!(four_state::take_unknown_conditions().is_empty());
+    four_state::set_four_state(true);
+    four_state::report_unknown_condition("top.rs:1", "self.a.val()");
+    four_state::report_unknown_condition("top.rs:1", "self.a.val()");
+    four_state::report_unknown_condition("top.rs:2", "self.b.val()");
+    four_state::set_four_state(false);
+    let reports = four_state::take_unknown_conditions();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].location, "top.rs:1");
+    assert_eq!(reports[1].condition, "self.b.val()");
+    assert!(four_state::take_unknown_conditions().is_empty());
+}
@@ -0,0 +1,93 @@
+use rust_hdl::core::prelude::*;
+
+#[hdl_fn]
+fn saturating_add(a: Bits<8>, b: Bits<8>) -> Bits<8> {
+    let sum: Bits<9> = bit_cast::<9, 8>(a) + bit_cast::<9, 8>(b);
+    if sum.get_bit(8) {
+        0xFF.into()
+    } else {
+        bit_cast::<8, 9>(sum)
+    }
+}
+
+#[hdl_fn]
+fn gray_code<const N: usize>(x: Bits<N>) -> Bits<N> {
+    x ^ (x >> 1)
+}
+
+#[hdl_fn]
+fn crc8_step(crc: Bits<8>, data: Bits<8>) -> Bits<8> {
+    let mut x: Bits<8> = crc ^ data;
+    for _i in 0..8 {
+        if x.get_bit(7) {
+            x = (x << 1) ^ 0x07;
+        } else {
+            x = x << 1;
+        }
+    }
+    x
+}
+
+#[derive(LogicBlock, Default)]
+struct Helpers {
+    pub a: Signal<In, Bits<8>>,
+    pub b: Signal<In, Bits<8>>,
+    pub sum: Signal<Out, Bits<8>>,
+    pub gray: Signal<Out, Bits<8>>,
+    pub crc: Signal<Out, Bits<8>>,
+}
+
+impl Logic for Helpers {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.sum.next = saturating_add(self.a.val(), self.b.val());
+        self.gray.next = gray_code::<8>(self.a.val());
+        self.crc.next = crc8_step(gray_code::<8>(self.a.val()), self.b.val());
+    }
+}
+
+#[test]
+fn test_hdl_fn_verilog() {
+    let mut uut = Helpers::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("function [7:0] saturating_add;"));
+    assert!(vlog.contains("input [7:0] a;"));
+    assert!(vlog.contains("reg [8:0] sum;"));
+    assert!(vlog.contains("function [7:0] gray_code$8;"));
+    assert!(vlog.contains("function [7:0] crc8_step;"));
+    assert!(vlog.contains("sum = saturating_add(a, b);"));
+    assert!(vlog.contains("crc = crc8_step(gray_code$8(a), b);"));
+    // Each function is defined once, no matter how often it is called
+    assert_eq!(vlog.matches("function [7:0] gray_code$8;").count(), 1);
+    yosys_validate("hdl_fn", &vlog).unwrap();
+}
+
+#[test]
+fn test_hdl_fn_simulation() {
+    let mut uut = Helpers::default();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_testbench(move |mut sim: Sim<Helpers>| {
+        let mut x = sim.init()?;
+        for (a, b) in [(0x10, 0x20), (0xF0, 0x20), (0x5A, 0xA5), (0xFF, 0x01)] {
+            x.a.next = a.into();
+            x.b.next = b.into();
+            x = sim.wait(10, x)?;
+            let sum = if a + b > 0xFF { 0xFF } else { a + b };
+            sim_assert_eq!(sim, x.sum.val(), sum, x);
+            sim_assert_eq!(sim, x.gray.val(), a ^ (a >> 1), x);
+            let mut crc = (a ^ (a >> 1)) ^ b;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 {
+                    ((crc << 1) ^ 0x07) & 0xFF
+                } else {
+                    (crc << 1) & 0xFF
+                };
+            }
+            sim_assert_eq!(sim, x.crc.val(), crc, x);
+        }
+        sim.done(x)
+    });
+    sim.run(Box::new(uut), 1000).unwrap();
+}
@@ -0,0 +1,105 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock)]
+struct PWMTest {
+    pub clock: Signal<In, Clock>,
+    pub pwm: PulseWidthModulator<8>,
+}
+
+impl Default for PWMTest {
+    fn default() -> Self {
+        Self {
+            clock: Signal::default(),
+            pwm: PulseWidthModulator::default(),
+        }
+    }
+}
+
+impl Logic for PWMTest {
+    #[hdl_gen]
+    fn update(&mut self) {
+        clock!(self, clock, pwm);
+        self.pwm.enable.next = true;
+        self.pwm.threshold.next = 32.into();
+    }
+}
+
+fn count_active(
+    sim: &mut Sim<PWMTest>,
+    mut x: Box<PWMTest>,
+) -> Result<(Box<PWMTest>, u32), SimError> {
+    let mut accum = 0;
+    for _ndx in 0..256 {
+        x = sim.wait(10, x)?;
+        if x.pwm.active.val() {
+            accum += 1;
+        }
+    }
+    Ok((x, accum))
+}
+
+#[hdl_test(uut = PWMTest::default(), clock = clock, interval = 5, max_time = 512 * 10)]
+fn test_hdl_test_pwm(mut sim: Sim<PWMTest>) -> Result<(), SimError> {
+    let x = sim.init()?;
+    let (x, accum) = count_active(&mut sim, x)?;
+    sim_assert_eq!(sim, accum, 32, x);
+    sim.done(x)
+}
+
+#[hdl_test(uut = PWMTest::default(), clock = clock, max_time = 512 * 10, waves = never)]
+#[should_panic(expected = "hdl_test test_hdl_test_reports_failure failed")]
+fn test_hdl_test_reports_failure(mut sim: Sim<PWMTest>) -> Result<(), SimError> {
+    let x = sim.init()?;
+    let (x, accum) = count_active(&mut sim, x)?;
+    sim_assert_eq!(sim, accum, 33, x);
+    sim.done(x)
+}
+
+#[test]
+fn test_hdl_test_keeps_waves_on_failure() {
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<PWMTest>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|mut sim: Sim<PWMTest>| {
+        let x = sim.init()?;
+        let (x, _) = count_active(&mut sim, x)?;
+        sim_assert!(sim, false, x);
+        sim.done(x)
+    });
+    let test = HdlTest::new(
+        module_path!(),
+        "keeps_waves_on_failure",
+        option_env!("CARGO_TARGET_TMPDIR"),
+    )
+    .yosys(false);
+    let dir = test.artifact_dir().to_owned();
+    assert!(dir.ends_with("hdl_test/core_hdl_test/keeps_waves_on_failure"));
+    let result = test.run(PWMTest::default(), sim, 512 * 10);
+    assert!(matches!(result, Err(HdlTestError::Sim(_))));
+    assert!(dir.join("top.v").exists());
+    let vcd = std::fs::read_to_string(dir.join("waves.vcd")).unwrap();
+    assert!(vcd.contains("$var"));
+    let report = std::fs::read_to_string(dir.join("report.txt")).unwrap();
+    assert!(report.contains("yosys: disabled"));
+    assert!(report.contains("FAILED: simulation failed"));
+}
+
+#[test]
+fn test_hdl_test_drops_waves_on_success() {
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<PWMTest>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|mut sim: Sim<PWMTest>| {
+        let x = sim.init()?;
+        let (x, _) = count_active(&mut sim, x)?;
+        sim.done(x)
+    });
+    let test = HdlTest::new(
+        module_path!(),
+        "drops_waves_on_success",
+        option_env!("CARGO_TARGET_TMPDIR"),
+    )
+    .yosys(false);
+    test.run(PWMTest::default(), sim, 512 * 10).unwrap();
+    assert!(test.artifact_dir().join("top.v").exists());
+    assert!(!test.artifact_dir().join("waves.vcd").exists());
+}
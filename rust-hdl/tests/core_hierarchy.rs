@@ -0,0 +1,126 @@
+use rust_hdl::core::hierarchy::*;
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock)]
+struct SPIFront {
+    pub clock: Signal<In, Clock>,
+    pub wires: SPIWiresSlave,
+    pub data: Signal<Out, Bits<16>>,
+    slave: SPISlave<16>,
+}
+
+impl Default for SPIFront {
+    fn default() -> Self {
+        let config = SPIConfig {
+            clock_speed: 48_000_000,
+            cs_off: true,
+            mosi_off: true,
+            speed_hz: 1_000_000,
+            cpha: true,
+            cpol: false,
+        };
+        Self {
+            clock: Default::default(),
+            wires: Default::default(),
+            data: Default::default(),
+            slave: SPISlave::new(config),
+        }
+    }
+}
+
+impl Logic for SPIFront {
+    #[hdl_gen]
+    fn update(&mut self) {
+        SPIWiresSlave::link(&mut self.wires, &mut self.slave.wires);
+        clock!(self, clock, slave);
+        self.slave.start_send.next = false;
+        self.slave.continued_transaction.next = false;
+        self.slave.data_outbound.next = 0.into();
+        self.slave.bits.next = 16.into();
+        self.slave.disabled.next = false;
+        self.data.next = self.slave.data_inbound.val();
+    }
+}
+
+#[derive(LogicBlock)]
+struct Board {
+    pub clock: Signal<In, Clock>,
+    pub spi: SPIWiresSlave,
+    pub leds: Signal<Out, Bits<8>>,
+    front: SPIFront,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        let mut leds = Signal::default();
+        for i in 0..8 {
+            leds.add_location(i, &format!("L{}", i));
+        }
+        Self {
+            clock: Default::default(),
+            spi: Default::default(),
+            leds,
+            front: Default::default(),
+        }
+    }
+}
+
+impl Logic for Board {
+    #[hdl_gen]
+    fn update(&mut self) {
+        SPIWiresSlave::link(&mut self.spi, &mut self.front.wires);
+        self.front.clock.next = self.clock.val();
+        self.leds.next = self.front.data.val().get_bits::<8>(0);
+    }
+}
+
+#[test]
+fn test_hierarchy_has_ports_constraints_and_connections() {
+    let uut = Board::default();
+    let top = design_hierarchy(&uut);
+    assert_eq!(top.instance, "top");
+    assert_eq!(top.children.len(), 1);
+    let front = &top.children[0];
+    assert_eq!(front.module, "top$front");
+    assert_eq!(front.children[0].instance, "slave");
+    let leds = top.ports.iter().find(|x| x.name == "leds").unwrap();
+    assert_eq!(leds.direction, PortDirection::Output);
+    assert_eq!(leds.width, 8);
+    assert_eq!(leds.constraints.len(), 8);
+    let miso = top.ports.iter().find(|x| x.name == "spi$miso").unwrap();
+    assert_eq!(miso.direction, PortDirection::Output);
+    let connection = |kind, from: &str, to: &str| HierarchyConnection {
+        kind,
+        from: from.into(),
+        to: to.into(),
+    };
+    assert!(top.connections.contains(&connection(
+        ConnectionKind::Link,
+        "spi$mosi",
+        "front.wires$mosi"
+    )));
+    assert!(top
+        .connections
+        .contains(&connection(ConnectionKind::Assign, "clock", "front.clock")));
+    assert!(front.connections.contains(&connection(
+        ConnectionKind::Assign,
+        "slave.data_inbound",
+        "data"
+    )));
+}
+
+#[test]
+fn test_hierarchy_json_and_block_diagram() {
+    let uut = Board::default();
+    let json = generate_hierarchy_json(&uut);
+    assert!(json.contains(r#""module": "top$front$slave""#));
+    assert!(!json.contains(r#""direction": "inout""#));
+    assert!(json.contains(r#""value": "L7""#));
+    assert!(json.contains(r#""variants": ["#));
+    let dot = generate_block_diagram(&uut, 1);
+    assert!(dot.contains(r#"subgraph "cluster_top" {"#));
+    assert!(dot.contains(r#""top.front" [shape=record"#));
+    assert!(dot.contains(r#""top.front" -> "top" [label="data -> leds"];"#));
+    assert!(!dot.contains("top.front.slave"));
+}
@@ -0,0 +1,120 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+// A register that powers up set, with a combinatorial output, in custom Verilog
+#[derive(LogicBlock)]
+struct Toggle {
+    pub clock: Signal<In, Clock>,
+    pub q: Signal<Out, Bit>,
+    pub not_q: Signal<Out, Bit>,
+}
+
+impl Default for Toggle {
+    fn default() -> Self {
+        Self {
+            clock: Default::default(),
+            q: Signal::new_with_default(true),
+            not_q: Default::default(),
+        }
+    }
+}
+
+impl Logic for Toggle {
+    fn update(&mut self) {
+        if self.clock.pos_edge() {
+            self.q.next = !self.q.val();
+        }
+        self.not_q.next = !self.q.val();
+    }
+    fn connect(&mut self) {
+        self.q.connect();
+        self.not_q.connect();
+    }
+    fn hdl(&self) -> Verilog {
+        Verilog::Custom("always @(posedge clock) q <= ~q;\nalways @(*) not_q = ~q;".into())
+    }
+}
+
+#[derive(LogicBlock)]
+struct PowerOn {
+    pub clock: Signal<In, Clock>,
+    pub phase: Signal<Out, Bit>,
+    pub count: Signal<Out, Bits<8>>,
+    toggle: Toggle,
+    counter: DFF<Bits<8>>,
+}
+
+impl Default for PowerOn {
+    fn default() -> Self {
+        Self {
+            clock: Default::default(),
+            phase: Default::default(),
+            count: Default::default(),
+            toggle: Default::default(),
+            counter: DFF {
+                q: Signal::new_with_default(5.into()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Logic for PowerOn {
+    #[hdl_gen]
+    fn update(&mut self) {
+        clock!(self, clock, toggle, counter);
+        self.counter.d.next = self.counter.q.val() + 1;
+        self.count.next = self.counter.q.val();
+        self.phase.next = self.toggle.q.val() & !self.toggle.not_q.val();
+    }
+}
+
+#[test]
+fn test_initial_values_are_optional() {
+    let mut uut = PowerOn::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(!vlog.contains("Power-on values"));
+    // Without the option, a DFF starts from the default value of its type
+    assert!(vlog.contains("q = 8'h0;"));
+    assert!(!vlog.contains("q = 8'h5;"));
+}
+
+#[test]
+fn test_registers_get_initial_values() {
+    let mut uut = PowerOn::default();
+    uut.connect_all();
+    let vlog = generate_verilog_with_options(
+        &uut,
+        VerilogOptions {
+            initial_values: true,
+            ..Default::default()
+        },
+    );
+    assert!(vlog.contains("// Power-on values\n    initial begin\n        q = 1'b1;\n    end"));
+    assert!(!vlog.contains("not_q = 1'b"));
+    // The DFF initializes its register itself, from the start value of its output
+    assert_eq!(vlog.matches("// Power-on values").count(), 1);
+    assert_eq!(vlog.matches("q = 8'h5;").count(), 1);
+    assert!(!vlog.contains("q = 8'h0;"));
+    yosys_validate("initial_values", &vlog).unwrap();
+}
+
+#[test]
+fn test_simulation_starts_from_initial_values() {
+    let mut uut = PowerOn::default();
+    uut.clock.connect();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<PowerOn>| x.clock.next = !x.clock.val());
+    sim.add_testbench(move |mut sim: Sim<PowerOn>| {
+        let mut x = sim.init()?;
+        sim_assert_eq!(sim, x.count.val(), 5, x);
+        sim_assert!(sim, x.phase.val(), x);
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(sim, x.count.val(), 6, x);
+        sim_assert!(sim, !x.phase.val(), x);
+        sim.done(x)
+    });
+    sim.run(Box::new(uut), 100).unwrap();
+}
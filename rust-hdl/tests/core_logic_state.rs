@@ -0,0 +1,120 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::docs::vcd2svg::signal_display::ValueFormat;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(Copy, Clone, PartialEq, Debug, LogicState)]
+enum Opcode {
+    Load = 0x03,
+    Store = 0x23,
+    Branch = 0x63,
+    Jump, // <-- 0x64
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, LogicState)]
+#[logic_state(encoding = "gray")]
+enum GrayState {
+    S0,
+    S1,
+    S2,
+    S3,
+    S4,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, LogicState)]
+#[logic_state(encoding = "one_hot")]
+enum HotState {
+    Idle,
+    Busy,
+    Done,
+}
+
+#[test]
+fn test_explicit_discriminants() {
+    assert_eq!(Opcode::BITS, 7);
+    let load: Bits<7> = Opcode::Load.into();
+    let jump: Bits<7> = Opcode::Jump.into();
+    assert_eq!(load, 0x03);
+    assert_eq!(jump, 0x64);
+    assert_eq!(
+        Opcode::Store.verilog().to_string(),
+        VerilogLiteral::from(0x23_usize).to_string()
+    );
+}
+
+#[test]
+fn test_gray_encoding() {
+    assert_eq!(GrayState::BITS, 3);
+    let codes: Vec<Bits<3>> = vec![
+        GrayState::S0.into(),
+        GrayState::S1.into(),
+        GrayState::S2.into(),
+        GrayState::S3.into(),
+        GrayState::S4.into(),
+    ];
+    assert_eq!(codes, vec![0b000, 0b001, 0b011, 0b010, 0b110]);
+}
+
+#[test]
+fn test_one_hot_encoding() {
+    assert_eq!(HotState::BITS, 3);
+    let busy: Bits<3> = HotState::Busy.into();
+    assert_eq!(busy, 0b010);
+    let format = ValueFormat::enum_names::<HotState>();
+    assert_eq!(format.format(&4.into(), 3), "Done");
+    assert_eq!(format.format(&3.into(), 3), "0h3");
+}
+
+#[derive(LogicBlock, Default)]
+struct Sequencer {
+    pub clock: Signal<In, Clock>,
+    pub start: Signal<In, Bit>,
+    pub done: Signal<Out, Bit>,
+    state: DFF<HotState>,
+}
+
+impl Logic for Sequencer {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, state);
+        self.done.next = false;
+        match self.state.q.val() {
+            HotState::Idle => {
+                if self.start.val() {
+                    self.state.d.next = HotState::Busy;
+                }
+            }
+            HotState::Busy => {
+                self.state.d.next = HotState::Done;
+            }
+            HotState::Done => {
+                self.done.next = true;
+                self.state.d.next = HotState::Idle;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_one_hot_state_machine() {
+    let mut uut = Sequencer::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("localparam HotState$Idle = 1;"));
+    assert!(vlog.contains("localparam HotState$Busy = 2;"));
+    assert!(vlog.contains("localparam HotState$Done = 4;"));
+    assert!(vlog.contains("reg  [2:0] state$d;"));
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<Sequencer>| x.clock.next = !x.clock.val());
+    sim.add_testbench(move |mut sim: Sim<Sequencer>| {
+        let mut x = sim.init()?;
+        x.start.next = true;
+        wait_clock_cycles!(sim, clock, x, 2);
+        x.start.next = false;
+        sim_assert!(sim, x.done.val(), x);
+        sim_assert_eq!(sim, x.state.q.val(), HotState::Done, x);
+        wait_clock_cycle!(sim, clock, x);
+        sim_assert!(sim, !x.done.val(), x);
+        sim.done(x)
+    });
+    sim.run(Box::new(uut), 1000).unwrap();
+}
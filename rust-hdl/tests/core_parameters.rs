@@ -0,0 +1,334 @@
+use rust_hdl::core::prelude::*;
+
+#[derive(LogicBlock, Default)]
+#[verilog_parameter(W)]
+struct Adder<const W: usize> {
+    pub a: Signal<In, Bits<W>>,
+    pub b: Signal<In, Bits<W>>,
+    pub sum: Signal<Out, Bits<W>>,
+    pub carry: Signal<Out, Bit>,
+}
+
+impl<const W: usize> Logic for Adder<W> {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.sum.next = self.a.val() + self.b.val();
+        self.carry.next = self.a.val() > self.sum.val();
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Pair {
+    pub x: Signal<In, Bits<8>>,
+    pub y: Signal<In, Bits<16>>,
+    pub x2: Signal<Out, Bits<8>>,
+    pub y2: Signal<Out, Bits<16>>,
+    narrow: Adder<8>,
+    wide: Adder<16>,
+    other: Adder<8>,
+}
+
+impl Logic for Pair {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.narrow.a.next = self.x.val();
+        self.narrow.b.next = self.x.val();
+        self.x2.next = self.narrow.sum.val();
+        self.other.a.next = self.x.val();
+        self.other.b.next = self.x.val();
+        self.wide.a.next = self.y.val();
+        self.wide.b.next = self.y.val();
+        self.y2.next = self.wide.sum.val();
+    }
+}
+
+#[test]
+fn test_parameterised_module_is_shared() {
+    let mut uut = Pair::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert_eq!(vlog.matches("module Adder #(parameter W = 8)(").count(), 1);
+    assert!(!vlog.contains("module top$narrow"));
+    assert!(vlog.contains("input wire  [W-1:0] a;"));
+    assert!(vlog.contains("output reg  [W-1:0] sum;"));
+    assert!(vlog.contains("output reg  carry;"));
+    assert!(vlog.contains("Adder #(.W(8)) narrow("));
+    assert!(vlog.contains("Adder #(.W(16)) wide("));
+    // The stubs in the parent have concrete widths
+    assert!(vlog.contains("reg  [15:0] wide$a;"));
+    yosys_validate("parameters", &vlog).unwrap();
+}
+
+#[test]
+fn test_parameterised_module_simulates() {
+    let mut uut = Pair::default();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_testbench(move |mut sim: Sim<Pair>| {
+        let mut x = sim.init()?;
+        x.x.next = 200.into();
+        x.y.next = 200.into();
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(sim, x.x2.val(), 144, x);
+        sim_assert_eq!(sim, x.y2.val(), 400, x);
+        sim.done(x)
+    });
+    sim.run(Box::new(uut), 100).unwrap();
+}
+
+#[derive(LogicBlock, Default)]
+#[verilog_parameter(WIDTH = T::BITS)]
+struct Register<T: Synth> {
+    pub clock: Signal<In, Clock>,
+    pub d: Signal<In, T>,
+    pub q: Signal<Out, T>,
+}
+
+impl<T: Synth> Logic for Register<T> {
+    fn update(&mut self) {
+        if self.clock.pos_edge() {
+            self.q.next = self.d.val();
+        }
+    }
+    fn connect(&mut self) {
+        self.q.connect();
+    }
+    fn hdl(&self) -> Verilog {
+        Verilog::Custom("always @(posedge clock) q <= d;".into())
+    }
+}
+
+#[derive(LogicBlock, Default)]
+#[verilog_parameter(N)]
+struct Accumulator<const N: usize> {
+    pub clock: Signal<In, Clock>,
+    pub data: Signal<In, Bits<N>>,
+    pub total: Signal<Out, Bits<N>>,
+    #[verilog_parameter(WIDTH = N)]
+    state: Register<Bits<N>>,
+}
+
+impl<const N: usize> Logic for Accumulator<N> {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.state.clock.next = self.clock.val();
+        self.state.d.next = self.state.q.val() + self.data.val();
+        self.total.next = self.state.q.val();
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Accumulators {
+    pub clock: Signal<In, Clock>,
+    pub data: Signal<In, Bits<16>>,
+    pub small: Signal<Out, Bits<4>>,
+    pub large: Signal<Out, Bits<16>>,
+    acc_4: Accumulator<4>,
+    acc_16: Accumulator<16>,
+}
+
+impl Logic for Accumulators {
+    #[hdl_gen]
+    fn update(&mut self) {
+        clock!(self, clock, acc_4, acc_16);
+        self.acc_4.data.next = self.data.val().get_bits::<4>(0);
+        self.acc_16.data.next = self.data.val();
+        self.small.next = self.acc_4.total.val();
+        self.large.next = self.acc_16.total.val();
+    }
+}
+
+#[test]
+fn test_parameters_are_passed_to_sub_blocks() {
+    let mut uut = Accumulators::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert_eq!(vlog.matches("module Accumulator #(").count(), 1);
+    assert_eq!(vlog.matches("module Register #(").count(), 1);
+    assert!(vlog.contains("module Register #(parameter WIDTH = 16)(clock,d,q);"));
+    assert!(vlog.contains("Register #(.WIDTH(N)) state("));
+    assert!(vlog.contains("reg  [N-1:0] state$d;"));
+    assert!(vlog.contains("Accumulator #(.N(4)) acc_4("));
+    yosys_validate("parameters_nested", &vlog).unwrap();
+}
+
+#[derive(LogicBlock)]
+#[verilog_parameter(W)]
+struct Offset<const W: usize> {
+    pub a: Signal<In, Bits<W>>,
+    pub b: Signal<Out, Bits<W>>,
+    offset: Constant<Bits<W>>,
+}
+
+impl<const W: usize> Default for Offset<W> {
+    fn default() -> Self {
+        Self {
+            a: Default::default(),
+            b: Default::default(),
+            offset: Constant::new(W.to_bits()),
+        }
+    }
+}
+
+impl<const W: usize> Logic for Offset<W> {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.b.next = self.a.val() + self.offset.val();
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Offsets {
+    pub a: Signal<In, Bits<8>>,
+    pub b: Signal<Out, Bits<8>>,
+    pub c: Signal<Out, Bits<4>>,
+    first: Offset<8>,
+    second: Offset<4>,
+}
+
+impl Logic for Offsets {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.first.a.next = self.a.val();
+        self.second.a.next = self.a.val().get_bits::<4>(0);
+        self.b.next = self.first.b.val();
+        self.c.next = self.second.b.val();
+    }
+}
+
+#[test]
+fn test_instances_that_differ_get_their_own_module() {
+    let mut uut = Offsets::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("module Offset #(parameter W = 8)(a,b);"));
+    assert!(vlog.contains("Offset #(.W(8)) first("));
+    assert!(vlog.contains("module top$second #(parameter W = 4)(a,b);"));
+    assert!(vlog.contains("top$second #(.W(4)) second("));
+    yosys_validate("parameters_fallback", &vlog).unwrap();
+}
+
+// A register that opts in to a shared module for all of its widths
+#[derive(LogicBlock, Default)]
+#[verilog_parameter(WIDTH = T::BITS)]
+struct Reg<T: Synth> {
+    pub d: Signal<In, T>,
+    pub q: Signal<Out, T>,
+    pub clock: Signal<In, Clock>,
+}
+
+impl<T: Synth> Logic for Reg<T> {
+    fn update(&mut self) {
+        if self.clock.pos_edge() {
+            self.q.next = self.d.val();
+        }
+    }
+    fn connect(&mut self) {
+        self.q.connect();
+    }
+    fn hdl(&self) -> Verilog {
+        Verilog::Custom("always @(posedge clock) q <= d;".into())
+    }
+}
+
+#[derive(LogicBlock, Default)]
+#[verilog_parameter(N)]
+struct Counter<const N: usize> {
+    pub clock: Signal<In, Clock>,
+    pub count: Signal<Out, Bits<N>>,
+    #[verilog_parameter(WIDTH = N)]
+    state: Reg<Bits<N>>,
+}
+
+impl<const N: usize> Logic for Counter<N> {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, state);
+        self.state.d.next = self.state.q.val() + 1;
+        self.count.next = self.state.q.val();
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Counters {
+    pub clock: Signal<In, Clock>,
+    pub small: Signal<Out, Bits<4>>,
+    pub large: Signal<Out, Bits<12>>,
+    count_4: Counter<4>,
+    count_12: Counter<12>,
+    total: Reg<Bits<32>>,
+}
+
+impl Logic for Counters {
+    #[hdl_gen]
+    fn update(&mut self) {
+        clock!(self, clock, count_4, count_12);
+        dff_setup!(self, clock, total);
+        self.total.d.next = self.total.q.val() + 1;
+        self.small.next = self.count_4.count.val();
+        self.large.next = self.count_12.count.val();
+    }
+}
+
+#[test]
+fn test_registers_share_a_module() {
+    let mut uut = Counters::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert_eq!(vlog.matches("module Counter #(").count(), 1);
+    assert_eq!(vlog.matches("module Reg #(").count(), 1);
+    assert!(vlog.contains("input wire  [WIDTH-1:0] d;"));
+    assert!(vlog.contains("Reg #(.WIDTH(N)) state("));
+    assert!(vlog.contains("Reg #(.WIDTH(32)) total("));
+    yosys_validate("parameters_reg", &vlog).unwrap();
+}
+
+mod other {
+    use rust_hdl::core::prelude::*;
+
+    // Not the same as the `Adder` above
+    #[derive(LogicBlock, Default)]
+    #[verilog_parameter(W)]
+    pub struct Adder<const W: usize> {
+        pub a: Signal<In, Bits<W>>,
+        pub sum: Signal<Out, Bits<W>>,
+    }
+
+    impl<const W: usize> Logic for Adder<W> {
+        #[hdl_gen]
+        fn update(&mut self) {
+            self.sum.next = self.a.val() + 1;
+        }
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Adders {
+    pub x: Signal<In, Bits<8>>,
+    pub y: Signal<Out, Bits<8>>,
+    pub z: Signal<Out, Bits<8>>,
+    mine: Adder<8>,
+    theirs: other::Adder<8>,
+}
+
+impl Logic for Adders {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.mine.a.next = self.x.val();
+        self.mine.b.next = self.x.val();
+        self.theirs.a.next = self.x.val();
+        self.y.next = self.mine.sum.val();
+        self.z.next = self.theirs.sum.val();
+    }
+}
+
+#[test]
+fn test_types_with_the_same_name_get_different_modules() {
+    let mut uut = Adders::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("module core_parameters$Adder #(parameter W = 8)(a,b,sum,carry);"));
+    assert!(vlog.contains("module core_parameters$other$Adder #(parameter W = 8)(a,sum);"));
+    assert!(vlog.contains("core_parameters$other$Adder #(.W(8)) theirs("));
+    yosys_validate("parameters_same_name", &vlog).unwrap();
+}
@@ -0,0 +1,180 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, LogicState)]
+enum Mode {
+    Idle,
+    Count,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Default, LogicStruct)]
+struct Status {
+    running: Bit,
+    count: Bits<4>,
+}
+
+#[derive(LogicBlock, Default)]
+struct Controller {
+    pub clock: Signal<In, Clock>,
+    pub start: Signal<In, Bit>,
+    pub status: Signal<Out, Status>,
+    counter: DFF<Bits<4>>,
+    state: DFF<Mode>,
+}
+
+impl Logic for Controller {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, counter, state);
+        self.status.next.running = false;
+        self.status.next.count = self.counter.q.val();
+        match self.state.q.val() {
+            Mode::Idle => {
+                if self.start.val() {
+                    self.state.d.next = Mode::Count;
+                }
+            }
+            Mode::Count => {
+                self.status.next.running = true;
+                self.counter.d.next = self.counter.q.val() + 1;
+            }
+        }
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Top {
+    pub clock: Signal<In, Clock>,
+    pub start: Signal<In, Bit>,
+    pub status: Signal<Out, Status>,
+    controller: Controller,
+}
+
+impl Logic for Top {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.controller.clock.next = self.clock.val();
+        self.controller.start.next = self.start.val();
+        self.status.next = self.controller.status.val();
+    }
+}
+
+#[test]
+fn test_signal_paths_are_listed() {
+    let uut = Top::default();
+    let paths = signal_paths(&uut);
+    assert!(paths.contains(&"start".to_string()));
+    assert!(paths.contains(&"controller.state.q".to_string()));
+    assert!(paths.contains(&"controller.counter.d".to_string()));
+}
+
+#[test]
+fn test_read_signal_values() {
+    let mut uut = Top::default();
+    uut.clock.connect();
+    uut.start.connect();
+    uut.connect_all();
+    assert_eq!(
+        read_signal(&uut, "controller.state.q"),
+        Ok(VCDValue::String("Idle".into()))
+    );
+    assert_eq!(
+        read_signal_as::<Mode>(&uut, "controller.state.q"),
+        Ok(Mode::Idle)
+    );
+    assert_eq!(
+        read_signal_as::<Status>(&uut, "status"),
+        Ok(Status::default())
+    );
+    assert_eq!(
+        read_signal(&uut, "controller.nope"),
+        Err(SignalPathError::NotFound("controller.nope".into()))
+    );
+    assert_eq!(
+        read_signal_as::<Bits<2>>(&uut, "controller.counter.q"),
+        Err(SignalPathError::InvalidValue("controller.counter.q".into()))
+    );
+}
+
+#[test]
+fn test_force_signal_checks_direction_and_type() {
+    let mut uut = Top::default();
+    assert_eq!(
+        force_signal(&mut uut, "status", Status::default().vcd()),
+        Err(SignalPathError::NotAnInput("status".into()))
+    );
+    assert_eq!(
+        force_signal(&mut uut, "start", Bits::<4>::from(3)),
+        Err(SignalPathError::InvalidValue("start".into()))
+    );
+    assert_eq!(force_signal(&mut uut, "start", true), Ok(()));
+    assert!(uut.start.next);
+}
+
+#[test]
+fn test_table_driven_testbench() {
+    let uut = Top::default();
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<Top>| x.clock.next = !x.clock.val());
+    sim.add_testbench(move |mut sim: Sim<Top>| {
+        let mut x = sim.init()?;
+        let table = [
+            ("start", VCDValue::from(true), "controller.state.q", "Count"),
+            (
+                "start",
+                VCDValue::from(false),
+                "controller.state.q",
+                "Count",
+            ),
+        ];
+        for (input, value, output, expected) in &table {
+            force_signal(x.as_mut(), input, value.clone()).unwrap();
+            wait_clock_cycle!(sim, clock, x);
+            let actual = read_signal(x.as_ref(), output).unwrap();
+            sim_assert_eq!(sim, actual, VCDValue::String(expected.to_string()), x);
+        }
+        wait_clock_cycles!(sim, clock, x, 3);
+        let status = read_signal_as::<Status>(x.as_ref(), "status").unwrap();
+        sim_assert!(sim, status.running, x);
+        sim_assert_eq!(sim, status.count, 4, x);
+        sim.done(x)
+    });
+    sim.run(Box::new(uut), 1_000).unwrap();
+}
+
+// A hand written block, which only implements the required methods of `Block`
+#[derive(Default)]
+struct Manual {
+    enable: Signal<In, Bit>,
+}
+
+impl Logic for Manual {
+    fn update(&mut self) {}
+}
+
+impl Block for Manual {
+    fn connect_all(&mut self) {
+        self.enable.connect_all();
+    }
+    fn update_all(&mut self) {
+        self.enable.update_all();
+    }
+    fn has_changed(&self) -> bool {
+        self.enable.has_changed()
+    }
+    fn accept(&self, name: &str, probe: &mut dyn Probe) {
+        probe.visit_start_scope(name, self);
+        self.enable.accept("enable", probe);
+        probe.visit_end_scope(name, self);
+    }
+}
+
+#[test]
+fn test_blocks_without_accept_mut_are_skipped() {
+    let mut uut = Manual::default();
+    assert_eq!(read_signal_as::<Bit>(&uut, "enable"), Ok(false));
+    assert_eq!(
+        force_signal(&mut uut, "enable", true),
+        Err(SignalPathError::NotFound("enable".into()))
+    );
+}
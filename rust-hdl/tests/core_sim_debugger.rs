@@ -0,0 +1,99 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+use std::cell::RefCell;
+use std::io::{Cursor, Write};
+use std::rc::Rc;
+
+#[derive(Clone, Default)]
+struct Transcript(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Transcript {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Transcript {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Counter {
+    pub clock: Signal<In, Clock>,
+    pub count: Signal<Out, Bits<8>>,
+    counter: DFF<Bits<8>>,
+}
+
+impl Logic for Counter {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, counter);
+        self.counter.d.next = self.counter.q.val() + 1;
+        self.count.next = self.counter.q.val();
+    }
+}
+
+fn counter_sim() -> Simulation<Counter> {
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<Counter>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|mut sim: Sim<Counter>| {
+        let mut x = sim.init()?;
+        wait_clock_cycles!(sim, clock, x, 20);
+        sim_assert!(sim, x.count.val() == 0, x);
+        sim.done(x)
+    });
+    sim
+}
+
+#[test]
+fn test_debugger_breakpoints_and_stepping() {
+    let script =
+        "ls counter\nb 100\nc\np counter.q\nn clock 2\np counter.q\nw count\nc\nunwatch count\nc\np count\nc\n";
+    let transcript = Transcript::default();
+    let mut debugger = Debugger::with_io(Cursor::new(script), transcript.clone());
+    let result = counter_sim().run_debug(Box::new(Counter::default()), 10_000, &mut debugger);
+    assert!(matches!(result, Err(SimError::SimHalted { .. })));
+    let text = transcript.text();
+    assert!(text.contains("[0] paused: start of simulation"));
+    assert!(text.contains("counter.q\n"));
+    assert!(text.contains("[100] paused: breakpoint at time 100"));
+    assert!(text.contains("counter.q = 0ha"));
+    assert!(text.contains("[115] paused: rising edge of clock"));
+    assert!(text.contains("counter.q = 0hc"));
+    assert!(text.contains("[125] paused: watch count: 0hc -> 0hd"));
+    assert!(text.contains("paused: simulation halted"));
+    assert!(text.contains("count = 0h14"));
+}
+
+#[test]
+fn test_debugger_conditions_dump_and_quit() {
+    let vcd = vcd_path!("sim_debugger_partial.vcd");
+    let script = format!("dump {}\nq\n", vcd);
+    let transcript = Transcript::default();
+    let mut debugger = Debugger::with_io(Cursor::new(script), transcript.clone())
+        .stop_at_start(false)
+        .break_when("count is 5", |x: &Counter| x.count.val() == 5);
+    let result = counter_sim().run_debug(Box::new(Counter::default()), 10_000, &mut debugger);
+    assert!(matches!(
+        result,
+        Err(SimError::SimHalted { testbench, .. }) if testbench == "debugger"
+    ));
+    let text = transcript.text();
+    assert!(text.contains("[45] paused: breakpoint count is 5"));
+    let trace = std::fs::read_to_string(vcd).unwrap();
+    assert!(trace.contains("#45"));
+    assert!(!trace.contains("#55"));
+}
+
+#[test]
+fn test_debugger_runs_to_completion_without_input() {
+    let mut debugger = Debugger::with_io(Cursor::new(""), std::io::sink()).break_on_halt(false);
+    let result = counter_sim().run_debug(Box::new(Counter::default()), 10_000, &mut debugger);
+    assert!(matches!(result, Err(SimError::SimHalted { .. })));
+    assert!(!debugger.trace().is_empty());
+}
@@ -0,0 +1,103 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+
+fn strobe_sim() -> Simulation<Strobe<32>> {
+    let mut sim = Simulation::new();
+    sim.add_clock(500, |x: &mut Box<Strobe<32>>| x.clock.next = !x.clock.val());
+    sim
+}
+
+fn strobe() -> Box<Strobe<32>> {
+    Box::new(Strobe::new(1_000_000_000, 100_000_000.0))
+}
+
+#[test]
+fn test_watch_with_timeout_reports_timeout() {
+    let mut sim = strobe_sim();
+    sim.add_named_testbench("watcher", |mut sim: Sim<Strobe<32>>| {
+        let x = sim.init()?;
+        let x = sim.watch_with_timeout(|x| x.strobe.val(), 5_000, x)?;
+        sim.done(x)
+    });
+    assert_eq!(
+        sim.run(strobe(), 100_000),
+        Err(SimError::Timeout {
+            time: 5_000,
+            testbench: "watcher".into(),
+            timeout: 5_000
+        })
+    );
+}
+
+#[test]
+fn test_watch_with_timeout_returns_when_condition_met() {
+    let mut sim = strobe_sim();
+    sim.add_testbench(|mut sim: Sim<Strobe<32>>| {
+        let mut x = sim.init()?;
+        x.enable.next = true;
+        let x = sim.watch_with_timeout(|x| x.strobe.val(), 20_000, x)?;
+        sim_assert!(sim, sim.time() < 20_000, x);
+        sim.done(x)
+    });
+    sim.run(strobe(), 100_000).unwrap();
+}
+
+#[test]
+fn test_sim_assert_eq_message_is_reported() {
+    let mut sim = strobe_sim();
+    sim.add_named_testbench("checker", |mut sim: Sim<Strobe<32>>| {
+        let mut x = sim.init()?;
+        x = sim.wait(1_000, x)?;
+        sim_assert_eq!(sim, x.strobe.val(), true, x);
+        sim.done(x)
+    });
+    match sim.run(strobe(), 100_000) {
+        Err(SimError::SimHalted {
+            time,
+            testbench,
+            message,
+        }) => {
+            assert_eq!(time, 1_000);
+            assert_eq!(testbench, "checker");
+            assert_eq!(message, "x.strobe.val() != true,  false != true");
+        }
+        x => panic!("Unexpected result {:?}", x),
+    }
+}
+
+#[test]
+fn test_panic_message_is_captured() {
+    let mut sim = strobe_sim();
+    sim.add_named_testbench("panicker", |mut sim: Sim<Strobe<32>>| {
+        let mut x = sim.init()?;
+        x = sim.wait(1_000, x)?;
+        assert!(x.strobe.val(), "strobe was not set");
+        sim.done(x)
+    });
+    match sim.run(strobe(), 100_000) {
+        Err(SimError::SimPanic {
+            time,
+            testbench,
+            message,
+        }) => {
+            assert_eq!(time, 1_000);
+            assert_eq!(testbench, "panicker");
+            assert_eq!(message, "strobe was not set");
+        }
+        x => panic!("Unexpected result {:?}", x),
+    }
+}
+
+#[test]
+fn test_max_time_reports_time() {
+    let mut sim = strobe_sim();
+    sim.add_testbench(|mut sim: Sim<Strobe<32>>| {
+        let x = sim.init()?;
+        let x = sim.wait(50_000, x)?;
+        sim.done(x)
+    });
+    assert_eq!(
+        sim.run(strobe(), 10_000),
+        Err(SimError::MaxTimeReached { time: 10_000 })
+    );
+}
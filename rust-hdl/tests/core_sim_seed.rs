@@ -0,0 +1,83 @@
+use rand::Rng;
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+use std::sync::{Arc, Mutex};
+
+type Draws = Arc<Mutex<Vec<(String, u32)>>>;
+
+fn draw_randoms(seed: u64) -> Vec<(String, u32)> {
+    let draws: Draws = Default::default();
+    let mut sim = Simulation::new();
+    sim.set_seed(seed);
+    sim.add_clock(5, |x: &mut Box<Strobe<32>>| x.clock.next = !x.clock.val());
+    for _ in 0..2 {
+        let draws = draws.clone();
+        sim.add_testbench(move |mut sim: Sim<Strobe<32>>| {
+            let mut x = sim.init()?;
+            for _ in 0..4 {
+                let delay = sim.rng().gen_range(1..100);
+                x = sim.wait(delay, x)?;
+                let name = sim.name().to_string();
+                draws.lock().unwrap().push((name, delay as u32));
+            }
+            sim.done(x)
+        });
+    }
+    let uut = Strobe::<32>::new(1_000_000, 1000.0);
+    sim.run(Box::new(uut), 10_000).unwrap();
+    let ret = draws.lock().unwrap().clone();
+    ret
+}
+
+#[test]
+fn test_same_seed_gives_same_sequence() {
+    assert_eq!(draw_randoms(42), draw_randoms(42));
+    assert_ne!(draw_randoms(42), draw_randoms(43));
+}
+
+#[test]
+fn test_testbenches_get_different_streams() {
+    let draws = draw_randoms(7);
+    let first = draws
+        .iter()
+        .filter(|x| x.0 == "testbench_1")
+        .map(|x| x.1)
+        .collect::<Vec<_>>();
+    let second = draws
+        .iter()
+        .filter(|x| x.0 == "testbench_2")
+        .map(|x| x.1)
+        .collect::<Vec<_>>();
+    assert_eq!(first.len(), 4);
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_simulation_rng_is_reproducible() {
+    let mut sim: Simulation<Strobe<32>> = Simulation::new();
+    sim.set_seed(1234);
+    assert_eq!(sim.seed(), 1234);
+    let a = sim.rng().gen::<u64>();
+    let b = sim.rng().gen::<u64>();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_run_with_seeds_reports_first_failure() {
+    let mut seen = vec![];
+    let result = run_with_seeds(8, |seed| {
+        seen.push(seed);
+        let mut sim = Simulation::new();
+        sim.set_seed(seed);
+        sim.add_testbench(move |sim: Sim<Strobe<32>>| {
+            let x = sim.init()?;
+            sim_assert!(sim, seed != 3, x);
+            sim.done(x)
+        });
+        sim.run(Box::new(Strobe::new(1_000_000, 1000.0)), 1_000)
+    });
+    if std::env::var("RUST_HDL_SEED").is_err() {
+        assert!(matches!(result, Err(SimError::SimHalted { .. })));
+        assert_eq!(seen, vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,83 @@
+use rust_hdl::core::prelude::*;
+
+#[derive(LogicBlock, Default)]
+struct Saturate {
+    pub a: Signal<In, Bits<8>>,
+    pub b: Signal<In, Bits<8>>,
+    pub sum: Signal<Out, Bits<8>>,
+}
+
+impl Logic for Saturate {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.sum.next = self.a.val() + self.b.val();
+        if self.sum.val() < self.a.val() {
+            self.sum.next = 0xFF.into();
+        }
+    }
+}
+
+fn annotated_verilog() -> String {
+    let mut uut = Saturate::default();
+    uut.connect_all();
+    generate_verilog_with_options(
+        &uut,
+        VerilogOptions {
+            source_locations: true,
+            ..Default::default()
+        },
+    )
+}
+
+// The line (numbered from 1) of this file that contains `text`
+fn rust_line(text: &str) -> u32 {
+    include_str!("core_source_locations.rs")
+        .lines()
+        .position(|x| x.contains(text))
+        .unwrap() as u32
+        + 1
+}
+
+// The line (numbered from 1) of the Verilog that contains `text`
+fn verilog_line(vlog: &str, text: &str) -> usize {
+    vlog.lines().position(|x| x.contains(text)).unwrap() + 1
+}
+
+#[test]
+fn test_statements_are_annotated() {
+    let vlog = annotated_verilog();
+    let expected = format!(
+        "// {}:{}\n        sum = a + b;",
+        file!(),
+        rust_line("self.sum.next = self.a.val() + self.b.val();")
+    );
+    assert!(vlog.contains(&expected));
+}
+
+#[test]
+fn test_verilog_lines_map_to_rust_source() {
+    let vlog = annotated_verilog();
+    let location = source_location(&vlog, verilog_line(&vlog, "sum = 32'hff;")).unwrap();
+    assert_eq!(location.file, file!());
+    assert_eq!(location.line, rust_line("self.sum.next = 0xFF.into();"));
+    // The closing `end` of the if belongs to the last statement inside it
+    let location = source_location(&vlog, verilog_line(&vlog, "if (sum < a)") + 2).unwrap();
+    assert_eq!(location.line, rust_line("self.sum.next = 0xFF.into();"));
+    // Declarations are not part of the update code
+    assert!(source_location(&vlog, verilog_line(&vlog, "input wire  [7:0] a;")).is_none());
+    assert!(source_location(&vlog, 0).is_none());
+    assert!(source_location(&vlog, 10_000).is_none());
+}
+
+#[test]
+fn test_annotations_are_opt_in() {
+    let mut uut = Saturate::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(!vlog.contains(file!()));
+    // Snapshots ignore the annotations
+    assert_eq!(
+        normalize_verilog(&vlog),
+        normalize_verilog(&annotated_verilog())
+    );
+}
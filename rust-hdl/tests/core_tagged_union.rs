@@ -0,0 +1,199 @@
+use rust_hdl::core::prelude::*;
+
+#[derive(Copy, Clone, PartialEq, Debug, LogicState)]
+enum Cmd {
+    Nop,
+    Read { addr: Bits<24> },
+    Write { addr: Bits<24>, data: Bits<16> },
+    Fill(Bits<8>),
+}
+
+#[test]
+fn test_union_packing() {
+    assert_eq!(Cmd::BITS, 2 + 40);
+    assert_eq!(Cmd::default(), Cmd::Nop);
+    let nop: Bits<42> = Cmd::Nop.into();
+    assert_eq!(nop, 0);
+    let write: Bits<42> = Cmd::Write {
+        addr: 0x12_3456.into(),
+        data: 0xBEEF.into(),
+    }
+    .into();
+    assert_eq!(write, 2 | (0x12_3456 << 2) | (0xBEEF << 26));
+    let fill: Bits<42> = Cmd::Fill(0x5A.into()).into();
+    assert_eq!(fill, 3 | (0x5A << 2));
+    assert_eq!(Cmd::get_my_offset_Write_data(), 26);
+    assert_eq!(Cmd::get_my_width_Fill_0(), 8);
+}
+
+#[test]
+fn test_union_vcd_is_decoded() {
+    let read = Cmd::Read { addr: 0x42.into() };
+    match read.vcd() {
+        VCDValue::Composite(x) => {
+            assert_eq!(x.len(), 4);
+            assert_eq!(*x[0], VCDValue::String("Read".into()));
+            assert_eq!(
+                *x[1],
+                VCDValue::Composite(vec![Box::new(Bits::<24>::from(0x42).vcd())])
+            );
+            // The fields of the other variants are unknown
+            assert_eq!(
+                *x[2],
+                VCDValue::Composite(vec![
+                    Box::new(VCDValue::Vector(vec![vcd::Value::X; 24])),
+                    Box::new(VCDValue::Vector(vec![vcd::Value::X; 16])),
+                ])
+            );
+        }
+        x => panic!("Expected a composite value, got {:?}", x),
+    }
+    for cmd in [
+        Cmd::Nop,
+        read,
+        Cmd::Write {
+            addr: 0x100.into(),
+            data: 0xCAFE.into(),
+        },
+        Cmd::Fill(0x3.into()),
+    ] {
+        assert_eq!(Cmd::from_vcd(&cmd.vcd()), Some(cmd));
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Encoder {
+    pub addr: Signal<In, Bits<24>>,
+    pub data: Signal<In, Bits<16>>,
+    pub read: Signal<In, Bit>,
+    pub write: Signal<In, Bit>,
+    pub cmd: Signal<Out, Cmd>,
+}
+
+impl Logic for Encoder {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.cmd.next = Cmd::Nop;
+        if self.write.val() {
+            self.cmd.next = Cmd::Write {
+                addr: self.addr.val(),
+                data: self.data.val(),
+            };
+        } else if self.read.val() {
+            self.cmd.next = Cmd::Read {
+                addr: self.addr.val(),
+            };
+        }
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Decoder {
+    pub cmd: Signal<In, Cmd>,
+    pub addr: Signal<Out, Bits<24>>,
+    pub data: Signal<Out, Bits<16>>,
+    pub write: Signal<Out, Bit>,
+}
+
+impl Logic for Decoder {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.addr.next = 0.into();
+        self.data.next = 0.into();
+        self.write.next = false;
+        match self.cmd.val() {
+            Cmd::Read { addr } => {
+                self.addr.next = addr;
+            }
+            Cmd::Write { addr, data: value } => {
+                self.addr.next = addr;
+                self.data.next = value;
+                self.write.next = true;
+            }
+            Cmd::Fill(x) => {
+                self.data.next = bit_cast::<16, 8>(x);
+            }
+            Cmd::Nop => {}
+        }
+    }
+}
+
+#[derive(LogicBlock, Default)]
+struct Loopback {
+    pub addr: Signal<In, Bits<24>>,
+    pub data: Signal<In, Bits<16>>,
+    pub read: Signal<In, Bit>,
+    pub write: Signal<In, Bit>,
+    pub addr_out: Signal<Out, Bits<24>>,
+    pub data_out: Signal<Out, Bits<16>>,
+    pub write_out: Signal<Out, Bit>,
+    encoder: Encoder,
+    decoder: Decoder,
+}
+
+impl Logic for Loopback {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.encoder.addr.next = self.addr.val();
+        self.encoder.data.next = self.data.val();
+        self.encoder.read.next = self.read.val();
+        self.encoder.write.next = self.write.val();
+        self.decoder.cmd.next = self.encoder.cmd.val();
+        self.addr_out.next = self.decoder.addr.val();
+        self.data_out.next = self.decoder.data.val();
+        self.write_out.next = self.decoder.write.val();
+    }
+}
+
+#[test]
+fn test_union_verilog() {
+    let mut uut = Loopback::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("localparam Cmd$Write = 2;"));
+    assert!(vlog.contains("case (cmd[(64'h0)+:(2)])"));
+    assert!(vlog.contains("addr = cmd[(64'h2)+:(24)];"));
+    assert!(vlog.contains("data = cmd[(64'h1a)+:(16)];"));
+    assert!(vlog.contains("cmd = Cmd$Write;"));
+    assert!(vlog.contains("cmd[(64'h1a)+:(16)] = data;"));
+    yosys_validate("tagged_union", &vlog).unwrap();
+}
+
+#[test]
+fn test_union_simulation() {
+    let mut uut = Loopback::default();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_testbench(move |mut sim: Sim<Loopback>| {
+        let mut x = sim.init()?;
+        x.addr.next = 0xABCDEF.into();
+        x.data.next = 0x1234.into();
+        x.write.next = true;
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(
+            sim,
+            x.encoder.cmd.val(),
+            Cmd::Write {
+                addr: 0xABCDEF.into(),
+                data: 0x1234.into()
+            },
+            x
+        );
+        sim_assert_eq!(sim, x.addr_out.val(), 0xABCDEF, x);
+        sim_assert_eq!(sim, x.data_out.val(), 0x1234, x);
+        sim_assert!(sim, x.write_out.val(), x);
+        x.write.next = false;
+        x.read.next = true;
+        x = sim.wait(10, x)?;
+        sim_assert_eq!(sim, x.data_out.val(), 0, x);
+        sim_assert!(sim, !x.write_out.val(), x);
+        sim.done(x)
+    });
+    let mut vcd = vec![];
+    sim.run_traced(Box::new(uut), 100, &mut vcd).unwrap();
+    // The command is shown decoded in the trace
+    let vcd = String::from_utf8(vcd).unwrap();
+    assert!(vcd.contains("cmd$tag"));
+    assert!(vcd.contains("cmd$Write$data"));
+    assert!(vcd.contains("sWrite"));
+}
@@ -0,0 +1,79 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::docs::vcd2svg::display_metrics::DisplayMetrics;
+use rust_hdl::docs::vcd2svg::signal_display::*;
+use rust_hdl::docs::vcd2svg::vcd_to_svg_with_options;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock, Default)]
+struct DownCounter {
+    pub clock: Signal<In, Clock>,
+    pub count: Signal<Out, Signed<8>>,
+    counter: DFF<Bits<8>>,
+}
+
+impl Logic for DownCounter {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, counter);
+        self.counter.d.next = self.counter.q.val() - 1;
+        self.count.next = signed_cast(self.counter.q.val());
+    }
+}
+
+#[test]
+fn test_vcd_to_svg_with_formats_and_annotations() {
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<DownCounter>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|mut sim: Sim<DownCounter>| {
+        let mut x = sim.init()?;
+        wait_clock_cycles!(sim, clock, x, 8);
+        sim.done(x)
+    });
+    let vcd = vcd_path!("vcd2svg_options.vcd");
+    let svg = vcd_path!("vcd2svg_options.svg");
+    sim.run_to_file(Box::new(DownCounter::default()), 1_000, &vcd)
+        .unwrap();
+    let mut metrics = DisplayMetrics::default();
+    metrics.min_time = 0;
+    metrics.max_time = 80;
+    metrics.markers.push(TimeMarker::new(20, "first_edge"));
+    metrics
+        .highlights
+        .push(TimeHighlight::new(30, 50, "interesting"));
+    vcd_to_svg_with_options(
+        &vcd,
+        &svg,
+        &[
+            SignalDisplay::new("uut.clock").alias("clk"),
+            SignalDisplay::new("uut.count").format(ValueFormat::Signed),
+            SignalDisplay::new("uut.counter.q").format(ValueFormat::Binary),
+        ],
+        &metrics,
+    )
+    .unwrap();
+    let svg = std::fs::read_to_string(svg).unwrap();
+    let has_text = |text: &str| svg.lines().any(|line| line.trim() == text);
+    assert!(has_text("clk"));
+    assert!(has_text("uut.count"));
+    assert!(has_text("-1"));
+    assert!(has_text("0b11111111"));
+    assert!(has_text("first_edge"));
+    assert!(has_text("interesting"));
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, LogicState)]
+enum Phase {
+    Setup,
+    Hold,
+    Done,
+}
+
+#[test]
+fn test_enum_names_from_logic_state() {
+    let format = ValueFormat::enum_names::<Phase>();
+    assert_eq!(
+        format,
+        ValueFormat::Enum(vec!["Setup".into(), "Hold".into(), "Done".into()])
+    );
+    assert_eq!(format.format(&2.into(), 2), "Done");
+}
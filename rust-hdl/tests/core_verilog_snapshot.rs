@@ -0,0 +1,59 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::widgets::prelude::*;
+use std::path::PathBuf;
+
+#[test]
+fn test_strobe_verilog_snapshot() {
+    let mut uut = Strobe::<32>::new(1_000_000, 1000.0);
+    uut.connect_all();
+    verilog_snapshot!(uut, "strobe");
+}
+
+#[test]
+fn test_snapshot_mismatch_reports_diff() {
+    let dir = PathBuf::from(target_path!("verilog_snapshot"));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut uut = Strobe::<16>::new(1_000_000, 1000.0);
+    uut.connect_all();
+    let verilog = generate_verilog(&uut);
+    assert!(matches!(
+        check_verilog_snapshot(&dir, "strobe", &verilog, false),
+        Err(SnapshotError::Missing(_))
+    ));
+    assert_eq!(
+        check_verilog_snapshot(&dir, "strobe", &verilog, true),
+        Ok(SnapshotStatus::Created)
+    );
+    // Whitespace differences are ignored
+    let padded = format!("\n{}\n\n", verilog.replace("\n", "  \r\n"));
+    assert_eq!(
+        check_verilog_snapshot(&dir, "strobe", &padded, false),
+        Ok(SnapshotStatus::Matched)
+    );
+    let mut uut = Strobe::<16>::new(1_000_000, 500.0);
+    uut.connect_all();
+    let changed = generate_verilog(&uut);
+    match check_verilog_snapshot(&dir, "strobe", &changed, false) {
+        Err(SnapshotError::Mismatch { diff, .. }) => {
+            println!("{}", diff);
+            assert!(diff.contains("@@ -"));
+            assert!(diff
+                .lines()
+                .any(|x| x.starts_with("-") && x.contains("16'h3e8")));
+            assert!(diff
+                .lines()
+                .any(|x| x.starts_with("+") && x.contains("16'h7d0")));
+        }
+        x => panic!("Expected a mismatch, got {:?}", x),
+    }
+    assert!(dir.join("strobe.v.new").exists());
+    assert_eq!(
+        check_verilog_snapshot(&dir, "strobe", &changed, true),
+        Ok(SnapshotStatus::Updated)
+    );
+    assert!(!dir.join("strobe.v.new").exists());
+    assert_eq!(
+        check_verilog_snapshot(&dir, "strobe", &changed, false),
+        Ok(SnapshotStatus::Matched)
+    );
+}
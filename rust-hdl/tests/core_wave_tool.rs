@@ -0,0 +1,125 @@
+use rust_hdl::core::prelude::*;
+use rust_hdl::docs::vcd2svg::wave_tool::*;
+use rust_hdl::widgets::prelude::*;
+
+#[derive(LogicBlock, Default)]
+struct Pair {
+    pub clock: Signal<In, Clock>,
+    pub left: DFF<Bits<4>>,
+    pub right: DFF<Bits<4>>,
+}
+
+impl Logic for Pair {
+    #[hdl_gen]
+    fn update(&mut self) {
+        dff_setup!(self, clock, left, right);
+        self.left.d.next = self.left.q.val() + 1;
+        self.right.d.next = self.right.q.val() + 2;
+    }
+}
+
+fn make_vcd(name: &str) -> String {
+    let mut sim = Simulation::new();
+    sim.add_clock(5, |x: &mut Box<Pair>| x.clock.next = !x.clock.val());
+    sim.add_testbench(|mut sim: Sim<Pair>| {
+        let mut x = sim.init()?;
+        wait_clock_cycles!(sim, clock, x, 8);
+        sim.done(x)
+    });
+    let vcd = vcd_path!(name);
+    sim.run_to_file(Box::new(Pair::default()), 1_000, &vcd)
+        .unwrap();
+    vcd
+}
+
+fn args(x: &[&str]) -> Vec<String> {
+    x.iter().map(|x| x.to_string()).collect()
+}
+
+#[test]
+fn test_wave_tool_resolves_globs_against_header() {
+    let vcd = make_vcd("wave_tool_globs.vcd");
+    let names = vcd_signal_names(&vcd).unwrap();
+    assert!(names.contains(&"uut.left.q".to_string()));
+    assert_eq!(
+        match_signals(&names, &["uut.*.q"]),
+        vec!["uut.left.q", "uut.right.q"]
+    );
+    let options = parse_args(&args(&[&vcd, "-s", "uut.*.q=dec", "--to", "80"])).unwrap();
+    let text = render(&options).unwrap();
+    assert!(text.contains("uut.left.q"));
+    assert!(text.contains("uut.right.q"));
+    assert!(!text.contains("uut.clock"));
+    let options = parse_args(&args(&[&vcd, "-s", "uut.nothing"])).unwrap();
+    assert!(render(&options).is_err());
+}
+
+#[test]
+fn test_wave_tool_writes_svg_and_html() {
+    let vcd = make_vcd("wave_tool_output.vcd");
+    let svg = vcd_path!("wave_tool_output.svg");
+    let html = vcd_path!("wave_tool_output.html");
+    run(&args(&[
+        &vcd,
+        "-s",
+        "uut.clock",
+        "--marker",
+        "20=edge",
+        "-o",
+        &svg,
+    ]))
+    .unwrap();
+    let svg = std::fs::read_to_string(svg).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.lines().any(|l| l.trim() == "edge"));
+    run(&args(&[&vcd, "--style", "scansion", "-o", &html])).unwrap();
+    let html = std::fs::read_to_string(html).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("viewBox"));
+    assert!(html.contains("uut.right.q"));
+}
+
+#[test]
+fn test_wave_tool_text_uses_signal_format() {
+    let vcd = make_vcd("wave_tool_format.vcd");
+    let options = parse_args(&args(&[&vcd, "-s", "uut.right.q=bin", "--to", "40"])).unwrap();
+    let text = render(&options).unwrap();
+    assert!(text.contains("0b0010"));
+    let options = parse_args(&args(&[&vcd, "-s", "uut.right.q", "--to", "40"])).unwrap();
+    let text = render(&options).unwrap();
+    assert!(text.contains("0h2"));
+    assert!(!text.contains("0b0010"));
+}
+
+#[test]
+fn test_wave_tool_times_follow_the_timescale() {
+    let vcd = vcd_path!("wave_tool_timescale.vcd");
+    std::fs::write(
+        &vcd,
+        "$timescale 1ns $end
+$scope module top $end
+$var wire 4 ! count $end
+$upscope $end
+$enddefinitions $end
+#0
+b0 !
+#10
+b1 !
+#20
+b10 !
+#30
+b11 !
+",
+    )
+    .unwrap();
+    let text = |from: &str, to: &str| {
+        let options = parse_args(&args(&[&vcd, "--from", from, "--to", to])).unwrap();
+        render(&options).unwrap()
+    };
+    // Bare numbers are in nanoseconds, like the timestamps in the file
+    assert_eq!(text("10", "30"), text("10ns", "30ns"));
+    assert_eq!(text("10", "30"), text("10000ps", "0.03us"));
+    // Both of these round to 0ns, which leaves nothing to show
+    let options = parse_args(&args(&[&vcd, "--from", "10ps", "--to", "30ps"])).unwrap();
+    assert!(render(&options).is_err());
+}
@@ -0,0 +1,105 @@
+use rust_hdl::core::prelude::*;
+
+#[test]
+fn test_wide_literals() {
+    let x: Bits<128> = 0xFFFF_0000_FFFF_0000_FFFF_0000_FFFF_0000_u128.to_bits();
+    let y: Bits<128> = hex_bits("ffff0000_ffff0000_ffff0000_ffff0000");
+    assert_eq!(x, y);
+    assert_eq!(x.get_bits::<16>(112), bits(0xFFFF));
+    let z: Bits<200> = hex_bits("0x80_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0001");
+    assert!(z.get_bit(199));
+    assert!(z.get_bit(0));
+    assert_eq!(
+        z.verilog().to_string(),
+        format!("200'h8{}1", "0".repeat(48))
+    );
+    let big = num_bigint::BigUint::from(1_u32) << 150;
+    assert_eq!(
+        VerilogLiteral::from(big).to_string(),
+        format!("151'h4{}", "0".repeat(37))
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_wide_literal_too_wide() {
+    let _x: Bits<68> = hex_bits("1_0000_0000_0000_0000_0");
+}
+
+#[derive(LogicBlock)]
+struct WideMask {
+    pub x: Signal<In, Bits<128>>,
+    pub masked: Signal<Out, Bits<128>>,
+    pub flipped: Signal<Out, Bits<128>>,
+    pub magic: Signal<Out, Bit>,
+    pub wide: Signal<Out, Bits<200>>,
+    mask: Constant<Bits<128>>,
+}
+
+impl Default for WideMask {
+    fn default() -> Self {
+        Self {
+            x: Default::default(),
+            masked: Default::default(),
+            flipped: Default::default(),
+            magic: Default::default(),
+            wide: Default::default(),
+            mask: Constant::new(hex_bits("ffff_ffff_0000_0000_0000_0000_ffff_ffff")),
+        }
+    }
+}
+
+impl Logic for WideMask {
+    #[hdl_gen]
+    fn update(&mut self) {
+        self.masked.next = self.x.val() & self.mask.val();
+        self.flipped.next = self.x.val() ^ 0x8000_0000_0000_0000_0000_0000_0000_0001_u128.to_bits();
+        self.magic.next =
+            self.x.val() == hex_bits::<128>("dead_beef_cafe_babe_0123_4567_89ab_cdef");
+        self.wide.next = bit_cast::<200, 128>(self.x.val())
+            | hex_bits::<200>("ab_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000");
+    }
+}
+
+#[test]
+fn test_wide_literals_verilog() {
+    let mut uut = WideMask::default();
+    uut.connect_all();
+    let vlog = generate_verilog(&uut);
+    assert!(vlog.contains("mask = 128'hffffffff0000000000000000ffffffff;"));
+    assert!(vlog.contains("flipped = x ^ 128'h80000000000000000000000000000001;"));
+    assert!(vlog.contains("magic = x == 128'hdeadbeefcafebabe0123456789abcdef;"));
+    assert!(vlog.contains(&format!("200'hab{};", "0".repeat(48))));
+    yosys_validate("wide_literals", &vlog).unwrap();
+}
+
+#[test]
+fn test_wide_literals_simulation() {
+    let mut uut = WideMask::default();
+    uut.connect_all();
+    let mut sim = Simulation::new();
+    sim.add_testbench(move |mut sim: Sim<WideMask>| {
+        let mut x = sim.init()?;
+        x.x.next = hex_bits("dead_beef_cafe_babe_0123_4567_89ab_cdef");
+        x = sim.wait(10, x)?;
+        sim_assert!(sim, x.magic.val(), x);
+        sim_assert_eq!(
+            sim,
+            x.masked.val(),
+            hex_bits::<128>("dead_beef_0000_0000_0000_0000_89ab_cdef"),
+            x
+        );
+        sim_assert_eq!(
+            sim,
+            x.flipped.val(),
+            hex_bits::<128>("5ead_beef_cafe_babe_0123_4567_89ab_cdee"),
+            x
+        );
+        sim_assert_eq!(sim, x.wide.val().get_bits::<8>(192), 0xAB, x);
+        x.x.next = 0_u128.to_bits();
+        x = sim.wait(10, x)?;
+        sim_assert!(sim, !x.magic.val(), x);
+        sim.done(x)
+    });
+    sim.run(Box::new(uut), 100).unwrap();
+}
@@ -0,0 +1,54 @@
+module top(enable,strobe,clock);
+
+    // Module arguments
+    input wire  enable;
+    output reg  strobe;
+    input wire  clock;
+
+    // Constant declarations
+    localparam  threshold = 32'h3e8;
+
+    // Stub signals
+    reg  [31:0] counter$d;
+    wire  [31:0] counter$q;
+    reg  counter$clock;
+
+    // Sub module instances
+    top$counter counter(
+        .d(counter$d),
+        .q(counter$q),
+        .clock(counter$clock)
+    );
+
+    // Update code
+    always @(*) begin
+        counter$clock = clock;
+        counter$d = counter$q;
+        if (enable) begin
+            counter$d = counter$q + 32'h1;
+        end
+        strobe = enable & (counter$q == threshold);
+        if (strobe) begin
+            counter$d = 32'h1;
+        end
+    end
+
+endmodule // top
+
+module top$counter(d,q,clock);
+
+    // Module arguments
+    input wire  [31:0] d;
+    output reg  [31:0] q;
+    input wire  clock;
+
+    // Update code (custom)
+    initial begin
+       q = 32'h0;
+    end
+
+    always @(posedge clock) begin
+       q <= d;
+    end
+
+endmodule // top$counter